@@ -10,7 +10,7 @@ fn unwrap_nested_result<T, X, Y>(input: Result<Result<T, X>, Y>, default: T) ->
 fn main() {
     let git_hash = unwrap_nested_result(
         Command::new("git")
-            .args(&["rev-parse", "HEAD"])
+            .args(["rev-parse", "HEAD"])
             .output()
             .map(|output| String::from_utf8(output.stdout)),
         "UNKNOWN_GIT_COMMIT".to_string(),
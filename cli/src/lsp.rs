@@ -0,0 +1,416 @@
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use lsp_server::{Connection, ExtractError, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, GotoDefinition, HoverRequest, PrepareRenameRequest, References, Rename,
+};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionResponse, Diagnostic,
+    DiagnosticSeverity, GotoDefinitionResponse, Hover, HoverContents, HoverProviderCapability,
+    InitializeParams, Location, MarkupContent, MarkupKind, OneOf, Position, PrepareRenameResponse,
+    PublishDiagnosticsParams, Range, RenameOptions, ServerCapabilities, TextDocumentPositionParams,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url, WorkspaceEdit,
+};
+use tsql::ide::{self, CompletionKind, Workspace};
+use tsql::naming::NamingConvention;
+use tsql::syntax::Span;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Runs the language server on stdin and stdout until the client shuts it down.
+pub(crate) fn run(naming: NamingConvention) -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        rename_provider: Some(OneOf::Right(RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: Default::default(),
+        })),
+        ..Default::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+
+    main_loop(connection, params, naming)?;
+    io_threads.join()?;
+
+    Ok(())
+}
+
+/// Handles the messages until the client shuts the server down, the connection has to be
+/// dropped before the io threads can be joined.
+fn main_loop(
+    connection: Connection,
+    params: InitializeParams,
+    naming: NamingConvention,
+) -> Result<()> {
+    let mut server = Server {
+        connection: &connection,
+        workspace: Workspace::new(naming),
+        open: BTreeSet::new(),
+    };
+
+    // every file of the workspace, so that references and renames work across files
+    #[allow(deprecated)]
+    let roots = params
+        .workspace_folders
+        .unwrap_or_default()
+        .into_iter()
+        .map(|folder| folder.uri)
+        .chain(params.root_uri)
+        .filter_map(|uri| uri.to_file_path().ok());
+    for root in roots {
+        if let Err(err) = server.workspace.add_dir(&root) {
+            eprintln!("Can't read the workspace {}: {}", root.display(), err);
+        }
+    }
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+
+                server.request(request)?;
+            }
+            Message::Notification(notification) => server.notification(notification)?,
+            Message::Response(_) => (),
+        }
+    }
+
+    Ok(())
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    workspace: Workspace,
+    /// files which are open in the editor, they get the diagnostics
+    open: BTreeSet<PathBuf>,
+}
+
+impl<'a> Server<'a> {
+    fn request(&mut self, request: Request) -> Result<()> {
+        let request = match cast::<GotoDefinition>(request) {
+            Ok((id, params)) => {
+                let params = params.text_document_position_params;
+                let result = self
+                    .locate(&params)
+                    .and_then(|(path, offset)| self.workspace.definition(&path, offset))
+                    .and_then(|location| self.location(&location))
+                    .map(GotoDefinitionResponse::Scalar);
+
+                return self.respond(id, result);
+            }
+            Err(request) => request,
+        };
+
+        let request = match cast::<References>(request) {
+            Ok((id, params)) => {
+                let result = self
+                    .locate(&params.text_document_position)
+                    .map(|(path, offset)| {
+                        self.workspace
+                            .references(&path, offset, params.context.include_declaration)
+                            .iter()
+                            .filter_map(|location| self.location(location))
+                            .collect::<Vec<_>>()
+                    });
+
+                return self.respond(id, result);
+            }
+            Err(request) => request,
+        };
+
+        let request = match cast::<HoverRequest>(request) {
+            Ok((id, params)) => {
+                let result = self
+                    .locate(&params.text_document_position_params)
+                    .and_then(|(path, offset)| self.workspace.hover(&path, offset))
+                    .map(|value| Hover {
+                        contents: HoverContents::Markup(MarkupContent {
+                            kind: MarkupKind::Markdown,
+                            value,
+                        }),
+                        range: None,
+                    });
+
+                return self.respond(id, result);
+            }
+            Err(request) => request,
+        };
+
+        let request = match cast::<Completion>(request) {
+            Ok((id, _)) => {
+                let items = self
+                    .workspace
+                    .completions()
+                    .into_iter()
+                    .map(|completion| CompletionItem {
+                        label: completion.label,
+                        kind: Some(match completion.kind {
+                            CompletionKind::Type => CompletionItemKind::TYPE_PARAMETER,
+                            CompletionKind::Table => CompletionItemKind::STRUCT,
+                        }),
+                        detail: Some(completion.detail),
+                        ..Default::default()
+                    })
+                    .collect();
+
+                return self.respond(id, Some(CompletionResponse::Array(items)));
+            }
+            Err(request) => request,
+        };
+
+        let request = match cast::<PrepareRenameRequest>(request) {
+            Ok((id, params)) => {
+                let result = self.locate(&params).and_then(|(path, offset)| {
+                    let span = self.workspace.rename_span(&path, offset)?;
+                    let content = self.workspace.file(&path)?;
+
+                    Some(PrepareRenameResponse::Range(range(content, span)))
+                });
+
+                return self.respond(id, result);
+            }
+            Err(request) => request,
+        };
+
+        let request = match cast::<Rename>(request) {
+            Ok((id, params)) => {
+                let Some((path, offset)) = self.locate(&params.text_document_position) else {
+                    return self.respond(id, None::<WorkspaceEdit>);
+                };
+
+                let edits = match self.workspace.rename(&path, offset, &params.new_name) {
+                    Ok(edits) => edits,
+                    Err(err) => {
+                        let response = Response::new_err(
+                            id,
+                            lsp_server::ErrorCode::InvalidParams as i32,
+                            err.to_string(),
+                        );
+                        self.connection.sender.send(Message::Response(response))?;
+
+                        return Ok(());
+                    }
+                };
+
+                let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+                for edit in edits {
+                    let (Some(content), Ok(uri)) = (
+                        self.workspace.file(&edit.path),
+                        Url::from_file_path(&edit.path),
+                    ) else {
+                        continue;
+                    };
+
+                    changes.entry(uri).or_default().push(TextEdit {
+                        range: range(content, edit.span),
+                        new_text: edit.text,
+                    });
+                }
+
+                return self.respond(
+                    id,
+                    Some(WorkspaceEdit {
+                        changes: Some(changes),
+                        ..Default::default()
+                    }),
+                );
+            }
+            Err(request) => request,
+        };
+
+        let response = Response::new_err(
+            request.id,
+            lsp_server::ErrorCode::MethodNotFound as i32,
+            format!("Unsupported request {:?}", request.method),
+        );
+        self.connection.sender.send(Message::Response(response))?;
+
+        Ok(())
+    }
+
+    fn notification(&mut self, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = notification
+                    .extract::<lsp_types::DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)?;
+
+                if let Ok(path) = params.text_document.uri.to_file_path() {
+                    self.workspace.set_file(&path, params.text_document.text);
+                    self.open.insert(path);
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = notification.extract::<lsp_types::DidChangeTextDocumentParams>(
+                    DidChangeTextDocument::METHOD,
+                )?;
+
+                // the content is always sent in full, see the capabilities
+                if let (Ok(path), Some(change)) = (
+                    params.text_document.uri.to_file_path(),
+                    params.content_changes.into_iter().last(),
+                ) {
+                    self.workspace.set_file(&path, change.text);
+                }
+            }
+            DidSaveTextDocument::METHOD => (),
+            DidCloseTextDocument::METHOD => {
+                let params = notification.extract::<lsp_types::DidCloseTextDocumentParams>(
+                    DidCloseTextDocument::METHOD,
+                )?;
+
+                if let Ok(path) = params.text_document.uri.to_file_path() {
+                    // unsaved changes are discarded
+                    match read_to_string(&path) {
+                        Ok(content) => self.workspace.set_file(&path, content),
+                        Err(_) => self.workspace.remove_file(&path),
+                    }
+
+                    self.open.remove(&path);
+                    self.publish(&path, Vec::new())?;
+                }
+            }
+            _ => return Ok(()),
+        }
+
+        // a change can fix or break every file which imports the changed one
+        for path in self.open.clone() {
+            let diagnostics = self.diagnostics(&path);
+            self.publish(&path, diagnostics)?;
+        }
+
+        Ok(())
+    }
+
+    fn diagnostics(&self, path: &Path) -> Vec<Diagnostic> {
+        let Some(content) = self.workspace.file(path) else {
+            return Vec::new();
+        };
+
+        self.workspace
+            .diagnostics(path)
+            .into_iter()
+            .map(|diagnostic| Diagnostic {
+                range: range(content, diagnostic.span),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("tsql".to_string()),
+                message: diagnostic.message,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn publish(&self, path: &Path, diagnostics: Vec<Diagnostic>) -> Result<()> {
+        let Ok(uri) = Url::from_file_path(path) else {
+            return Ok(());
+        };
+
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection
+            .sender
+            .send(Message::Notification(notification))?;
+
+        Ok(())
+    }
+
+    fn respond<T: serde::Serialize>(&self, id: RequestId, result: Option<T>) -> Result<()> {
+        let response = Response::new_ok(id, result);
+        self.connection.sender.send(Message::Response(response))?;
+
+        Ok(())
+    }
+
+    /// Path and byte offset of a position inside of a document.
+    fn locate(&self, params: &TextDocumentPositionParams) -> Option<(PathBuf, usize)> {
+        let path = params.text_document.uri.to_file_path().ok()?;
+        let content = self.workspace.file(&path)?;
+        let offset = offset(content, params.position);
+
+        Some((path, offset))
+    }
+
+    fn location(&self, location: &ide::Location) -> Option<Location> {
+        let content = self.workspace.file(&location.path)?;
+
+        Some(Location {
+            uri: Url::from_file_path(&location.path).ok()?,
+            range: range(content, location.span),
+        })
+    }
+}
+
+fn cast<R>(request: Request) -> std::result::Result<(RequestId, R::Params), Request>
+where
+    R: lsp_types::request::Request,
+{
+    let id = request.id.clone();
+
+    match request.extract(R::METHOD) {
+        Ok(value) => Ok(value),
+        Err(ExtractError::MethodMismatch(request)) => Err(request),
+        // invalid parameters are answered like an unsupported request
+        Err(ExtractError::JsonError { method, error }) => Err(Request {
+            id,
+            method: format!("{} ({})", method, error),
+            params: serde_json::Value::Null,
+        }),
+    }
+}
+
+/// Byte offset of an lsp position, whose character is counted in utf-16 code units.
+fn offset(content: &str, position: Position) -> usize {
+    let line_start = content
+        .split_inclusive('\n')
+        .take(position.line as usize)
+        .map(str::len)
+        .sum::<usize>();
+
+    let mut units = 0;
+    for (index, c) in content[line_start..].char_indices() {
+        if units >= position.character as usize || c == '\n' {
+            return line_start + index;
+        }
+
+        units += c.len_utf16();
+    }
+
+    content.len()
+}
+
+/// Lsp position of a byte offset, see [`offset`].
+fn position(content: &str, offset: usize) -> Position {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+fn range(content: &str, span: Span) -> Range {
+    Range {
+        start: position(content, span.start),
+        end: position(content, span.end),
+    }
+}
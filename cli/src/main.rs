@@ -1,24 +1,206 @@
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::fs::{self, File};
+use std::io::{stdout, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::str::FromStr;
 
-use tsql::{parse_file, TransformSQL};
+use anyhow::{anyhow, Result};
+use tsql::codegen::rust::{self, RustOptions};
+use tsql::codegen::typescript::{self, DateType, TypeScriptOptions};
+use tsql::ddl::parse_sql;
+use tsql::dialect::Dialect;
+use tsql::diff::diff;
+use tsql::formatter::format_str;
+use tsql::graph::{write_graph, GraphFormat};
+use tsql::json_schema::json_schema;
+use tsql::migrations::MigrationDir;
+use tsql::naming::{NamingConvention, NamingStrategy};
+use tsql::printer::print_tables_with_naming;
+use tsql::sqlite::read_database;
+use tsql::types::TableCollection;
+use tsql::{
+    parse_dir_with_naming, parse_file_with_naming, transform_drop_tables, transform_tables,
+    DropOptions,
+};
+
+mod lsp;
 
 const HELP: &str = "\
 tsql
 
 USAGE:
-    tsql [INPUT] [OUTPUT]
+    tsql [INPUT] [OUTPUT] [--emit FORMAT]
+    tsql diff [OLD] [NEW] [OUTPUT] [--check]
+    tsql migrate new [NAME] [INPUT]
+    tsql drop [INPUT] [OUTPUT]
+    tsql import [SQL | DATABASE] [-o OUTPUT]
+    tsql fmt [INPUT] [--check]
+    tsql codegen rust [INPUT] [OUTPUT] [--derive LIST]
+    tsql codegen typescript [INPUT] [OUTPUT] [--dates TYPE] [--zod]
+    tsql graph [INPUT] [OUTPUT] [--format FORMAT]
+    tsql lsp
+
+    INPUT can be a file or a directory, in which case every .tsql file inside of it is used.
+    The resolved tables are written to OUTPUT as CREATE TABLE statements, as json with
+    --emit json or as JSON Schema with --emit json-schema. OLD and NEW can also be a sqlite
+    database (.db, .sqlite or .sqlite3), which is read with its current schema
+
+    diff writes the ALTER TABLE statements which migrate OLD to NEW, to stdout if OUTPUT is
+    missing. Renames are marked with @renamed_from(old_name) in NEW. With --check it exits
+    with an error if there is any difference, e.g. `tsql diff app.db schema.tsql --check`
+    detects a database which has drifted from the schema
+
+    migrate new compares INPUT with the snapshot of the last migration and writes the next
+    migration NNNN_NAME with an up.sql, a down.sql and a new snapshot
+
+    drop writes the DROP TABLE statements which remove INPUT from the database, to stdout if
+    OUTPUT is missing
+
+    import converts the CREATE TABLE statements of an sql file or the tables of a sqlite
+    database into tsql, to stdout if OUTPUT is missing. Everything which can't be expressed in
+    tsql is reported as a warning
+
+    fmt formats INPUT in place, with --check it only lists the files which aren't formatted
+    and fails if there are any
+
+    codegen rust writes a struct for the rows of every table, to stdout if OUTPUT is missing.
+    codegen typescript writes an interface for them instead, with --zod also a zod schema
+
+    graph draws the tables with their columns and an edge for every foreign key, to stdout if
+    OUTPUT is missing
+
+    lsp runs the language server on stdin and stdout, with diagnostics, go to definition,
+    references, hover, completion and rename of tables and fields
 
 FLAGS:
   -h, --help            Prints help information
+  --check               diff: Fails if OLD and NEW are different
+                        fmt: Fails if INPUT isn't formatted instead of formatting it
+  --zod                 codegen typescript: Also writes a zod schema for every table
+  --if-exists           drop: Uses DROP TABLE IF EXISTS
+  --cascade             drop: Also drops the objects which depend on a table, not supported
+                        by sqlite
+  --drop-schemas        drop: Also drops the empty schemas of the tables, not supported by
+                        mysql
+
+OPTIONS:
+  --naming STRATEGY     Naming of generated foreign key columns: snake_case (default),
+                        camel_case, table_pk or a template like \"{field}_{pk}\"
+  --pk-naming TEMPLATE  Name of primary key constraints, default \"pk_{table}\"
+  --fk-naming TEMPLATE  Name of foreign key constraints, default \"fk_{table}_{field}\"
+  --unique-naming TEMPLATE
+                        Name of unique constraints, default \"uq_{table}_{columns}\"
+  --check-naming TEMPLATE
+                        Name of check constraints, default \"ck_{table}_{index}\"
+  -o, --output FILE     import: File the tsql is written to
+  --migrations DIR      Directory of the migrations, default \"migrations\"
+  --dialect DIALECT     Validates and writes the output for generic (default), postgres, mysql
+                        or sqlite
+  --emit FORMAT         Output of the tables: sql (default), json or json-schema
+  --derive LIST         codegen rust: Derive macros added to Debug, Clone and PartialEq,
+                        separated by commas, serde and sqlx are short for their derives
+  --dates TYPE          codegen typescript: Type of dates, string (default) or date
+  --format FORMAT       graph: mermaid (default) or dot
 ";
 
+#[derive(Debug)]
+enum Command {
+    Create {
+        tsql_path: PathBuf,
+        out_path: PathBuf,
+        emit: Emit,
+    },
+    Diff {
+        old_path: PathBuf,
+        new_path: PathBuf,
+        out_path: Option<PathBuf>,
+        check: bool,
+    },
+    Drop {
+        tsql_path: PathBuf,
+        out_path: Option<PathBuf>,
+        options: DropOptions,
+    },
+    MigrateNew {
+        name: String,
+        tsql_path: PathBuf,
+        migrations: PathBuf,
+    },
+    Import {
+        sql_path: PathBuf,
+        out_path: Option<PathBuf>,
+    },
+    Fmt {
+        tsql_path: PathBuf,
+        check: bool,
+    },
+    Codegen {
+        language: Language,
+        tsql_path: PathBuf,
+        out_path: Option<PathBuf>,
+        derives: Vec<String>,
+        typescript: TypeScriptOptions,
+    },
+    Graph {
+        tsql_path: PathBuf,
+        out_path: Option<PathBuf>,
+        format: GraphFormat,
+    },
+    Lsp,
+}
+
+/// Format of the resolved tables written by `tsql [INPUT] [OUTPUT]`.
+#[derive(Debug, Clone, Copy, Default)]
+enum Emit {
+    #[default]
+    Sql,
+    Json,
+    JsonSchema,
+}
+
+impl FromStr for Emit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sql" => Ok(Emit::Sql),
+            "json" => Ok(Emit::Json),
+            "json-schema" => Ok(Emit::JsonSchema),
+            _ => Err(format!(
+                "unknown format {:?}, expected sql, json or json-schema",
+                s
+            )),
+        }
+    }
+}
+
+/// Language of `tsql codegen`.
+#[derive(Debug, Clone, Copy)]
+enum Language {
+    Rust,
+    TypeScript,
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rust" => Ok(Language::Rust),
+            "typescript" | "ts" => Ok(Language::TypeScript),
+            _ => Err(format!(
+                "unknown language {:?}, expected rust or typescript",
+                s
+            )),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct AppArgs {
-    tsql_path: PathBuf,
-    out_path: PathBuf,
+    command: Command,
+    naming: NamingConvention,
+    dialect: Dialect,
 }
 
 fn main() {
@@ -30,29 +212,375 @@ fn main() {
         }
     };
 
-    let tables = parse_file(&args.tsql_path).unwrap();
+    let result = match args.command {
+        Command::Create {
+            tsql_path,
+            out_path,
+            emit,
+        } => create(&tsql_path, &out_path, emit, args.naming, args.dialect),
+        Command::Diff {
+            old_path,
+            new_path,
+            out_path,
+            check,
+        } => migrate(
+            &old_path,
+            &new_path,
+            out_path.as_deref(),
+            check,
+            args.naming,
+            args.dialect,
+        ),
+        Command::Drop {
+            tsql_path,
+            out_path,
+            options,
+        } => drop_tables(
+            &tsql_path,
+            out_path.as_deref(),
+            options,
+            args.naming,
+            args.dialect,
+        ),
+        Command::MigrateNew {
+            name,
+            tsql_path,
+            migrations,
+        } => migrate_new(&name, &tsql_path, &migrations, args.naming, args.dialect),
+        Command::Import { sql_path, out_path } => {
+            import(&sql_path, out_path.as_deref(), &args.naming)
+        }
+        Command::Fmt { tsql_path, check } => fmt(&tsql_path, check),
+        Command::Codegen {
+            language,
+            tsql_path,
+            out_path,
+            derives,
+            typescript,
+        } => codegen(
+            language,
+            &tsql_path,
+            out_path.as_deref(),
+            &derives,
+            &typescript,
+            args.naming,
+            args.dialect,
+        ),
+        Command::Graph {
+            tsql_path,
+            out_path,
+            format,
+        } => graph(
+            &tsql_path,
+            out_path.as_deref(),
+            format,
+            args.naming,
+            args.dialect,
+        ),
+        Command::Lsp => {
+            if let Err(err) = lsp::run(args.naming) {
+                eprintln!("Error: {}.", err);
+                exit(1);
+            }
+            Ok(())
+        }
+    };
 
-    let mut file = BufWriter::new(File::create(&args.out_path).unwrap());
+    // the errors of the library start with `Error: ` already
+    if let Err(err) = result {
+        eprintln!("{:#}", err);
+        exit(1);
+    }
+}
 
+fn parse(path: &Path, naming: NamingConvention, dialect: Dialect) -> Result<TableCollection> {
+    let tables = if is_database(path) {
+        let (tables, warnings) = read_database(path)?;
+        print_warnings(warnings);
+        tables
+    } else if path.is_dir() {
+        parse_dir_with_naming(path, naming)?
+    } else {
+        parse_file_with_naming(path, naming)?
+    };
+    dialect.validate(&tables)?;
+
+    Ok(tables)
+}
+
+fn is_database(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("db" | "sqlite" | "sqlite3")
+    )
+}
+
+fn print_warnings(warnings: Vec<String>) {
+    for warning in warnings {
+        eprintln!("Warning: {}.", warning);
+    }
+}
+
+fn write_header<W: Write>(file: &mut W) -> Result<()> {
     let out = (0..32).map(|_| "=").collect::<Vec<_>>().join("");
-    writeln!(file, "-- {}", &out).unwrap();
+    writeln!(file, "-- {}", &out)?;
     writeln!(
         file,
         "-- Warning! This file has been generated with tsql. Keep in mind that manuel changes will be overridden."
-    )
-    .unwrap();
+    )?;
     writeln!(
         file,
         "-- Executable tsql build with git commit {:?}",
         env!("GIT_HASH")
-    )
-    .unwrap();
-    writeln!(file, "-- {}", &out).unwrap();
+    )?;
+    writeln!(file, "-- {}", &out)?;
+
+    Ok(())
+}
+
+fn create(
+    tsql_path: &Path,
+    out_path: &Path,
+    emit: Emit,
+    naming: NamingConvention,
+    dialect: Dialect,
+) -> Result<()> {
+    let tables = parse(tsql_path, naming, dialect)?;
+
+    let mut file = create_output(Some(out_path))?;
+
+    match emit {
+        Emit::Sql => {
+            write_header(&mut file)?;
+            transform_tables(&tables, dialect, &mut file)?;
+        }
+        Emit::Json => {
+            serde_json::to_writer_pretty(&mut file, &tables)?;
+            writeln!(file)?;
+        }
+        Emit::JsonSchema => {
+            serde_json::to_writer_pretty(&mut file, &json_schema(&tables))?;
+            writeln!(file)?;
+        }
+    }
+    file.flush()?;
+
+    Ok(())
+}
+
+fn migrate(
+    old_path: &Path,
+    new_path: &Path,
+    out_path: Option<&Path>,
+    check: bool,
+    naming: NamingConvention,
+    dialect: Dialect,
+) -> Result<()> {
+    let old = parse(old_path, naming.clone(), dialect)?;
+    let new = parse(new_path, naming, dialect)?;
+
+    let migration = diff(&old, &new);
+
+    // a change the dialect can't express fails before anything is written
+    let mut sql = Vec::new();
+    migration.transform(dialect, &mut sql)?;
+
+    let mut file = create_output(out_path)?;
+
+    write_header(&mut file)?;
+    file.write_all(&sql)?;
+    file.flush()?;
+
+    if check && !migration.is_empty() {
+        eprintln!(
+            "Error: {} and {} are different.",
+            old_path.display(),
+            new_path.display()
+        );
+        exit(1);
+    }
+
+    Ok(())
+}
 
-    for (_, table) in tables {
-        table.transform(&mut file).unwrap();
+fn drop_tables(
+    tsql_path: &Path,
+    out_path: Option<&Path>,
+    options: DropOptions,
+    naming: NamingConvention,
+    dialect: Dialect,
+) -> Result<()> {
+    let tables = parse(tsql_path, naming, dialect)?;
+
+    // options the dialect doesn't support fail before anything is written
+    let mut sql = Vec::new();
+    transform_drop_tables(&tables, dialect, options, &mut sql)?;
+
+    let mut file = create_output(out_path)?;
+
+    write_header(&mut file)?;
+    file.write_all(&sql)?;
+    file.flush()?;
+
+    Ok(())
+}
+
+fn codegen(
+    language: Language,
+    tsql_path: &Path,
+    out_path: Option<&Path>,
+    derives: &[String],
+    typescript_options: &TypeScriptOptions,
+    naming: NamingConvention,
+    dialect: Dialect,
+) -> Result<()> {
+    let tables = parse(tsql_path, naming, dialect)?;
+
+    let mut file = create_output(out_path)?;
+
+    match language {
+        Language::Rust => {
+            let options = derives
+                .iter()
+                .fold(RustOptions::default(), |options, derive| {
+                    options.derive(derive)
+                });
+            rust::generate(&tables, &options, &mut file)?;
+        }
+        Language::TypeScript => {
+            typescript::generate(&tables, typescript_options, &mut file)?;
+        }
     }
-    file.flush().unwrap();
+    file.flush()?;
+
+    Ok(())
+}
+
+fn graph(
+    tsql_path: &Path,
+    out_path: Option<&Path>,
+    format: GraphFormat,
+    naming: NamingConvention,
+    dialect: Dialect,
+) -> Result<()> {
+    let tables = parse(tsql_path, naming, dialect)?;
+
+    let mut file = create_output(out_path)?;
+    write_graph(&tables, format, &mut file)?;
+    file.flush()?;
+
+    Ok(())
+}
+
+/// Writes into the file at `out_path`, or to stdout if there is none.
+fn create_output(out_path: Option<&Path>) -> Result<BufWriter<Box<dyn Write>>> {
+    let output: Box<dyn Write> = match out_path {
+        Some(out_path) => Box::new(
+            File::create(out_path)
+                .map_err(|err| anyhow!("Error: Can't create the file {:?}: {}", out_path, err))?,
+        ),
+        None => Box::new(stdout()),
+    };
+
+    Ok(BufWriter::new(output))
+}
+
+fn migrate_new(
+    name: &str,
+    tsql_path: &Path,
+    migrations: &Path,
+    naming: NamingConvention,
+    dialect: Dialect,
+) -> Result<()> {
+    let tables = parse(tsql_path, naming, dialect)?;
+
+    match MigrationDir::new(migrations).create(name, &tables, dialect)? {
+        Some(path) => println!("Created migration {}", path.display()),
+        None => println!("No changes since the last migration"),
+    }
+
+    Ok(())
+}
+
+fn import(sql_path: &Path, out_path: Option<&Path>, naming: &NamingConvention) -> Result<()> {
+    let (tables, warnings) = if is_database(sql_path) {
+        read_database(sql_path)?
+    } else {
+        let content = fs::read_to_string(sql_path)
+            .map_err(|err| anyhow!("Error: Can't read the file {:?}: {}", sql_path, err))?;
+        parse_sql(&content)?
+    };
+    print_warnings(warnings);
+
+    let mut file = create_output(out_path)?;
+
+    print_tables_with_naming(&tables, naming, &mut file)?;
+    file.flush()?;
+
+    Ok(())
+}
+
+/// Formats every file which can be parsed, a file with a syntax error is reported and skipped.
+fn fmt(tsql_path: &Path, check: bool) -> Result<()> {
+    let mut paths = Vec::new();
+    tsql_files(tsql_path, &mut paths)?;
+
+    let mut failed = false;
+    for path in paths {
+        let content = fs::read_to_string(&path)
+            .map_err(|err| anyhow!("Error: Can't read the file {:?}: {}", path, err))?;
+        let formatted = match format_str(&content) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                eprintln!("{} in {}", err, path.display());
+                failed = true;
+                continue;
+            }
+        };
+
+        if formatted == content {
+            continue;
+        }
+
+        if check {
+            eprintln!("{} isn't formatted", path.display());
+            failed = true;
+        } else {
+            fs::write(&path, formatted)
+                .map_err(|err| anyhow!("Error: Can't write the file {:?}: {}", path, err))?;
+        }
+    }
+
+    if failed {
+        exit(1);
+    }
+
+    Ok(())
+}
+
+/// The file itself or every .tsql file inside of the directory and its subdirectories.
+fn tsql_files(path: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        paths.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir()
+            || entry
+                .extension()
+                .is_some_and(|extension| extension == "tsql")
+        {
+            tsql_files(&entry, paths)?;
+        }
+    }
+
+    Ok(())
 }
 
 fn parse_args() -> Result<AppArgs, pico_args::Error> {
@@ -63,10 +591,119 @@ fn parse_args() -> Result<AppArgs, pico_args::Error> {
         std::process::exit(0);
     }
 
+    let mut naming = NamingConvention::default();
+    if let Some(column) = pargs.opt_value_from_str::<_, NamingStrategy>("--naming")? {
+        naming.column = column;
+    }
+    if let Some(primary_key) = pargs.opt_value_from_str("--pk-naming")? {
+        naming.primary_key = primary_key;
+    }
+    if let Some(foreign_key) = pargs.opt_value_from_str("--fk-naming")? {
+        naming.foreign_key = foreign_key;
+    }
+    if let Some(unique) = pargs.opt_value_from_str("--unique-naming")? {
+        naming.unique = unique;
+    }
+    if let Some(check) = pargs.opt_value_from_str("--check-naming")? {
+        naming.check = check;
+    }
+
+    let dialect = pargs
+        .opt_value_from_str::<_, Dialect>("--dialect")?
+        .unwrap_or_default();
+
+    let options = DropOptions {
+        if_exists: pargs.contains("--if-exists"),
+        cascade: pargs.contains("--cascade"),
+        drop_schemas: pargs.contains("--drop-schemas"),
+    };
+
+    let migrations = pargs
+        .opt_value_from_str("--migrations")?
+        .unwrap_or_else(|| PathBuf::from("migrations"));
+
+    let output = pargs.opt_value_from_str(["-o", "--output"])?;
+    let emit = pargs.opt_value_from_str("--emit")?.unwrap_or_default();
+    let check = pargs.contains("--check");
+    let derives = pargs
+        .opt_value_from_str::<_, String>("--derive")?
+        .map(|derives| {
+            derives
+                .split(',')
+                .map(|derive| derive.trim().to_string())
+                .filter(|derive| !derive.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    let typescript = TypeScriptOptions {
+        dates: pargs
+            .opt_value_from_str::<_, DateType>("--dates")?
+            .unwrap_or_default(),
+        zod: pargs.contains("--zod"),
+    };
+    let format = pargs
+        .opt_value_from_str::<_, GraphFormat>("--format")?
+        .unwrap_or_default();
+
+    // Parses the required free-standing/positional arguments.
+    let command = match pargs.subcommand()?.as_deref() {
+        Some("migrate") => match pargs.subcommand()?.as_deref() {
+            Some("new") => Command::MigrateNew {
+                name: pargs.free_from_str()?,
+                tsql_path: pargs.free_from_str()?,
+                migrations,
+            },
+            _ => return Err(pico_args::Error::MissingArgument),
+        },
+        Some("drop") => Command::Drop {
+            tsql_path: pargs.free_from_str()?,
+            out_path: pargs.opt_free_from_str()?,
+            options,
+        },
+        Some("import") => Command::Import {
+            sql_path: pargs.free_from_str()?,
+            out_path: output,
+        },
+        Some("fmt") => Command::Fmt {
+            tsql_path: pargs.free_from_str()?,
+            check,
+        },
+        Some("codegen") => Command::Codegen {
+            language: pargs.free_from_str()?,
+            tsql_path: pargs.free_from_str()?,
+            out_path: pargs.opt_free_from_str()?,
+            derives,
+            typescript,
+        },
+        Some("graph") => Command::Graph {
+            tsql_path: pargs.free_from_str()?,
+            out_path: pargs.opt_free_from_str()?,
+            format,
+        },
+        Some("lsp") => {
+            // passed by some editors, stdio is the only transport
+            pargs.contains("--stdio");
+
+            Command::Lsp
+        }
+        Some("diff") => Command::Diff {
+            old_path: pargs.free_from_str()?,
+            new_path: pargs.free_from_str()?,
+            out_path: pargs.opt_free_from_str()?,
+            check,
+        },
+        Some(tsql_path) => Command::Create {
+            tsql_path: PathBuf::from(tsql_path),
+            out_path: pargs.free_from_str()?,
+            emit,
+        },
+        None => return Err(pico_args::Error::MissingArgument),
+    };
+
     let args = AppArgs {
-        // Parses a required free-standing/positional argument.
-        tsql_path: pargs.free_from_str()?,
-        out_path: pargs.free_from_str()?,
+        command,
+        naming,
+        dialect,
     };
 
     let remaining = pargs.finish();
@@ -0,0 +1,286 @@
+//! Generates the sql and the types of a schema in a build script, as an alternative to the
+//! `tsql_macros::include_schema!` macro:
+//!
+//! ```no_run
+//! // build.rs
+//! use tsql::build::{Build, Emit};
+//! use tsql::codegen::rust::RustOptions;
+//! use tsql::dialect::Dialect;
+//!
+//! fn main() {
+//!     Build::new()
+//!         .input("schema.tsql")
+//!         .dialect(Dialect::Postgres)
+//!         .emit(Emit::Sql)
+//!         .emit(Emit::Rust(RustOptions::default().derive("serde")))
+//!         .run()
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! The outputs are written into `OUT_DIR` and can be included with
+//! `include!(concat!(env!("OUT_DIR"), "/schema.rs"))` or
+//! `include_str!(concat!(env!("OUT_DIR"), "/schema.sql"))`. Every read file, including the
+//! imported ones, is printed as `cargo:rerun-if-changed`, so the build script runs again when
+//! one of them changes.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use crate::codegen::rust::{self, RustOptions};
+use crate::codegen::typescript::{self, TypeScriptOptions};
+use crate::dialect::Dialect;
+use crate::loader::Loader;
+use crate::naming::NamingConvention;
+use crate::transform_tables;
+use crate::types::TableCollection;
+
+/// Output of a [`Build`], written into `{name}.{extension}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Emit {
+    /// `CREATE TABLE` statements for the dialect, `.sql`
+    Sql,
+    /// structs of the rows, `.rs`, see [`crate::codegen::rust`]
+    Rust(RustOptions),
+    /// interfaces of the rows, `.ts`, see [`crate::codegen::typescript`]
+    TypeScript(TypeScriptOptions),
+    /// the resolved tables, `.json`
+    #[cfg(feature = "serde")]
+    Json,
+    /// JSON Schema of the tables, `.schema.json`, see [`crate::json_schema`]
+    #[cfg(feature = "serde")]
+    JsonSchema,
+}
+
+impl Emit {
+    fn extension(&self) -> &'static str {
+        match self {
+            Emit::Sql => "sql",
+            Emit::Rust(_) => "rs",
+            Emit::TypeScript(_) => "ts",
+            #[cfg(feature = "serde")]
+            Emit::Json => "json",
+            #[cfg(feature = "serde")]
+            Emit::JsonSchema => "schema.json",
+        }
+    }
+
+    fn write<W: Write>(
+        &self,
+        tables: &TableCollection,
+        dialect: Dialect,
+        buffer: &mut W,
+    ) -> Result<()> {
+        match self {
+            Emit::Sql => transform_tables(tables, dialect, buffer),
+            Emit::Rust(options) => rust::generate(tables, options, buffer),
+            Emit::TypeScript(options) => typescript::generate(tables, options, buffer),
+            #[cfg(feature = "serde")]
+            Emit::Json => {
+                serde_json::to_writer_pretty(&mut *buffer, tables)?;
+                Ok(writeln!(buffer)?)
+            }
+            #[cfg(feature = "serde")]
+            Emit::JsonSchema => {
+                serde_json::to_writer_pretty(
+                    &mut *buffer,
+                    &crate::json_schema::json_schema(tables),
+                )?;
+                Ok(writeln!(buffer)?)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Build {
+    inputs: Vec<PathBuf>,
+    dialect: Dialect,
+    naming: NamingConvention,
+    emits: Vec<Emit>,
+    name: String,
+    out_dir: Option<PathBuf>,
+}
+
+impl Default for Build {
+    fn default() -> Self {
+        Build {
+            inputs: Vec::new(),
+            dialect: Dialect::default(),
+            naming: NamingConvention::default(),
+            emits: Vec::new(),
+            name: "schema".to_string(),
+            out_dir: None,
+        }
+    }
+}
+
+impl Build {
+    pub fn new() -> Self {
+        Build::default()
+    }
+
+    /// Adds a file or a directory, whose `.tsql` files are all used. Paths are relative to the
+    /// `Cargo.toml` of the crate, the tables of every input are written into the same outputs.
+    pub fn input<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.inputs.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Dialect the tables are validated and the sql is written for.
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    pub fn naming(mut self, naming: NamingConvention) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    pub fn emit(mut self, emit: Emit) -> Self {
+        self.emits.push(emit);
+        self
+    }
+
+    /// Name of the output files without their extension, default `schema`.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Directory of the outputs, `OUT_DIR` of the build script by default.
+    pub fn out_dir<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Parses the inputs, prints `cargo:rerun-if-changed` for every read file and directory
+    /// and writes the outputs. Returns the paths of the written files.
+    pub fn run(self) -> Result<Vec<PathBuf>> {
+        if self.inputs.is_empty() {
+            bail!("Error: The build has no input");
+        }
+
+        let out_dir = self
+            .out_dir
+            .or_else(|| std::env::var_os("OUT_DIR").map(PathBuf::from));
+        let Some(out_dir) = out_dir else {
+            bail!(
+                "Error: OUT_DIR isn't set, run the build from a build script or set the \
+                 directory with `Build::out_dir`"
+            );
+        };
+
+        let mut loader = Loader::new(self.naming);
+        for input in &self.inputs {
+            // a directory is watched too, so that new files are picked up
+            if input.is_dir() {
+                println!("cargo:rerun-if-changed={}", input.display());
+                loader.add_dir(input)?;
+            } else {
+                loader.add_file(input)?;
+            }
+        }
+
+        for file in loader.files() {
+            println!("cargo:rerun-if-changed={}", file.display());
+        }
+
+        let tables = loader.finish()?;
+        self.dialect.validate(&tables)?;
+
+        let mut written = Vec::with_capacity(self.emits.len());
+        for emit in &self.emits {
+            let path = out_dir.join(format!("{}.{}", self.name, emit.extension()));
+
+            let mut file = BufWriter::new(File::create(&path)?);
+            emit.write(&tables, self.dialect, &mut file)?;
+            file.flush()?;
+
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod run {
+        use std::path::PathBuf;
+
+        use crate::build::{Build, Emit};
+        use crate::codegen::rust::RustOptions;
+        use crate::dialect::Dialect;
+
+        fn out_dir(name: &str) -> PathBuf {
+            let out_dir =
+                std::env::temp_dir().join(format!("tsql_build_{}_{}", name, std::process::id()));
+            std::fs::create_dir_all(&out_dir).unwrap();
+
+            out_dir
+        }
+
+        #[test]
+        fn just_works() {
+            let out_dir = out_dir("just_works");
+
+            let written = Build::new()
+                .input("./tests/files/imports/main.tsql")
+                .dialect(Dialect::Postgres)
+                .emit(Emit::Sql)
+                .emit(Emit::Rust(RustOptions::default()))
+                .name("appointments")
+                .out_dir(&out_dir)
+                .run()
+                .unwrap();
+            assert_eq!(
+                written,
+                vec![
+                    out_dir.join("appointments.sql"),
+                    out_dir.join("appointments.rs")
+                ]
+            );
+
+            let sql = std::fs::read_to_string(&written[0]).unwrap();
+            assert!(sql.contains("CREATE TABLE Termin"));
+            let rust = std::fs::read_to_string(&written[1]).unwrap();
+            assert!(rust.contains("pub struct HasAppointment {"));
+
+            let written = Build::new()
+                .input("./tests/files/imports")
+                .emit(Emit::Sql)
+                .out_dir(&out_dir)
+                .run()
+                .unwrap();
+            let sql = std::fs::read_to_string(&written[0]).unwrap();
+            assert!(sql.contains("CREATE TABLE Room"));
+
+            std::fs::remove_dir_all(out_dir).unwrap();
+        }
+
+        #[test]
+        fn errors() {
+            let out_dir = out_dir("errors");
+
+            assert!(Build::new().out_dir(&out_dir).run().is_err());
+            assert!(Build::new()
+                .input("./tests/files/imports_errors/cycle_a.tsql")
+                .emit(Emit::Sql)
+                .out_dir(&out_dir)
+                .run()
+                .is_err());
+            assert!(Build::new()
+                .input("./tests/files/imports/missing.tsql")
+                .out_dir(&out_dir)
+                .run()
+                .is_err());
+
+            std::fs::remove_dir_all(out_dir).unwrap();
+        }
+    }
+}
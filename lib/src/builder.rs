@@ -0,0 +1,412 @@
+//! Builds a [`TableCollection`] from code instead of tsql source, e.g. in tests or build
+//! scripts:
+//!
+//! ```
+//! use tsql::builder::Schema;
+//! use tsql::types::DataType;
+//!
+//! let tables = Schema::builder()
+//!     .table("Human", |t| {
+//!         t.pk_field("id", DataType::Int)
+//!             .field("name", DataType::VarChar(32))
+//!     })
+//!     .table("Car", |t| {
+//!         t.pk_field("plate", DataType::VarChar(16))
+//!             .nullable_foreign_key("owner", "Human")
+//!     })
+//!     .build()
+//!     .unwrap();
+//!
+//! assert!(tables["Car"].get_field("owner_id").unwrap().is_nullable());
+//! ```
+//!
+//! The tables are validated and resolved exactly like parsed ones.
+
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+
+use crate::ddl::tsql_identifier;
+use crate::naming::NamingConvention;
+use crate::parser::types::{
+    FieldExtra, FieldType, RawDataType, RawField, RawForeignKey, RawTable, TableExtra,
+};
+use crate::types::{DataType, Position, RawTableCollection, Table, TableCollection, BUILTIN_TYPES};
+
+/// Entry point of the builder, see the [module](self).
+pub struct Schema;
+
+impl Schema {
+    pub fn builder() -> SchemaBuilder {
+        SchemaBuilder::default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SchemaBuilder {
+    naming: NamingConvention,
+    tables: Vec<TableBuilder>,
+}
+
+impl SchemaBuilder {
+    /// Naming convention of all tables, the same as a `@naming(...);` directive.
+    pub fn naming(mut self, naming: NamingConvention) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Adds a table, `name` can be qualified by a database schema, e.g. `billing.Invoice`.
+    pub fn table<F>(mut self, name: &str, build: F) -> Self
+    where
+        F: FnOnce(TableBuilder) -> TableBuilder,
+    {
+        let (schema, name) = match name.split_once('.') {
+            Some((schema, name)) => (Some(schema.to_string()), name),
+            None => (None, name),
+        };
+
+        self.tables.push(build(TableBuilder {
+            name: name.to_string(),
+            schema,
+            extra: TableExtra::default(),
+            fields: Vec::new(),
+        }));
+        self
+    }
+
+    /// Validates and resolves the tables, see [`crate::parse_str`].
+    pub fn build(self) -> Result<TableCollection> {
+        let naming = Rc::new(self.naming);
+
+        let mut defined = HashSet::with_capacity(self.tables.len());
+        let mut tables = Vec::with_capacity(self.tables.len());
+        for builder in self.tables {
+            let mut table = builder.finish()?;
+            table.naming = naming.clone();
+
+            if !defined.insert(table.qualified_name()) {
+                bail!(
+                    "Error: Table {:?} is defined more than once",
+                    table.qualified_name()
+                );
+            }
+
+            tables.push(table);
+        }
+
+        let mut raw_tables = RawTableCollection::new();
+        for mut table in tables {
+            table.qualify_references(&defined);
+            raw_tables.insert(table.qualified_name(), table);
+        }
+
+        Table::parse_raw_tables(raw_tables)
+    }
+}
+
+#[derive(Debug)]
+pub struct TableBuilder {
+    name: String,
+    schema: Option<String>,
+    extra: TableExtra,
+    fields: Vec<FieldType>,
+}
+
+impl TableBuilder {
+    pub fn field(self, name: &str, datatype: DataType) -> Self {
+        self.field_with(name, datatype, |f| f)
+    }
+
+    pub fn nullable_field(self, name: &str, datatype: DataType) -> Self {
+        self.field_with(name, datatype, FieldBuilder::nullable)
+    }
+
+    /// Adds the field and appends it to the primary key.
+    pub fn pk_field(mut self, name: &str, datatype: DataType) -> Self {
+        self.extra.primary_key.push(name.to_string());
+        self.field(name, datatype)
+    }
+
+    /// Adds a field with the annotations set by `build`.
+    pub fn field_with<F>(mut self, name: &str, datatype: DataType, build: F) -> Self
+    where
+        F: FnOnce(FieldBuilder) -> FieldBuilder,
+    {
+        let field = build(FieldBuilder(raw_field(name, datatype.into())));
+        self.fields.push(FieldType::Real(field.0));
+        self
+    }
+
+    /// Adds a `@foreign_key() table name` field, an unqualified `table` inside of a database
+    /// schema references a table of the same schema if there is one.
+    pub fn foreign_key(self, name: &str, table: &str) -> Self {
+        self.foreign_key_with(name, table, |f| f)
+    }
+
+    pub fn nullable_foreign_key(self, name: &str, table: &str) -> Self {
+        self.foreign_key_with(name, table, ForeignKeyBuilder::nullable)
+    }
+
+    /// Adds a foreign key with the arguments and annotations set by `build`.
+    pub fn foreign_key_with<F>(mut self, name: &str, table: &str, build: F) -> Self
+    where
+        F: FnOnce(ForeignKeyBuilder) -> ForeignKeyBuilder,
+    {
+        let datatype = RawDataType::ForeignKeyTable(table.to_string());
+        let foreign_key = build(ForeignKeyBuilder(
+            raw_field(name, datatype),
+            RawForeignKey::default(),
+        ));
+
+        self.fields.push(FieldType::Virtual((
+            foreign_key.0,
+            FieldExtra::ForeignKey(foreign_key.1),
+        )));
+        self
+    }
+
+    /// `@primary_key(fields)`, the fields are appended to the ones of [`TableBuilder::pk_field`].
+    pub fn primary_key(mut self, fields: &[&str]) -> Self {
+        self.extra
+            .primary_key
+            .extend(fields.iter().map(|field| field.to_string()));
+        self
+    }
+
+    /// `@primary_key(...) as name`
+    pub fn primary_key_name(mut self, name: &str) -> Self {
+        self.extra.primary_key_name = Some(name.to_string());
+        self
+    }
+
+    pub fn unique(self, fields: &[&str]) -> Self {
+        self.add_unique(fields, None)
+    }
+
+    /// `@unique(fields) as name`
+    pub fn unique_as(self, fields: &[&str], name: &str) -> Self {
+        self.add_unique(fields, Some(name))
+    }
+
+    /// `@check(expression)`, the expression is written into the sql as it is.
+    pub fn check(self, expression: &str) -> Self {
+        self.add_check(expression, None)
+    }
+
+    /// `@check(expression) as name`
+    pub fn check_as(self, expression: &str, name: &str) -> Self {
+        self.add_check(expression, Some(name))
+    }
+
+    /// `@renamed_from(old_name)`, see [`crate::diff`].
+    pub fn renamed_from(mut self, old_name: &str) -> Self {
+        self.extra.renamed_from = Some(old_name.to_string());
+        self
+    }
+
+    fn add_unique(mut self, fields: &[&str], name: Option<&str>) -> Self {
+        self.extra.unique.push((
+            fields.iter().map(|field| field.to_string()).collect(),
+            name.map(str::to_string),
+        ));
+        self
+    }
+
+    fn add_check(mut self, expression: &str, name: Option<&str>) -> Self {
+        self.extra
+            .check
+            .push((expression.to_string(), name.map(str::to_string)));
+        self
+    }
+
+    /// Checks the names which the parser would have rejected.
+    fn finish(self) -> Result<RawTable> {
+        let names = self
+            .schema
+            .iter()
+            .chain(std::iter::once(&self.name))
+            .chain(self.fields.iter().map(|field| &field.raw_field().name));
+        for name in names {
+            tsql_identifier(name)?;
+        }
+
+        if BUILTIN_TYPES
+            .iter()
+            .any(|(datatype, _)| *datatype == self.name)
+        {
+            bail!(
+                "Error: The type {:?} can't be used as the name of a table",
+                self.name
+            );
+        }
+
+        Ok(RawTable {
+            extra: self.extra,
+            name: self.name,
+            schema: self.schema,
+            position: Position::default(),
+            file: None,
+            naming: Rc::default(),
+            span: Default::default(),
+            comment: None,
+            fields: self.fields,
+        })
+    }
+}
+
+/// Annotations of a field added with [`TableBuilder::field_with`].
+#[derive(Debug)]
+pub struct FieldBuilder(RawField);
+
+impl FieldBuilder {
+    pub fn nullable(mut self) -> Self {
+        self.0.nullable = true;
+        self
+    }
+
+    /// `@default(value)`, the value is written into the sql as it is.
+    pub fn default(mut self, value: &str) -> Self {
+        self.0.default = Some(value.to_string());
+        self
+    }
+
+    pub fn renamed_from(mut self, old_name: &str) -> Self {
+        self.0.renamed_from = Some(old_name.to_string());
+        self
+    }
+}
+
+/// Arguments and annotations of a foreign key added with [`TableBuilder::foreign_key_with`].
+#[derive(Debug)]
+pub struct ForeignKeyBuilder(RawField, RawForeignKey);
+
+impl ForeignKeyBuilder {
+    pub fn nullable(mut self) -> Self {
+        self.0.nullable = true;
+        self
+    }
+
+    /// Overrides the names of the generated columns, `@foreign_key(columns)`.
+    pub fn columns(mut self, columns: &[&str]) -> Self {
+        self.1.columns = columns.iter().map(|column| column.to_string()).collect();
+        self
+    }
+
+    /// `@foreign_key(name = constraint_name)`
+    pub fn constraint_name(mut self, name: &str) -> Self {
+        self.1.name = Some(name.to_string());
+        self
+    }
+
+    pub fn renamed_from(mut self, old_name: &str) -> Self {
+        self.0.renamed_from = Some(old_name.to_string());
+        self
+    }
+}
+
+fn raw_field(name: &str, datatype: RawDataType) -> RawField {
+    RawField {
+        name: name.to_string(),
+        datatype,
+        position: Position::default(),
+        renamed_from: None,
+        nullable: false,
+        default: None,
+        span: Default::default(),
+        comment: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod build {
+        use crate::builder::Schema;
+        use crate::diff::diff;
+        use crate::naming::{NamingConvention, NamingStrategy};
+        use crate::parse_str;
+        use crate::types::DataType;
+
+        #[test]
+        fn just_works() {
+            let tables = Schema::builder()
+                .table("Human", |t| {
+                    t.pk_field("id", DataType::Int)
+                        .field("name", DataType::VarChar(32))
+                        .field_with("birth", DataType::Date, |f| f.nullable())
+                })
+                .table("billing.Invoice", |t| {
+                    t.pk_field("id", DataType::Int)
+                        .foreign_key_with("customer", "Human", |f| {
+                            f.columns(&["customer"]).constraint_name("fk_customer")
+                        })
+                        .field_with("total", DataType::Decimal(10, 2), |f| f.default("0"))
+                        .check("total >= 0")
+                })
+                .build()
+                .unwrap();
+
+            let parsed = parse_str(
+                "@primary_key(id) table Human { int id, varchar(32) name, @nullable date birth, };
+                schema billing {
+                    @primary_key(id) @check(total >= 0)
+                    table Invoice {
+                        int id,
+                        @foreign_key(columns = (customer), name = fk_customer) Human customer,
+                        @default(0) decimal(10, 2) total,
+                    };
+                };",
+            )
+            .unwrap();
+            assert!(diff(&parsed, &tables).is_empty());
+
+            let invoice = &tables["billing.Invoice"];
+            let fields = invoice.fields().map(|f| f.name()).collect::<Vec<_>>();
+            assert_eq!(fields, vec!["id", "customer", "total"]);
+            assert_eq!(invoice.foreign_keys()[0].constraint(), "fk_customer");
+
+            let tables = Schema::builder()
+                .naming(NamingConvention {
+                    column: NamingStrategy::CamelCase,
+                    ..Default::default()
+                })
+                .table("Human", |t| t.pk_field("id", DataType::Int))
+                .table("Pet", |t| t.foreign_key("owner", "Human"))
+                .build()
+                .unwrap();
+            assert!(tables["Pet"].get_field("ownerId").is_some());
+        }
+
+        #[test]
+        fn errors() {
+            let missing = Schema::builder()
+                .table("Car", |t| t.foreign_key("owner", "Human"))
+                .build();
+            assert!(missing.unwrap_err().to_string().contains("\"Human\""));
+
+            let duplicate = Schema::builder()
+                .table("Human", |t| t.field("id", DataType::Int))
+                .table("Human", |t| t.field("id", DataType::Int))
+                .build();
+            assert_eq!(
+                duplicate.unwrap_err().to_string(),
+                "Error: Table \"Human\" is defined more than once"
+            );
+
+            let no_primary_key = Schema::builder()
+                .table("Human", |t| t.primary_key(&["id"]))
+                .build();
+            assert!(no_primary_key.is_err());
+
+            let invalid_name = Schema::builder()
+                .table("Human", |t| t.field("first name", DataType::Int))
+                .build();
+            assert!(invalid_name.is_err());
+
+            let type_name = Schema::builder()
+                .table("uuid", |t| t.field("id", DataType::Int))
+                .build();
+            assert!(type_name.is_err());
+        }
+    }
+}
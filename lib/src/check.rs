@@ -0,0 +1,269 @@
+//! Parses the simple `@check` expressions and `@default` values which the generators can
+//! translate, e.g. `length(name) > 0` or `status IN ('active', 'blocked')`.
+
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case, take_while};
+use nom::character::complete::{char, digit1, multispace0, multispace1};
+use nom::combinator::{all_consuming, map, map_opt, opt, recognize, value};
+use nom::multi::{separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use nom::IResult;
+
+use crate::parser::helper::get_identifier;
+
+/// A constant of an sql expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Literal {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operator {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    NotEqual,
+}
+
+impl Operator {
+    /// Operator with swapped sides, `1 < a` is `a > 1`.
+    fn flip(self) -> Self {
+        match self {
+            Operator::Less => Operator::Greater,
+            Operator::LessEqual => Operator::GreaterEqual,
+            Operator::Greater => Operator::Less,
+            Operator::GreaterEqual => Operator::LessEqual,
+            Operator::NotEqual => Operator::NotEqual,
+        }
+    }
+}
+
+/// A condition on the value of a single column.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Condition {
+    /// `column < literal`
+    Compare(Operator, Literal),
+    /// `length(column) < literal`
+    Length(Operator, Literal),
+    /// `column IN (literals)`
+    In(Vec<Literal>),
+}
+
+/// The conditions of a `@check` expression as `(column, condition)`, which are joined with
+/// `AND`. Empty if the expression contains anything else, e.g. `OR` or a second column.
+pub(crate) fn check_conditions(expression: &str) -> Vec<(String, Condition)> {
+    match all_consuming(delimited(multispace0, conjunction, multispace0))(expression) {
+        Ok((_, conditions)) => conditions.into_iter().flatten().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// The `@default` value if it's a literal, `None` for any other expression, e.g. `now()`.
+#[cfg(feature = "serde")]
+pub(crate) fn literal_value(expression: &str) -> Option<Literal> {
+    all_consuming(delimited(multispace0, literal, multispace0))(expression)
+        .ok()
+        .map(|(_, literal)| literal)
+}
+
+/// `a AND b AND ...`
+fn conjunction(input: &str) -> IResult<&str, Vec<Vec<(String, Condition)>>> {
+    separated_list1(
+        tuple((multispace1, tag_no_case("and"), multispace1)),
+        alt((
+            map(
+                delimited(
+                    pair(char('('), multispace0),
+                    conjunction,
+                    pair(multispace0, char(')')),
+                ),
+                |conditions| conditions.into_iter().flatten().collect(),
+            ),
+            map(in_list, |condition| vec![condition]),
+            map(comparison, |condition| vec![condition]),
+        )),
+    )(input)
+}
+
+/// `column IN (value, ...)`
+fn in_list(input: &str) -> IResult<&str, (String, Condition)> {
+    map(
+        separated_pair(
+            get_identifier,
+            tuple((multispace1, tag_no_case("in"), multispace0)),
+            delimited(
+                pair(char('('), multispace0),
+                separated_list0(tuple((multispace0, char(','), multispace0)), literal),
+                pair(multispace0, char(')')),
+            ),
+        ),
+        |(column, values)| (column.to_string(), Condition::In(values)),
+    )(input)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand<'a> {
+    Column(&'a str),
+    Length(&'a str),
+    Literal(Literal),
+}
+
+/// `a < 1`, `1 <= a`, `length(a) > 0` or `a <> ''`
+fn comparison(input: &str) -> IResult<&str, (String, Condition)> {
+    map_opt(
+        tuple((
+            operand,
+            delimited(multispace0, operator, multispace0),
+            operand,
+        )),
+        |(left, operator, right)| {
+            let (column, operator, literal) = match (left, right) {
+                (Operand::Literal(literal), column) => (column, operator.flip(), literal),
+                (column, Operand::Literal(literal)) => (column, operator, literal),
+                _ => return None,
+            };
+
+            match column {
+                Operand::Column(column) => {
+                    Some((column.to_string(), Condition::Compare(operator, literal)))
+                }
+                Operand::Length(column) => {
+                    Some((column.to_string(), Condition::Length(operator, literal)))
+                }
+                Operand::Literal(_) => None,
+            }
+        },
+    )(input)
+}
+
+fn operand(input: &str) -> IResult<&str, Operand<'_>> {
+    alt((
+        map(literal, Operand::Literal),
+        map(
+            preceded(
+                pair(tag_no_case("length"), multispace0),
+                delimited(
+                    pair(char('('), multispace0),
+                    get_identifier,
+                    pair(multispace0, char(')')),
+                ),
+            ),
+            Operand::Length,
+        ),
+        map(get_identifier, Operand::Column),
+    ))(input)
+}
+
+fn operator(input: &str) -> IResult<&str, Operator> {
+    alt((
+        value(Operator::LessEqual, tag("<=")),
+        value(Operator::GreaterEqual, tag(">=")),
+        value(Operator::NotEqual, tag("<>")),
+        value(Operator::NotEqual, tag("!=")),
+        value(Operator::Less, tag("<")),
+        value(Operator::Greater, tag(">")),
+    ))(input)
+}
+
+/// `'text'`, a number, `true` or `false`
+fn literal(input: &str) -> IResult<&str, Literal> {
+    alt((
+        map(
+            delimited(char('\''), take_while(|c| c != '\''), char('\'')),
+            |text: &str| Literal::Text(text.to_string()),
+        ),
+        number,
+        value(
+            Literal::Bool(true),
+            terminated(tag_no_case("true"), word_end),
+        ),
+        value(
+            Literal::Bool(false),
+            terminated(tag_no_case("false"), word_end),
+        ),
+    ))(input)
+}
+
+fn number(input: &str) -> IResult<&str, Literal> {
+    map_opt(
+        recognize(tuple((
+            opt(char('-')),
+            digit1,
+            opt(pair(char('.'), digit1)),
+        ))),
+        |text: &str| match text.parse::<i64>() {
+            Ok(integer) => Some(Literal::Integer(integer)),
+            Err(_) => text.parse::<f64>().ok().map(Literal::Float),
+        },
+    )(input)
+}
+
+/// Succeeds if the keyword isn't the start of a longer identifier, e.g. `true_value`.
+fn word_end(input: &str) -> IResult<&str, ()> {
+    match input.chars().next() {
+        Some(c) if c.is_alphanumeric() || c == '_' => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Tag,
+        ))),
+        _ => Ok((input, ())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod check_conditions {
+        use crate::check::{check_conditions, Condition, Literal, Operator};
+
+        #[test]
+        fn just_works() {
+            assert_eq!(
+                check_conditions("(age >= 0) AND (150 > age) and length(name) <= 32"),
+                vec![
+                    (
+                        "age".to_string(),
+                        Condition::Compare(Operator::GreaterEqual, Literal::Integer(0))
+                    ),
+                    (
+                        "age".to_string(),
+                        Condition::Compare(Operator::Less, Literal::Integer(150))
+                    ),
+                    (
+                        "name".to_string(),
+                        Condition::Length(Operator::LessEqual, Literal::Integer(32))
+                    ),
+                ]
+            );
+
+            assert_eq!(
+                check_conditions("status IN ('active', 'blocked')"),
+                vec![(
+                    "status".to_string(),
+                    Condition::In(vec![
+                        Literal::Text("active".to_string()),
+                        Literal::Text("blocked".to_string())
+                    ])
+                )]
+            );
+
+            assert_eq!(
+                check_conditions("price > -0.5"),
+                vec![(
+                    "price".to_string(),
+                    Condition::Compare(Operator::Greater, Literal::Float(-0.5))
+                )]
+            );
+        }
+
+        #[test]
+        fn errors() {
+            assert!(check_conditions("a + b > 0").is_empty());
+            assert!(check_conditions("a > 0 OR b > 0").is_empty());
+            assert!(check_conditions("a > b").is_empty());
+            assert!(check_conditions("lower(name) <> name").is_empty());
+        }
+    }
+}
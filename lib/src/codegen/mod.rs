@@ -0,0 +1,41 @@
+//! Generates the types of other languages from the resolved tables, so that code which reads
+//! or writes the rows doesn't drift from the schema.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+use crate::naming::to_pascal_case;
+use crate::types::{Table, TableCollection};
+
+pub mod rust;
+pub mod typescript;
+
+/// Name of the type of each table in PascalCase, a table inside of a database schema is
+/// prefixed with the schema, e.g. `billing.Invoice` becomes `BillingInvoice`.
+///
+/// Fails if two tables end up with the same name.
+pub(crate) fn type_names(tables: &TableCollection) -> Result<HashMap<String, String>> {
+    let mut names = HashMap::with_capacity(tables.len());
+    let mut tables_by_name = HashMap::with_capacity(tables.len());
+
+    for (qualified_name, table) in tables {
+        let name = type_name(table);
+        if let Some(other) = tables_by_name.insert(name.clone(), qualified_name) {
+            bail!(
+                "Error: The tables {:?} and {:?} both result in the type {:?}",
+                other,
+                qualified_name,
+                name
+            );
+        }
+
+        names.insert(qualified_name.clone(), name);
+    }
+
+    Ok(names)
+}
+
+fn type_name(table: &Table) -> String {
+    to_pascal_case(&table.qualified_name().replace('.', "_"))
+}
@@ -0,0 +1,348 @@
+//! Rust structs of the rows of each table:
+//!
+//! ```
+//! use tsql::codegen::rust::{generate, RustOptions};
+//!
+//! let tables = tsql::parse_str(
+//!     "@primary_key(id) table Human { uuid id, @nullable decimal(10, 2) height, };",
+//! )
+//! .unwrap();
+//!
+//! let mut out = Vec::new();
+//! generate(&tables, &RustOptions::default().derive("serde"), &mut out).unwrap();
+//!
+//! let out = String::from_utf8(out).unwrap();
+//! assert!(out.contains("pub struct Human {"));
+//! assert!(out.contains("pub height: Option<rust_decimal::Decimal>,"));
+//! ```
+//!
+//! Types which aren't part of std are written with their full path, `uuid` is `uuid::Uuid`,
+//! `decimal` is `rust_decimal::Decimal` and dates and times are the naive types of `chrono`.
+
+use std::collections::HashSet;
+use std::io::Write;
+
+use anyhow::{bail, Result};
+
+use crate::codegen::type_names;
+use crate::types::{DataType, Field, TableCollection};
+
+/// Strict and reserved keywords which can be used as a raw identifier, e.g. `r#type`.
+const KEYWORDS: [&str; 50] = [
+    "abstract",
+    "as",
+    "async",
+    "await",
+    "become",
+    "box",
+    "break",
+    "const",
+    "continue",
+    "do",
+    "dyn",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "final",
+    "fn",
+    "for",
+    "gen",
+    "if",
+    "impl",
+    "in",
+    "let",
+    "loop",
+    "macro",
+    "match",
+    "mod",
+    "move",
+    "mut",
+    "override",
+    "priv",
+    "pub",
+    "ref",
+    "return",
+    "static",
+    "struct",
+    "trait",
+    "true",
+    "try",
+    "type",
+    "typeof",
+    "unsafe",
+    "unsized",
+    "use",
+    "virtual",
+    "where",
+    "while",
+    "yield",
+    "union",
+    "macro_rules",
+];
+
+/// Keywords which can't be raw identifiers, `_` is appended to them.
+const PATH_KEYWORDS: [&str; 3] = ["crate", "self", "super"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RustOptions {
+    /// Paths of the derive macros of every struct, default `Debug`, `Clone` and `PartialEq`.
+    pub derives: Vec<String>,
+}
+
+impl Default for RustOptions {
+    fn default() -> Self {
+        RustOptions {
+            derives: vec![
+                "Debug".to_string(),
+                "Clone".to_string(),
+                "PartialEq".to_string(),
+            ],
+        }
+    }
+}
+
+impl RustOptions {
+    /// Adds a derive macro, `serde` is short for `serde::Serialize` and `serde::Deserialize`
+    /// and `sqlx` for `sqlx::FromRow`.
+    pub fn derive(mut self, derive: &str) -> Self {
+        let paths = match derive {
+            "serde" => vec!["serde::Serialize", "serde::Deserialize"],
+            "sqlx" => vec!["sqlx::FromRow"],
+            path => vec![path],
+        };
+
+        for path in paths {
+            if !self.derives.iter().any(|derive| derive == path) {
+                self.derives.push(path.to_string());
+            }
+        }
+        self
+    }
+
+    fn derives_from(&self, krate: &str) -> bool {
+        self.derives
+            .iter()
+            .any(|derive| derive.starts_with(&format!("{}::", krate)))
+    }
+}
+
+/// Writes a struct for every table, with a field for every column in the order of declaration.
+///
+/// Columns are renamed to snake_case, with `#[serde(rename)]` and `#[sqlx(rename)]` if these
+/// are derived. Fails if two tables or two columns of a table end up with the same name.
+pub fn generate<W: Write>(
+    tables: &TableCollection,
+    options: &RustOptions,
+    buffer: &mut W,
+) -> Result<()> {
+    let names = type_names(tables)?;
+
+    writeln!(
+        buffer,
+        "// Generated with tsql, manual changes will be overridden."
+    )?;
+
+    for (qualified_name, table) in tables {
+        writeln!(buffer)?;
+
+        if let Some(comment) = table.comment() {
+            write_doc(comment, "", buffer)?;
+            writeln!(buffer, "///")?;
+        }
+        writeln!(buffer, "/// Row of the table `{}`.", qualified_name)?;
+        if !options.derives.is_empty() {
+            writeln!(buffer, "#[derive({})]", options.derives.join(", "))?;
+        }
+        writeln!(buffer, "pub struct {} {{", names[qualified_name])?;
+
+        let mut identifiers = HashSet::new();
+        for field in table.fields() {
+            let identifier = identifier(field.name());
+            if !identifiers.insert(identifier.clone()) {
+                bail!(
+                    "Error: More than one field of the table {:?} results in the field {:?}",
+                    qualified_name,
+                    identifier
+                );
+            }
+
+            if let Some(comment) = field.comment() {
+                write_doc(comment, "    ", buffer)?;
+            }
+            if let Some((target, key)) = field.references(tables) {
+                if field.comment().is_some() {
+                    writeln!(buffer, "    ///")?;
+                }
+                writeln!(
+                    buffer,
+                    "    /// References `{}` of [`{}`].",
+                    key.name(),
+                    names[&target.qualified_name()]
+                )?;
+            }
+
+            if identifier.trim_start_matches("r#") != field.name() {
+                for krate in ["serde", "sqlx"] {
+                    if options.derives_from(krate) {
+                        writeln!(buffer, "    #[{}(rename = {:?})]", krate, field.name())?;
+                    }
+                }
+            }
+
+            writeln!(buffer, "    pub {}: {},", identifier, field_type(field))?;
+        }
+
+        writeln!(buffer, "}}")?;
+    }
+
+    Ok(())
+}
+
+fn write_doc<W: Write>(comment: &str, indent: &str, buffer: &mut W) -> Result<()> {
+    for line in comment.lines() {
+        match line.is_empty() {
+            true => writeln!(buffer, "{}///", indent)?,
+            false => writeln!(buffer, "{}/// {}", indent, line)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn field_type(field: &Field) -> String {
+    let datatype = match field.datatype() {
+        DataType::Int => "i32",
+        DataType::BigInt => "i64",
+        DataType::Bool => "bool",
+        DataType::Double => "f64",
+        DataType::Float => "f32",
+        DataType::Date => "chrono::NaiveDate",
+        DataType::DateTime => "chrono::NaiveDateTime",
+        DataType::Time => "chrono::NaiveTime",
+        DataType::Uuid => "uuid::Uuid",
+        DataType::Decimal(_, _) => "rust_decimal::Decimal",
+        DataType::VarChar(_) | DataType::Char(_) | DataType::Text(_) => "String",
+    };
+
+    match field.is_nullable() {
+        true => format!("Option<{}>", datatype),
+        false => datatype.to_string(),
+    }
+}
+
+/// Name of the column in snake_case, which is a valid identifier.
+fn identifier(column: &str) -> String {
+    let mut name = String::with_capacity(column.len() + 4);
+
+    let mut previous: Option<char> = None;
+    for c in column.chars() {
+        if c.is_uppercase() && previous.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit()) {
+            name.push('_');
+        }
+
+        name.extend(c.to_lowercase());
+        previous = Some(c);
+    }
+
+    if KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else if PATH_KEYWORDS.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod generate {
+        use crate::codegen::rust::{generate, RustOptions};
+        use crate::parse_str;
+
+        #[test]
+        fn just_works() {
+            let tables = parse_str(
+                "@primary_key(id) table Human { int id, varchar(32) name, };
+                schema billing {
+                    // an invoice
+                    //
+                    // sent once a month
+                    @primary_key(id)
+                    table Invoice {
+                        uuid id,
+                        @foreign_key() Human customer, // who pays
+                        @nullable decimal(10, 2) total,
+                        datetime createdAt,
+                        varchar(8) type,
+                    };
+                };",
+            )
+            .unwrap();
+
+            let mut out = Vec::new();
+            let options = RustOptions::default().derive("serde").derive("sqlx");
+            generate(&tables, &options, &mut out).unwrap();
+
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                "// Generated with tsql, manual changes will be overridden.
+
+/// Row of the table `Human`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+pub struct Human {
+    pub id: i32,
+    pub name: String,
+}
+
+/// an invoice
+///
+/// sent once a month
+///
+/// Row of the table `billing.Invoice`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+pub struct BillingInvoice {
+    pub id: uuid::Uuid,
+    /// who pays
+    ///
+    /// References `id` of [`Human`].
+    pub customer_id: i32,
+    pub total: Option<rust_decimal::Decimal>,
+    #[serde(rename = \"createdAt\")]
+    #[sqlx(rename = \"createdAt\")]
+    pub created_at: chrono::NaiveDateTime,
+    pub r#type: String,
+}
+"
+            );
+
+            let mut out = Vec::new();
+            let options = RustOptions {
+                derives: Vec::new(),
+            };
+            generate(&tables, &options, &mut out).unwrap();
+
+            let out = String::from_utf8(out).unwrap();
+            assert!(!out.contains("#["));
+            assert!(out.contains("    pub created_at: chrono::NaiveDateTime,\n"));
+        }
+
+        #[test]
+        fn errors() {
+            let tables = parse_str(
+                "table billing_invoice { int id, };
+                table billing.Invoice { int id, };",
+            )
+            .unwrap();
+            let result = generate(&tables, &RustOptions::default(), &mut Vec::new());
+            assert!(result
+                .unwrap_err()
+                .to_string()
+                .contains("\"BillingInvoice\""));
+
+            let tables = parse_str("table Human { int ownerId, int owner_id, };").unwrap();
+            let result = generate(&tables, &RustOptions::default(), &mut Vec::new());
+            assert!(result.unwrap_err().to_string().contains("\"owner_id\""));
+        }
+    }
+}
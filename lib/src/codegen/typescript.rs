@@ -0,0 +1,463 @@
+//! TypeScript interfaces of the rows of each table, optionally with a [zod](https://zod.dev)
+//! schema to validate them:
+//!
+//! ```
+//! use tsql::codegen::typescript::{generate, TypeScriptOptions};
+//!
+//! let tables = tsql::parse_str(
+//!     "@primary_key(id) @check(status IN ('active', 'blocked'))
+//!     table Human { uuid id, varchar(32) name, @nullable varchar(8) status, };",
+//! )
+//! .unwrap();
+//!
+//! let options = TypeScriptOptions {
+//!     zod: true,
+//!     ..Default::default()
+//! };
+//! let mut out = Vec::new();
+//! generate(&tables, &options, &mut out).unwrap();
+//!
+//! let out = String::from_utf8(out).unwrap();
+//! assert!(out.contains("export type HumanStatus = \"active\" | \"blocked\";"));
+//! assert!(out.contains("    status: HumanStatus | null;"));
+//! assert!(out.contains("    name: z.string().max(32),"));
+//! ```
+//!
+//! `bigint` and `decimal` are strings, as a `number` can't hold every value of them.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::str::FromStr;
+
+use anyhow::{bail, Error, Result};
+
+use crate::check::{check_conditions, Condition, Literal, Operator};
+use crate::codegen::type_names;
+use crate::naming::to_pascal_case;
+use crate::types::{ConstraintKind, DataType, Field, Table, TableCollection};
+
+/// Type of the `date` and `datetime` fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateType {
+    /// An ISO 8601 string, as the values are sent in json.
+    #[default]
+    String,
+    /// A `Date`, zod coerces the value into one.
+    Date,
+}
+
+impl FromStr for DateType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "string" => Ok(DateType::String),
+            "date" => Ok(DateType::Date),
+            _ => bail!("Error: unknown date type {:?}", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeScriptOptions {
+    pub dates: DateType,
+    /// Also writes a zod schema `{Table}Schema` for every table.
+    pub zod: bool,
+}
+
+/// Writes an interface for every table, with a property for every column in the order of
+/// declaration.
+///
+/// A column restricted with `@check(column IN (values))` gets a union type of the values,
+/// named after the table and the column, e.g. `HumanStatus`. Fails if two tables or a table
+/// and a union end up with the same name.
+pub fn generate<W: Write>(
+    tables: &TableCollection,
+    options: &TypeScriptOptions,
+    buffer: &mut W,
+) -> Result<()> {
+    let names = type_names(tables)?;
+    let mut defined = names.values().cloned().collect::<HashSet<_>>();
+
+    writeln!(
+        buffer,
+        "// Generated with tsql, manual changes will be overridden."
+    )?;
+    if options.zod {
+        writeln!(buffer)?;
+        writeln!(buffer, "import {{ z }} from \"zod\";")?;
+    }
+
+    for (qualified_name, table) in tables {
+        let name = &names[qualified_name];
+        let checks = Checks::new(table);
+
+        writeln!(buffer)?;
+        for (column, values) in &checks.enums {
+            let union = format!("{}{}", name, to_pascal_case(column));
+            if !defined.insert(union.clone()) {
+                bail!(
+                    "Error: The union of the field {:?} of the table {:?} is named {:?} like \
+                     another type",
+                    column,
+                    qualified_name,
+                    union
+                );
+            }
+
+            let values = values.iter().map(literal).collect::<Vec<_>>();
+            writeln!(buffer, "export type {} = {};", union, values.join(" | "))?;
+            writeln!(buffer)?;
+        }
+
+        let mut doc = table
+            .comment()
+            .map(|comment| comment.lines().map(str::to_string).collect::<Vec<_>>())
+            .unwrap_or_default();
+        if !doc.is_empty() {
+            doc.push(String::new());
+        }
+        doc.push(format!("Row of the table `{}`.", qualified_name));
+        write_doc(&doc, "", buffer)?;
+
+        writeln!(buffer, "export interface {} {{", name)?;
+        for field in table.fields() {
+            let mut doc = field
+                .comment()
+                .map(|comment| comment.lines().map(str::to_string).collect::<Vec<_>>())
+                .unwrap_or_default();
+            if let Some((target, key)) = field.references(tables) {
+                if !doc.is_empty() {
+                    doc.push(String::new());
+                }
+                doc.push(format!(
+                    "References `{}` of {{@link {}}}.",
+                    key.name(),
+                    names[&target.qualified_name()]
+                ));
+            }
+            write_doc(&doc, "    ", buffer)?;
+
+            let datatype = match checks.enums.contains_key(field.name()) {
+                true => format!("{}{}", name, to_pascal_case(field.name())),
+                false => field_type(field, options.dates).to_string(),
+            };
+            match field.is_nullable() {
+                true => writeln!(buffer, "    {}: {} | null;", field.name(), datatype)?,
+                false => writeln!(buffer, "    {}: {};", field.name(), datatype)?,
+            }
+        }
+        writeln!(buffer, "}}")?;
+
+        if options.zod {
+            writeln!(buffer)?;
+            writeln!(buffer, "export const {}Schema = z.object({{", name)?;
+            for field in table.fields() {
+                writeln!(
+                    buffer,
+                    "    {}: {},",
+                    field.name(),
+                    zod_type(field, &checks, options.dates)
+                )?;
+            }
+            writeln!(buffer, "}});")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The `@check` expressions of a table which can be expressed in TypeScript or zod.
+struct Checks {
+    /// values: `column -> allowed values`
+    enums: BTreeMap<String, Vec<Literal>>,
+    /// values: `(column, condition)` without the enums
+    conditions: Vec<(String, Condition)>,
+}
+
+impl Checks {
+    fn new(table: &Table) -> Self {
+        let mut checks = Checks {
+            enums: BTreeMap::new(),
+            conditions: Vec::new(),
+        };
+
+        for constraint in table.constraints() {
+            let ConstraintKind::Check(expression) = constraint.kind() else {
+                continue;
+            };
+
+            for (column, condition) in check_conditions(expression) {
+                if table.get_field(&column).is_none() {
+                    continue;
+                }
+
+                match condition {
+                    Condition::In(values) if !values.is_empty() => {
+                        checks.enums.insert(column, values);
+                    }
+                    Condition::In(_) => (),
+                    condition => checks.conditions.push((column, condition)),
+                }
+            }
+        }
+
+        checks
+    }
+}
+
+fn write_doc<W: Write>(lines: &[String], indent: &str, buffer: &mut W) -> Result<()> {
+    match lines {
+        [] => (),
+        [line] => writeln!(buffer, "{}/** {} */", indent, line)?,
+        lines => {
+            writeln!(buffer, "{}/**", indent)?;
+            for line in lines {
+                match line.is_empty() {
+                    true => writeln!(buffer, "{} *", indent)?,
+                    false => writeln!(buffer, "{} * {}", indent, line)?,
+                }
+            }
+            writeln!(buffer, "{} */", indent)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn field_type(field: &Field, dates: DateType) -> &'static str {
+    match field.datatype() {
+        DataType::Int | DataType::Double | DataType::Float => "number",
+        DataType::Bool => "boolean",
+        DataType::Date | DataType::DateTime if dates == DateType::Date => "Date",
+        DataType::BigInt
+        | DataType::Decimal(_, _)
+        | DataType::Date
+        | DataType::DateTime
+        | DataType::Time
+        | DataType::Uuid
+        | DataType::VarChar(_)
+        | DataType::Char(_)
+        | DataType::Text(_) => "string",
+    }
+}
+
+fn zod_type(field: &Field, checks: &Checks, dates: DateType) -> String {
+    let mut zod = match checks.enums.get(field.name()) {
+        Some(values) if values.iter().all(|value| matches!(value, Literal::Text(_))) => {
+            let values = values.iter().map(literal).collect::<Vec<_>>();
+            format!("z.enum([{}])", values.join(", "))
+        }
+        Some(values) if values.len() == 1 => format!("z.literal({})", literal(&values[0])),
+        Some(values) => {
+            let values = values
+                .iter()
+                .map(|value| format!("z.literal({})", literal(value)))
+                .collect::<Vec<_>>();
+            format!("z.union([{}])", values.join(", "))
+        }
+        None => zod_datatype(field.datatype(), dates),
+    };
+
+    if !checks.enums.contains_key(field.name()) {
+        let conditions = checks
+            .conditions
+            .iter()
+            .filter(|(column, _)| column == field.name());
+        for (_, condition) in conditions {
+            if let Some(method) = zod_condition(field.datatype(), condition) {
+                zod.push_str(&method);
+            }
+        }
+    }
+
+    if field.is_nullable() {
+        zod.push_str(".nullable()");
+    }
+
+    zod
+}
+
+fn zod_datatype(datatype: &DataType, dates: DateType) -> String {
+    match (datatype, dates) {
+        (DataType::Int, _) => "z.number().int()".to_string(),
+        (DataType::Double | DataType::Float, _) => "z.number()".to_string(),
+        (DataType::Bool, _) => "z.boolean()".to_string(),
+        (DataType::Date | DataType::DateTime, DateType::Date) => "z.coerce.date()".to_string(),
+        (DataType::Date, DateType::String) => "z.string().date()".to_string(),
+        (DataType::DateTime, DateType::String) => {
+            "z.string().datetime({ local: true })".to_string()
+        }
+        (DataType::Time, _) => "z.string().time()".to_string(),
+        (DataType::Uuid, _) => "z.string().uuid()".to_string(),
+        (DataType::VarChar(length) | DataType::Text(length), _) => {
+            format!("z.string().max({})", length)
+        }
+        (DataType::Char(length), _) => format!("z.string().max({})", length),
+        (DataType::BigInt | DataType::Decimal(_, _), _) => "z.string()".to_string(),
+    }
+}
+
+/// Method which restricts the zod type like the condition, e.g. `.min(1)`.
+fn zod_condition(datatype: &DataType, condition: &Condition) -> Option<String> {
+    let text = matches!(
+        datatype,
+        DataType::VarChar(_) | DataType::Char(_) | DataType::Text(_)
+    );
+    let number = matches!(datatype, DataType::Int | DataType::Double | DataType::Float);
+
+    match condition {
+        Condition::Compare(Operator::NotEqual, Literal::Text(value))
+            if text && value.is_empty() =>
+        {
+            Some(".min(1)".to_string())
+        }
+        Condition::Compare(operator, bound @ (Literal::Integer(_) | Literal::Float(_)))
+            if number =>
+        {
+            let method = match operator {
+                Operator::Less => "lt",
+                Operator::LessEqual => "lte",
+                Operator::Greater => "gt",
+                Operator::GreaterEqual => "gte",
+                Operator::NotEqual => return None,
+            };
+
+            Some(format!(".{}({})", method, literal(bound)))
+        }
+        Condition::Length(operator, Literal::Integer(length)) if text => {
+            let (method, length) = match operator {
+                Operator::Less => ("max", length.checked_sub(1)?),
+                Operator::LessEqual => ("max", *length),
+                Operator::Greater => ("min", length.checked_add(1)?),
+                Operator::GreaterEqual => ("min", *length),
+                Operator::NotEqual => return None,
+            };
+
+            Some(format!(".{}({})", method, length.max(0)))
+        }
+        _ => None,
+    }
+}
+
+/// The literal as a TypeScript value.
+fn literal(literal: &Literal) -> String {
+    match literal {
+        Literal::Text(text) => format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"")),
+        Literal::Integer(integer) => integer.to_string(),
+        Literal::Float(float) => float.to_string(),
+        Literal::Bool(bool) => bool.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod generate {
+        use crate::codegen::typescript::{generate, DateType, TypeScriptOptions};
+        use crate::parse_str;
+
+        #[test]
+        fn just_works() {
+            let tables = parse_str(
+                "@primary_key(id) table Human { int id, varchar(32) name, };
+                // a car
+                //
+                // with a plate
+                @primary_key(plate)
+                @check(length(plate) > 2 AND color IN ('red', 'blue'))
+                @check(wheels IN (3, 4))
+                @check(weight >= 0)
+                table Car {
+                    varchar(16) plate,
+                    @nullable @foreign_key() Human owner, // who drives
+                    @nullable varchar(8) color,
+                    int wheels,
+                    double weight,
+                    datetime built,
+                    decimal(10, 2) price,
+                };",
+            )
+            .unwrap();
+
+            let mut out = Vec::new();
+            generate(&tables, &TypeScriptOptions::default(), &mut out).unwrap();
+
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                "// Generated with tsql, manual changes will be overridden.
+
+export type CarColor = \"red\" | \"blue\";
+
+export type CarWheels = 3 | 4;
+
+/**
+ * a car
+ *
+ * with a plate
+ *
+ * Row of the table `Car`.
+ */
+export interface Car {
+    plate: string;
+    /**
+     * who drives
+     *
+     * References `id` of {@link Human}.
+     */
+    owner_id: number | null;
+    color: CarColor | null;
+    wheels: CarWheels;
+    weight: number;
+    built: string;
+    price: string;
+}
+
+/** Row of the table `Human`. */
+export interface Human {
+    id: number;
+    name: string;
+}
+"
+            );
+
+            let options = TypeScriptOptions {
+                dates: DateType::Date,
+                zod: true,
+            };
+            let mut out = Vec::new();
+            generate(&tables, &options, &mut out).unwrap();
+
+            let out = String::from_utf8(out).unwrap();
+            assert!(out.contains("\nimport { z } from \"zod\";\n"));
+            assert!(out.contains("    built: Date;\n"));
+            assert!(out.contains(
+                "export const CarSchema = z.object({
+    plate: z.string().max(16).min(3),
+    owner_id: z.number().int().nullable(),
+    color: z.enum([\"red\", \"blue\"]).nullable(),
+    wheels: z.union([z.literal(3), z.literal(4)]),
+    weight: z.number().gte(0),
+    built: z.coerce.date(),
+    price: z.string(),
+});"
+            ));
+        }
+
+        #[test]
+        fn errors() {
+            let tables = parse_str(
+                "@check(status IN ('a', 'b')) table Human { varchar(8) status, };
+                table HumanStatus { int id, };",
+            )
+            .unwrap();
+            let result = generate(&tables, &TypeScriptOptions::default(), &mut Vec::new());
+            assert!(result.unwrap_err().to_string().contains("\"HumanStatus\""));
+
+            let tables = parse_str("table a_b { int id, }; table AB { int id, };").unwrap();
+            let result = generate(&tables, &TypeScriptOptions::default(), &mut Vec::new());
+            assert!(result.is_err());
+
+            assert!("Date".parse::<DateType>().is_err());
+        }
+    }
+}
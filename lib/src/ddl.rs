@@ -0,0 +1,1273 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Result};
+use nom::branch::alt;
+use nom::bytes::complete::{is_not, tag, tag_no_case, take_while1};
+use nom::character::complete::{char, digit1, multispace0, multispace1, satisfy};
+use nom::combinator::{map, not, opt, recognize, value};
+use nom::multi::{many0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+
+use crate::naming::NamingConvention;
+use crate::parse_str;
+use crate::parser::helper::balanced_parentheses;
+use crate::parser::types::TableExtra;
+use crate::printer::print_tables;
+use crate::types::{
+    qualified_name, unqualified_name, Constraint, ConstraintKind, DataType, Field, ForeignKey,
+    Table, TableCollection,
+};
+
+/// Length of `text` and `varchar` columns without a length, as tsql always needs one.
+const DEFAULT_TEXT_LENGTH: u16 = u16::MAX;
+
+/// Parses the `CREATE TABLE` and `CREATE UNIQUE INDEX` statements of a sql file into tables,
+/// every other statement is skipped.
+///
+/// Foreign keys which reference the primary key of a table are turned back into
+/// `@foreign_key()` fields, with the same column and constraint names as in the sql. Everything
+/// which can't be expressed in tsql, e.g. a foreign key to a unique column or `ON DELETE
+/// CASCADE`, is left out and reported in the returned warnings.
+pub fn parse_sql(content: &str) -> Result<(TableCollection, Vec<String>)> {
+    let mut warnings = Vec::new();
+    let mut tables: Vec<SqlTable> = Vec::new();
+
+    for statement in split_statements(content) {
+        let statement = statement.trim();
+        if statement.is_empty() {
+            continue;
+        }
+
+        if let Some(table) = parse_create_table(statement, &mut warnings)? {
+            tables.push(table);
+        } else if let Ok((rest, (name, (schema, table_name), columns))) =
+            create_unique_index(statement)
+        {
+            let qualified = qualified_name(schema, table_name);
+
+            match tables
+                .iter_mut()
+                .find(|table| table.qualified_name() == qualified)
+            {
+                Some(table) if rest.trim().is_empty() => table.unique.push((
+                    columns.iter().map(|column| column.to_string()).collect(),
+                    Some(name.to_string()),
+                )),
+                _ => warnings.push(format!("Skipped the unique index {:?}", name)),
+            }
+        } else {
+            let line = statement.lines().next().unwrap_or_default();
+            warnings.push(format!("Skipped the statement {:?}", line));
+        }
+    }
+
+    let tables = resolve_tables(tables, &mut warnings)?;
+
+    Ok((tables, warnings))
+}
+
+/// Parses a `CREATE TABLE` statement, `None` if it's another statement.
+pub(crate) fn parse_create_table(
+    statement: &str,
+    warnings: &mut Vec<String>,
+) -> Result<Option<SqlTable>> {
+    match create_table(statement) {
+        Ok((rest, (schema, name, items))) => {
+            let table = SqlTable::new(schema, name, items, warnings)?;
+
+            if !rest.trim().is_empty() {
+                warnings.push(format!(
+                    "Ignored the options {:?} of table {:?}",
+                    rest.trim(),
+                    table.qualified_name()
+                ));
+            }
+
+            Ok(Some(table))
+        }
+        Err(_) if create_table_start(statement).is_ok() => {
+            let line = statement.lines().next().unwrap_or_default();
+            bail!("Error: Couldn't parse the table {:?}", line);
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parses a type like `VARCHAR(64)` and maps it to the closest tsql type.
+#[cfg(feature = "sqlite")]
+pub(crate) fn parse_datatype(
+    table: &str,
+    column: &str,
+    datatype: &str,
+    warnings: &mut Vec<String>,
+) -> Result<DataType> {
+    match data_type(datatype.trim()) {
+        Ok((rest, raw)) if rest.trim().is_empty() => map_datatype(table, column, &raw, warnings),
+        _ => bail!(
+            "Error: Column {:?} in table {:?} has the unsupported type {:?}",
+            column,
+            table,
+            datatype
+        ),
+    }
+}
+
+/// A table as it's defined in sql, before the foreign keys are resolved.
+#[derive(Debug, Default)]
+pub(crate) struct SqlTable {
+    pub(crate) schema: Option<String>,
+    pub(crate) name: String,
+    pub(crate) columns: Vec<SqlColumn>,
+    /// values: `(fields, constraint name)`
+    pub(crate) primary_key: Option<(Vec<String>, Option<String>)>,
+    pub(crate) unique: Vec<(Vec<String>, Option<String>)>,
+    pub(crate) check: Vec<(String, Option<String>)>,
+    pub(crate) foreign_keys: Vec<SqlForeignKey>,
+}
+
+#[derive(Debug)]
+pub(crate) struct SqlColumn {
+    pub(crate) name: String,
+    pub(crate) datatype: DataType,
+    pub(crate) nullable: bool,
+    pub(crate) default: Option<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct SqlForeignKey {
+    pub(crate) name: Option<String>,
+    pub(crate) columns: Vec<String>,
+    pub(crate) table: String,
+    /// empty if the primary key of `table` is referenced
+    pub(crate) references: Vec<String>,
+}
+
+impl SqlTable {
+    fn new(
+        schema: Option<&str>,
+        name: &str,
+        items: Vec<TableItem>,
+        warnings: &mut Vec<String>,
+    ) -> Result<Self> {
+        let mut table = SqlTable {
+            schema: schema.map(tsql_identifier).transpose()?,
+            name: tsql_identifier(name)?,
+            ..Default::default()
+        };
+
+        for item in items {
+            match item {
+                TableItem::Column(column) => table.add_column(column, warnings)?,
+                TableItem::Constraint(name, constraint) => {
+                    table.add_constraint(name.map(|item| item.to_string()), constraint, warnings)?
+                }
+                TableItem::Index(name) => warnings.push(format!(
+                    "Skipped the index {:?} of table {:?}",
+                    name,
+                    table.qualified_name()
+                )),
+            }
+        }
+
+        // primary keys can't be null, even without `NOT NULL`
+        if let Some((fields, _)) = &table.primary_key {
+            for column in &mut table.columns {
+                if fields.contains(&column.name) {
+                    column.nullable = false;
+                }
+            }
+        }
+
+        Ok(table)
+    }
+
+    pub(crate) fn qualified_name(&self) -> String {
+        qualified_name(self.schema.as_deref(), &self.name)
+    }
+
+    fn add_column(&mut self, column: RawColumn, warnings: &mut Vec<String>) -> Result<()> {
+        let name = tsql_identifier(column.name)?;
+        let mut sql_column = SqlColumn {
+            datatype: map_datatype(&self.qualified_name(), &name, &column.datatype, warnings)?,
+            name: name.clone(),
+            nullable: true,
+            default: None,
+        };
+
+        let mut constraint_name = None;
+        for constraint in column.constraints {
+            let constraint = match constraint {
+                ColumnConstraint::Name(name) => {
+                    constraint_name = Some(name.to_string());
+                    continue;
+                }
+                ColumnConstraint::NotNull => {
+                    sql_column.nullable = false;
+                    continue;
+                }
+                ColumnConstraint::Null => {
+                    sql_column.nullable = true;
+                    continue;
+                }
+                ColumnConstraint::Default(expression) => {
+                    sql_column.default = Some(expression.to_string());
+                    continue;
+                }
+                ColumnConstraint::Ignored(clause) => {
+                    warnings.push(format!(
+                        "Ignored {:?} of column {:?} in table {:?}",
+                        clause,
+                        name,
+                        self.qualified_name()
+                    ));
+                    continue;
+                }
+                ColumnConstraint::PrimaryKey => RawConstraint::PrimaryKey(vec![column.name]),
+                ColumnConstraint::Unique => RawConstraint::Unique(vec![column.name]),
+                ColumnConstraint::Check(expression) => RawConstraint::Check(expression),
+                ColumnConstraint::References(reference) => {
+                    RawConstraint::ForeignKey(vec![column.name], reference)
+                }
+            };
+
+            self.add_constraint(constraint_name.take(), constraint, warnings)?;
+        }
+
+        self.columns.push(sql_column);
+
+        Ok(())
+    }
+
+    fn add_constraint(
+        &mut self,
+        name: Option<String>,
+        constraint: RawConstraint,
+        warnings: &mut Vec<String>,
+    ) -> Result<()> {
+        let name = name.as_deref().map(tsql_identifier).transpose()?;
+        let identifiers = |columns: Vec<&str>| {
+            columns
+                .into_iter()
+                .map(tsql_identifier)
+                .collect::<Result<Vec<_>>>()
+        };
+
+        match constraint {
+            RawConstraint::PrimaryKey(columns) => {
+                if self.primary_key.is_some() {
+                    bail!(
+                        "Error: Table {:?} has more than one primary key",
+                        self.qualified_name()
+                    );
+                }
+
+                self.primary_key = Some((identifiers(columns)?, name));
+            }
+            RawConstraint::Unique(columns) => self.unique.push((identifiers(columns)?, name)),
+            RawConstraint::Check(expression) => self.check.push((expression.to_string(), name)),
+            RawConstraint::ForeignKey(columns, reference) => {
+                for action in reference.actions {
+                    warnings.push(format!(
+                        "Ignored {:?} of the foreign key {:?} in table {:?}",
+                        action,
+                        columns,
+                        self.qualified_name()
+                    ));
+                }
+
+                let (schema, table) = reference.table;
+
+                self.foreign_keys.push(SqlForeignKey {
+                    name,
+                    columns: identifiers(columns)?,
+                    table: qualified_name(
+                        schema.map(tsql_identifier).transpose()?.as_deref(),
+                        &tsql_identifier(table)?,
+                    ),
+                    references: identifiers(reference.columns)?,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Words which are reserved by Postgres and MySql, they can only be used as a name if they are
+/// quoted. The generated sql doesn't quote names, so they are rejected.
+const RESERVED_WORDS: &[&str] = &[
+    "ALL",
+    "ANALYZE",
+    "AND",
+    "ARRAY",
+    "AS",
+    "ASC",
+    "BOTH",
+    "CASE",
+    "CHECK",
+    "COLLATE",
+    "COLUMN",
+    "CONSTRAINT",
+    "CREATE",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "DEFAULT",
+    "DESC",
+    "DISTINCT",
+    "ELSE",
+    "EXCEPT",
+    "FALSE",
+    "FETCH",
+    "FOR",
+    "FOREIGN",
+    "FROM",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "IN",
+    "INTERSECT",
+    "INTO",
+    "LATERAL",
+    "LEADING",
+    "LIMIT",
+    "LOCALTIME",
+    "LOCALTIMESTAMP",
+    "NOT",
+    "NULL",
+    "ON",
+    "OR",
+    "ORDER",
+    "PRIMARY",
+    "REFERENCES",
+    "SELECT",
+    "TABLE",
+    "THEN",
+    "TO",
+    "TRAILING",
+    "TRUE",
+    "UNION",
+    "UNIQUE",
+    "USING",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+];
+
+/// Checks that `name` can be written in tsql, quoted sql identifiers can contain anything.
+pub(crate) fn tsql_identifier(name: &str) -> Result<String> {
+    let mut chars = name.chars();
+
+    let valid = matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+
+    if !valid {
+        bail!("Error: The name {:?} can't be used in tsql", name);
+    }
+
+    if RESERVED_WORDS
+        .iter()
+        .any(|word| word.eq_ignore_ascii_case(name))
+    {
+        bail!(
+            "Error: The name {:?} is a reserved word of sql, which tsql doesn't quote",
+            name
+        );
+    }
+
+    Ok(name.to_string())
+}
+
+fn map_datatype(
+    table: &str,
+    column: &str,
+    datatype: &RawType,
+    warnings: &mut Vec<String>,
+) -> Result<DataType> {
+    let name = datatype.name.to_lowercase();
+    let arguments = datatype
+        .arguments
+        .iter()
+        .map(|argument| argument.parse::<u32>())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut length = |default: u16| match arguments.first() {
+        Some(length) => u16::try_from(*length).ok(),
+        None => {
+            warnings.push(format!(
+                "Column {:?} in table {:?} has no length, using {}",
+                column, table, default
+            ));
+            Some(default)
+        }
+    };
+
+    let mapped = match name.as_str() {
+        "tinyint" if arguments == [1] => Some(DataType::Bool),
+        "int" | "integer" | "int4" | "smallint" | "int2" | "tinyint" | "mediumint" | "serial"
+        | "smallserial" => Some(DataType::Int),
+        "bigint" | "int8" | "bigserial" => Some(DataType::BigInt),
+        "bool" | "boolean" => Some(DataType::Bool),
+        "date" => Some(DataType::Date),
+        "datetime" | "timestamp" | "timestamptz" => Some(DataType::DateTime),
+        "time" | "timetz" => Some(DataType::Time),
+        "double" | "double precision" | "float8" => Some(DataType::Double),
+        "float" | "real" | "float4" => Some(DataType::Float),
+        "uuid" => Some(DataType::Uuid),
+
+        "varchar" | "character varying" | "nvarchar" => {
+            length(DEFAULT_TEXT_LENGTH).map(DataType::VarChar)
+        }
+        "char" | "character" | "nchar" => match arguments.first() {
+            Some(length) => u8::try_from(*length).ok().map(DataType::Char),
+            None => Some(DataType::Char(1)),
+        },
+        "text" | "tinytext" | "mediumtext" | "longtext" | "clob" => {
+            length(DEFAULT_TEXT_LENGTH).map(DataType::Text)
+        }
+
+        "decimal" | "numeric" => match arguments.as_slice() {
+            [precision] => u8::try_from(*precision)
+                .ok()
+                .map(|precision| DataType::Decimal(precision, 0)),
+            [precision, scale] => match (u8::try_from(*precision), u8::try_from(*scale)) {
+                (Ok(precision), Ok(scale)) => Some(DataType::Decimal(precision, scale)),
+                _ => None,
+            },
+            _ => {
+                warnings.push(format!(
+                    "Column {:?} in table {:?} has no precision, using decimal(10, 0)",
+                    column, table
+                ));
+                Some(DataType::Decimal(10, 0))
+            }
+        },
+
+        _ => None,
+    };
+
+    match mapped {
+        Some(mapped) => Ok(mapped),
+        None => bail!(
+            "Error: Column {:?} in table {:?} has the unsupported type {:?}",
+            column,
+            table,
+            datatype.name
+        ),
+    }
+}
+
+/// Turns the parsed sql tables into tables, foreign keys which can't be expressed in tsql are
+/// removed and their columns are kept as normal fields.
+pub(crate) fn resolve_tables(
+    tables: Vec<SqlTable>,
+    warnings: &mut Vec<String>,
+) -> Result<TableCollection> {
+    let tables = build_tables(tables, warnings)?;
+
+    // the printed tables run through the same validation as every tsql file
+    let mut printed = Vec::new();
+    print_tables(&tables, &mut printed)?;
+
+    parse_str(&String::from_utf8(printed)?)
+}
+
+fn build_tables(tables: Vec<SqlTable>, warnings: &mut Vec<String>) -> Result<TableCollection> {
+    let naming = NamingConvention::default();
+
+    let primary_keys = tables
+        .iter()
+        .map(|table| {
+            let fields = table
+                .primary_key
+                .iter()
+                .flat_map(|(fields, _)| fields)
+                .filter_map(|field| table.columns.iter().find(|column| &column.name == field))
+                .collect::<Vec<_>>();
+
+            (table.qualified_name(), fields)
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut collection = TableCollection::new();
+
+    for sql_table in &tables {
+        let qualified = sql_table.qualified_name();
+        let mut table = Table {
+            name: sql_table.name.clone(),
+            schema: sql_table.schema.clone(),
+            ..Default::default()
+        };
+
+        for column in &sql_table.columns {
+            let field = Field {
+                name: column.name.clone(),
+                datatype: column.datatype,
+                nullable: column.nullable,
+                default: column.default.clone(),
+                position: Default::default(),
+                span: Default::default(),
+                comment: None,
+                foreign_key_reference: None,
+                renamed_from: None,
+            };
+
+            if table.fields.insert(column.name.clone(), field).is_some() {
+                bail!(
+                    "Error: Table {:?} has more than one column with the name {:?}",
+                    qualified,
+                    column.name
+                );
+            }
+            table.field_order.push(column.name.clone());
+        }
+
+        let mut claimed = HashSet::new();
+        for sql_foreign_key in &sql_table.foreign_keys {
+            let primary_key = match primary_keys.get(&sql_foreign_key.table) {
+                Some(primary_key) if !primary_key.is_empty() => primary_key,
+                _ => {
+                    warnings.push(format!(
+                        "Skipped the foreign key {:?} of table {:?}, the referenced table {:?} doesn't exist or has no primary key",
+                        sql_foreign_key.columns, qualified, sql_foreign_key.table
+                    ));
+                    continue;
+                }
+            };
+            let references = primary_key
+                .iter()
+                .map(|field| field.name.clone())
+                .collect::<Vec<_>>();
+
+            let columns = sql_foreign_key
+                .columns
+                .iter()
+                .map(|column| table.fields.get(column))
+                .collect::<Option<Vec<_>>>();
+
+            let collapsible = match columns {
+                Some(columns) => {
+                    (sql_foreign_key.references.is_empty()
+                        || sql_foreign_key.references == references)
+                        && columns.len() == primary_key.len()
+                        && columns
+                            .iter()
+                            .zip(primary_key.iter())
+                            .all(|(column, field)| column.datatype == field.datatype)
+                        && columns.iter().all(|column| {
+                            column.default.is_none()
+                                && column.nullable == columns[0].nullable
+                                && !claimed.contains(&column.name)
+                        })
+                }
+                None => false,
+            };
+
+            if !collapsible {
+                warnings.push(format!(
+                    "Skipped the foreign key {:?} of table {:?}, it can't be written as a @foreign_key() field",
+                    sql_foreign_key.columns, qualified
+                ));
+                continue;
+            }
+
+            let is_self_reference = sql_foreign_key.table == qualified;
+            let target = unqualified_name(&sql_foreign_key.table);
+            let field_name = foreign_key_field_name(&table, sql_foreign_key, &references);
+
+            for (column, field) in sql_foreign_key.columns.iter().zip(primary_key.iter()) {
+                let generated = table.fields.get_mut(column).unwrap();
+
+                if is_self_reference && !generated.nullable {
+                    warnings.push(format!(
+                        "Column {:?} of table {:?} references its own table and is nullable in tsql",
+                        column, qualified
+                    ));
+                    generated.nullable = true;
+                }
+
+                generated.foreign_key_reference =
+                    Some((sql_foreign_key.table.clone(), field.name.clone()));
+                claimed.insert(column.clone());
+            }
+
+            table.foreign_keys.push(ForeignKey {
+                constraint: sql_foreign_key.name.clone().unwrap_or_else(|| {
+                    naming.foreign_key_name(&sql_table.name, &field_name, target)
+                }),
+                name: field_name,
+                table: sql_foreign_key.table.clone(),
+                columns: sql_foreign_key.columns.clone(),
+                references,
+                renamed_from: None,
+            });
+        }
+
+        let mut extra = TableExtra::default();
+
+        if let Some((fields, name)) = &sql_table.primary_key {
+            extra.primary_key = fields.clone();
+            table.constraints.push(Constraint {
+                name: name
+                    .clone()
+                    .unwrap_or_else(|| naming.primary_key_name(&sql_table.name)),
+                kind: ConstraintKind::PrimaryKey(fields.clone()),
+            });
+        }
+
+        for (fields, name) in &sql_table.unique {
+            table.constraints.push(Constraint {
+                name: name
+                    .clone()
+                    .unwrap_or_else(|| naming.unique_name(&sql_table.name, fields)),
+                kind: ConstraintKind::Unique(fields.clone()),
+            });
+        }
+
+        for (index, (expression, name)) in sql_table.check.iter().enumerate() {
+            table.constraints.push(Constraint {
+                name: name
+                    .clone()
+                    .unwrap_or_else(|| naming.check_name(&sql_table.name, index + 1)),
+                kind: ConstraintKind::Check(expression.clone()),
+            });
+        }
+
+        table.extra = extra;
+
+        if collection.insert(qualified.clone(), table).is_some() {
+            bail!("Error: Table {:?} is defined more than once", qualified);
+        }
+    }
+
+    Ok(collection)
+}
+
+/// Name of the `@foreign_key()` field of the columns, e.g. `owner` for `owner_id`, otherwise
+/// the name of the referenced table.
+fn foreign_key_field_name(
+    table: &Table,
+    foreign_key: &SqlForeignKey,
+    references: &[String],
+) -> String {
+    let prefixes = foreign_key
+        .columns
+        .iter()
+        .zip(references.iter())
+        .map(|(column, reference)| column.strip_suffix(&format!("_{}", reference)))
+        .collect::<Vec<_>>();
+
+    let name = match prefixes.first() {
+        Some(Some(prefix)) if prefixes.iter().all(|item| *item == Some(*prefix)) => {
+            prefix.to_string()
+        }
+        _ => unqualified_name(&foreign_key.table).to_lowercase(),
+    };
+
+    let mut unique_name = name.clone();
+    let mut index = 1;
+    while table
+        .foreign_keys
+        .iter()
+        .any(|item| item.name == unique_name)
+    {
+        index += 1;
+        unique_name = format!("{}_{}", name, index);
+    }
+
+    unique_name
+}
+
+/// Splits the content at every `;` which isn't part of a string or a comment, comments are
+/// removed.
+fn split_statements(content: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut statement = String::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                statement.push(c);
+
+                for inner in chars.by_ref() {
+                    statement.push(inner);
+
+                    if inner == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for inner in chars.by_ref() {
+                    if inner == '\n' {
+                        break;
+                    }
+                }
+                statement.push('\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+
+                let mut previous = ' ';
+                for inner in chars.by_ref() {
+                    if previous == '*' && inner == '/' {
+                        break;
+                    }
+                    previous = inner;
+                }
+                statement.push(' ');
+            }
+            ';' => statements.push(std::mem::take(&mut statement)),
+            _ => statement.push(c),
+        }
+    }
+
+    statements.push(statement);
+
+    statements
+}
+
+#[derive(Debug)]
+enum TableItem<'a> {
+    Column(RawColumn<'a>),
+    /// values: `(name, constraint)`
+    Constraint(Option<&'a str>, RawConstraint<'a>),
+    /// a mysql `KEY name (columns)`
+    Index(&'a str),
+}
+
+#[derive(Debug)]
+struct RawColumn<'a> {
+    name: &'a str,
+    datatype: RawType<'a>,
+    constraints: Vec<ColumnConstraint<'a>>,
+}
+
+#[derive(Debug)]
+struct RawType<'a> {
+    /// e.g. `varchar` or `double precision`
+    name: String,
+    arguments: Vec<&'a str>,
+}
+
+#[derive(Debug, Clone)]
+enum ColumnConstraint<'a> {
+    Name(&'a str),
+    NotNull,
+    Null,
+    PrimaryKey,
+    Unique,
+    Default(&'a str),
+    Check(&'a str),
+    References(RawReference<'a>),
+    /// a clause which can't be expressed in tsql, e.g. `AUTO_INCREMENT`
+    Ignored(&'a str),
+}
+
+#[derive(Debug)]
+enum RawConstraint<'a> {
+    PrimaryKey(Vec<&'a str>),
+    Unique(Vec<&'a str>),
+    Check(&'a str),
+    ForeignKey(Vec<&'a str>, RawReference<'a>),
+}
+
+#[derive(Debug, Clone)]
+struct RawReference<'a> {
+    table: (Option<&'a str>, &'a str),
+    columns: Vec<&'a str>,
+    /// e.g. `ON DELETE CASCADE`
+    actions: Vec<&'a str>,
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Matches the keyword case insensitive, as long as it isn't the start of a longer word.
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(tag_no_case(word), not(satisfy(is_identifier_char)))
+}
+
+/// Parses an identifier, which can be quoted with `"`, `` ` `` or `[]`.
+fn identifier(input: &str) -> IResult<&str, &str> {
+    alt((
+        delimited(char('"'), take_while1(|c| c != '"'), char('"')),
+        delimited(char('`'), take_while1(|c| c != '`'), char('`')),
+        delimited(char('['), take_while1(|c| c != ']'), char(']')),
+        take_while1(is_identifier_char),
+    ))(input)
+}
+
+/// Parses `schema.name` or `name`.
+fn qualified_identifier(input: &str) -> IResult<&str, (Option<&str>, &str)> {
+    map(
+        pair(identifier, opt(preceded(char('.'), identifier))),
+        |(first, second)| match second {
+            Some(name) => (Some(first), name),
+            None => (None, first),
+        },
+    )(input)
+}
+
+/// Parses `(a, b DESC)`, the sort order is ignored.
+fn identifier_list(input: &str) -> IResult<&str, Vec<&str>> {
+    delimited(
+        pair(char('('), multispace0),
+        separated_list1(
+            tuple((multispace0, char(','), multispace0)),
+            terminated(
+                identifier,
+                opt(preceded(
+                    multispace1,
+                    alt((keyword("ASC"), keyword("DESC"))),
+                )),
+            ),
+        ),
+        pair(multispace0, char(')')),
+    )(input)
+}
+
+fn if_not_exists(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        keyword("IF"),
+        multispace1,
+        keyword("NOT"),
+        multispace1,
+        keyword("EXISTS"),
+        multispace1,
+    )))(input)
+}
+
+/// Parses `CREATE [TEMPORARY] TABLE`.
+fn create_table_start(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        keyword("CREATE"),
+        multispace1,
+        opt(terminated(
+            alt((keyword("TEMPORARY"), keyword("TEMP"))),
+            multispace1,
+        )),
+        keyword("TABLE"),
+    )))(input)
+}
+
+type RawCreateTable<'a> = (Option<&'a str>, &'a str, Vec<TableItem<'a>>);
+
+/// Parses `CREATE TABLE name (...)`, the table options after the closing parenthesis are
+/// returned as the remaining input.
+fn create_table(input: &str) -> IResult<&str, RawCreateTable<'_>> {
+    map(
+        tuple((
+            create_table_start,
+            multispace1,
+            opt(if_not_exists),
+            qualified_identifier,
+            multispace0,
+            delimited(
+                pair(char('('), multispace0),
+                separated_list1(tuple((multispace0, char(','), multispace0)), table_item),
+                pair(multispace0, char(')')),
+            ),
+        )),
+        |(_, _, _, (schema, name), _, items)| (schema, name, items),
+    )(input)
+}
+
+type RawUniqueIndex<'a> = (&'a str, (Option<&'a str>, &'a str), Vec<&'a str>);
+
+/// Parses `CREATE UNIQUE INDEX name ON table (columns)`.
+fn create_unique_index(input: &str) -> IResult<&str, RawUniqueIndex<'_>> {
+    map(
+        tuple((
+            keyword("CREATE"),
+            multispace1,
+            keyword("UNIQUE"),
+            multispace1,
+            keyword("INDEX"),
+            multispace1,
+            opt(if_not_exists),
+            identifier,
+            multispace1,
+            keyword("ON"),
+            multispace1,
+            qualified_identifier,
+            multispace0,
+            identifier_list,
+        )),
+        |(_, _, _, _, _, _, _, name, _, _, _, table, _, columns)| (name, table, columns),
+    )(input)
+}
+
+fn table_item(input: &str) -> IResult<&str, TableItem<'_>> {
+    alt((
+        table_constraint,
+        map(
+            preceded(
+                pair(alt((keyword("KEY"), keyword("INDEX"))), multispace1),
+                terminated(identifier, pair(multispace0, identifier_list)),
+            ),
+            TableItem::Index,
+        ),
+        map(column_definition, TableItem::Column),
+    ))(input)
+}
+
+fn table_constraint(input: &str) -> IResult<&str, TableItem<'_>> {
+    let (input, name) = opt(delimited(
+        pair(keyword("CONSTRAINT"), multispace1),
+        identifier,
+        multispace1,
+    ))(input)?;
+
+    let (input, (index_name, constraint)) = alt((
+        map(
+            preceded(
+                tuple((keyword("PRIMARY"), multispace1, keyword("KEY"), multispace0)),
+                identifier_list,
+            ),
+            |columns| (None, RawConstraint::PrimaryKey(columns)),
+        ),
+        map(
+            tuple((
+                keyword("UNIQUE"),
+                opt(preceded(
+                    multispace1,
+                    alt((keyword("KEY"), keyword("INDEX"))),
+                )),
+                multispace0,
+                opt(terminated(identifier, multispace0)),
+                identifier_list,
+            )),
+            |(_, _, _, index_name, columns)| (index_name, RawConstraint::Unique(columns)),
+        ),
+        map(
+            tuple((
+                keyword("FOREIGN"),
+                multispace1,
+                keyword("KEY"),
+                multispace0,
+                opt(terminated(identifier, multispace0)),
+                identifier_list,
+                multispace0,
+                references,
+            )),
+            |(_, _, _, _, index_name, columns, _, reference)| {
+                (index_name, RawConstraint::ForeignKey(columns, reference))
+            },
+        ),
+        map(
+            preceded(pair(keyword("CHECK"), multispace0), balanced_parentheses),
+            |expression| (None, RawConstraint::Check(expression.trim())),
+        ),
+    ))(input)?;
+
+    Ok((
+        input,
+        TableItem::Constraint(name.or(index_name), constraint),
+    ))
+}
+
+fn column_definition(input: &str) -> IResult<&str, RawColumn<'_>> {
+    map(
+        tuple((
+            identifier,
+            multispace1,
+            data_type,
+            many0(preceded(multispace1, column_constraint)),
+        )),
+        |(name, _, datatype, constraints)| RawColumn {
+            name,
+            datatype,
+            constraints,
+        },
+    )(input)
+}
+
+fn data_type(input: &str) -> IResult<&str, RawType<'_>> {
+    map(
+        tuple((
+            take_while1(is_identifier_char),
+            opt(preceded(
+                multispace1,
+                alt((keyword("PRECISION"), keyword("VARYING"))),
+            )),
+            opt(preceded(
+                multispace0,
+                delimited(
+                    pair(char('('), multispace0),
+                    separated_list1(tuple((multispace0, char(','), multispace0)), digit1),
+                    pair(multispace0, char(')')),
+                ),
+            )),
+            opt(preceded(
+                multispace1,
+                tuple((
+                    alt((keyword("WITH"), keyword("WITHOUT"))),
+                    multispace1,
+                    keyword("TIME"),
+                    multispace1,
+                    keyword("ZONE"),
+                )),
+            )),
+            opt(preceded(multispace1, keyword("UNSIGNED"))),
+        )),
+        |(name, suffix, arguments, _, _)| RawType {
+            name: match suffix {
+                Some(suffix) => format!("{} {}", name, suffix),
+                None => name.to_string(),
+            },
+            arguments: arguments.unwrap_or_default(),
+        },
+    )(input)
+}
+
+fn column_constraint(input: &str) -> IResult<&str, ColumnConstraint<'_>> {
+    alt((
+        map(
+            preceded(pair(keyword("CONSTRAINT"), multispace1), identifier),
+            ColumnConstraint::Name,
+        ),
+        value(
+            ColumnConstraint::NotNull,
+            tuple((keyword("NOT"), multispace1, keyword("NULL"))),
+        ),
+        value(ColumnConstraint::Null, keyword("NULL")),
+        value(
+            ColumnConstraint::PrimaryKey,
+            tuple((
+                keyword("PRIMARY"),
+                multispace1,
+                keyword("KEY"),
+                opt(preceded(
+                    multispace1,
+                    alt((keyword("ASC"), keyword("DESC"))),
+                )),
+            )),
+        ),
+        value(
+            ColumnConstraint::Unique,
+            pair(
+                keyword("UNIQUE"),
+                opt(preceded(multispace1, keyword("KEY"))),
+            ),
+        ),
+        map(
+            preceded(pair(keyword("DEFAULT"), multispace1), default_expression),
+            ColumnConstraint::Default,
+        ),
+        map(
+            preceded(pair(keyword("CHECK"), multispace0), balanced_parentheses),
+            |expression| ColumnConstraint::Check(expression.trim()),
+        ),
+        map(references, ColumnConstraint::References),
+        map(
+            alt((
+                keyword("AUTO_INCREMENT"),
+                keyword("AUTOINCREMENT"),
+                recognize(tuple((keyword("COLLATE"), multispace1, identifier))),
+                recognize(tuple((keyword("COMMENT"), multispace1, string_literal))),
+                recognize(tuple((
+                    keyword("ON"),
+                    multispace1,
+                    keyword("UPDATE"),
+                    multispace1,
+                    default_expression,
+                ))),
+            )),
+            ColumnConstraint::Ignored,
+        ),
+    ))(input)
+}
+
+fn references(input: &str) -> IResult<&str, RawReference<'_>> {
+    map(
+        tuple((
+            keyword("REFERENCES"),
+            multispace1,
+            qualified_identifier,
+            opt(preceded(multispace0, identifier_list)),
+            many0(preceded(multispace1, reference_action)),
+        )),
+        |(_, _, table, columns, actions)| RawReference {
+            table,
+            columns: columns.unwrap_or_default(),
+            actions,
+        },
+    )(input)
+}
+
+/// Parses `ON DELETE CASCADE`, `ON UPDATE SET NULL`, ...
+fn reference_action(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        keyword("ON"),
+        multispace1,
+        alt((keyword("DELETE"), keyword("UPDATE"))),
+        multispace1,
+        alt((
+            keyword("CASCADE"),
+            keyword("RESTRICT"),
+            recognize(tuple((keyword("NO"), multispace1, keyword("ACTION")))),
+            recognize(tuple((
+                keyword("SET"),
+                multispace1,
+                alt((keyword("NULL"), keyword("DEFAULT"))),
+            ))),
+        )),
+    )))(input)
+}
+
+/// Parses `'it''s'`.
+fn string_literal(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        char('\''),
+        many0(alt((tag("''"), is_not("'")))),
+        char('\''),
+    )))(input)
+}
+
+/// Parses a single value like `'text'`, `-1.5`, `(1 + 2)`, `CURRENT_TIMESTAMP` or `now()`,
+/// optionally followed by a postgres cast like `::text`.
+fn default_expression(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        alt((
+            string_literal,
+            recognize(tuple((
+                opt(char('-')),
+                digit1,
+                opt(pair(char('.'), digit1)),
+            ))),
+            recognize(balanced_parentheses),
+            recognize(pair(
+                take_while1(is_identifier_char),
+                opt(balanced_parentheses),
+            )),
+        )),
+        opt(pair(tag("::"), take_while1(is_identifier_char))),
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    mod parse_sql {
+        use crate::ddl::parse_sql;
+        use crate::types::{ConstraintKind, DataType};
+
+        #[test]
+        fn just_works() {
+            let (tables, warnings) = parse_sql(
+                "-- people
+                CREATE TABLE IF NOT EXISTS people (
+                    id SERIAL PRIMARY KEY,
+                    name VARCHAR(64) NOT NULL,
+                    nickname varchar(32) DEFAULT 'none',
+                    balance numeric(10, 2) NOT NULL DEFAULT 0,
+                    CONSTRAINT ck_balance CHECK (balance >= 0),
+                    CONSTRAINT ck_nickname CHECK (nickname <> ')')
+                );
+
+                /* cars */
+                CREATE TABLE \"car\" (
+                    id int NOT NULL,
+                    owner_id integer NOT NULL REFERENCES people (id) ON DELETE CASCADE,
+                    plate char(8) NOT NULL UNIQUE,
+                    PRIMARY KEY (id)
+                );
+
+                CREATE INDEX idx_car_plate ON car (plate);",
+            )
+            .unwrap();
+
+            assert_eq!(tables.len(), 2);
+
+            let people = tables.get("people").unwrap();
+            assert_eq!(people.get_field("id").unwrap().datatype(), &DataType::Int);
+            assert!(!people.get_field("name").unwrap().is_nullable());
+            assert!(people.get_field("nickname").unwrap().is_nullable());
+            assert_eq!(
+                people.get_field("nickname").unwrap().default(),
+                Some("'none'")
+            );
+            assert_eq!(
+                people.get_field("balance").unwrap().datatype(),
+                &DataType::Decimal(10, 2)
+            );
+            assert!(people
+                .constraints()
+                .iter()
+                .any(|constraint| constraint.name() == "ck_balance"
+                    && constraint.kind() == &ConstraintKind::Check("balance >= 0".to_string())));
+            // the parenthesis inside of the string doesn't end the check
+            assert!(people
+                .constraints()
+                .iter()
+                .any(|constraint| constraint.name() == "ck_nickname"
+                    && constraint.kind() == &ConstraintKind::Check("nickname <> ')'".to_string())));
+
+            let car = tables.get("car").unwrap();
+            let foreign_key = &car.foreign_keys()[0];
+            assert_eq!(foreign_key.name(), "owner");
+            assert_eq!(foreign_key.table(), "people");
+            assert_eq!(foreign_key.columns(), &vec!["owner_id".to_string()]);
+            assert_eq!(
+                car.get_field("owner_id").unwrap().foreign_key_table(),
+                Some("people")
+            );
+            assert!(car.constraints().iter().any(|constraint| constraint.kind()
+                == &ConstraintKind::Unique(vec!["plate".to_string()])));
+
+            assert_eq!(warnings.len(), 2);
+            assert!(warnings[0].contains("ON DELETE CASCADE"));
+            assert!(warnings[1].contains("idx_car_plate"));
+        }
+
+        #[test]
+        fn foreign_keys() {
+            let (tables, warnings) = parse_sql(
+                "CREATE TABLE termin (
+                    from_date date NOT NULL,
+                    to_date date NOT NULL,
+                    code varchar(8) NOT NULL UNIQUE,
+                    parent_from date,
+                    parent_to date,
+                    PRIMARY KEY (from_date, to_date),
+                    FOREIGN KEY (parent_from, parent_to) REFERENCES termin (from_date, to_date)
+                );
+                CREATE TABLE visit (
+                    id bigint PRIMARY KEY,
+                    termin_code varchar(8) REFERENCES termin (code)
+                );",
+            )
+            .unwrap();
+
+            let termin = tables.get("termin").unwrap();
+            assert_eq!(termin.foreign_keys()[0].name(), "termin");
+            assert!(termin.get_field("parent_from").unwrap().is_nullable());
+
+            // only primary keys can be referenced by a @foreign_key() field
+            let visit = tables.get("visit").unwrap();
+            assert!(visit.foreign_keys().is_empty());
+            assert!(visit.get_field("termin_code").is_some());
+            assert_eq!(warnings.len(), 1);
+        }
+
+        #[test]
+        fn errors() {
+            assert!(parse_sql("CREATE TABLE a (id int[]);").is_err());
+            assert!(parse_sql("CREATE TABLE a (id geometry);").is_err());
+            assert!(parse_sql("CREATE TABLE \"a b\" (id int);").is_err());
+            // the sql written by tsql doesn't quote names
+            assert_eq!(
+                parse_sql("CREATE TABLE rental (\"from\" date, \"to\" date);")
+                    .unwrap_err()
+                    .to_string(),
+                "Error: The name \"from\" is a reserved word of sql, which tsql doesn't quote"
+            );
+            assert!(parse_sql("CREATE TABLE a (id int PRIMARY KEY, PRIMARY KEY (id));").is_err());
+        }
+    }
+}
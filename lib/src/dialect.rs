@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Error, Result};
+
+use crate::types::TableCollection;
+
+/// The database the generated sql is targeting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    /// No database specific rules are applied.
+    #[default]
+    Generic,
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl Dialect {
+    /// Maximum length of an identifier (table, column or constraint name), `None` if there is no
+    /// limit.
+    pub fn max_identifier_length(&self) -> Option<usize> {
+        match self {
+            Dialect::Generic | Dialect::Sqlite => None,
+            Dialect::Postgres => Some(63),
+            Dialect::MySql => Some(64),
+        }
+    }
+
+    /// Whether tables can be grouped into schemas with `CREATE SCHEMA`.
+    pub fn supports_schemas(&self) -> bool {
+        match self {
+            Dialect::Generic | Dialect::Postgres | Dialect::MySql => true,
+            Dialect::Sqlite => false,
+        }
+    }
+
+    /// Checks that every constraint name fits into the identifier length limit of the dialect.
+    pub fn validate(&self, tables: &TableCollection) -> Result<()> {
+        let max_length = match self.max_identifier_length() {
+            Some(max_length) => max_length,
+            None => return Ok(()),
+        };
+
+        for table in tables.values() {
+            for name in table.constraint_names() {
+                if name.len() > max_length {
+                    bail!(
+                        "Error: Constraint {:?} in table {:?} is {} characters long, but {:?} only allows {}",
+                        name,
+                        table.name,
+                        name.len(),
+                        self,
+                        max_length
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "generic" => Ok(Dialect::Generic),
+            "postgres" => Ok(Dialect::Postgres),
+            "mysql" => Ok(Dialect::MySql),
+            "sqlite" => Ok(Dialect::Sqlite),
+            _ => bail!("Error: unknown dialect {:?}", s),
+        }
+    }
+}
@@ -0,0 +1,876 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::Write;
+
+use anyhow::{bail, Result};
+
+use crate::dialect::Dialect;
+use crate::types::{
+    dependency_order, qualified_name, Constraint, ConstraintKind, Field, ForeignKey, Table,
+    TableCollection,
+};
+use crate::TransformSQL;
+
+/// A single step of a [`Migration`].
+///
+/// Drops reference the table as it is before the migration, every other change references the
+/// table as it is afterwards.
+#[derive(Debug, Clone)]
+pub enum Change<'a> {
+    CreateSchema(&'a str),
+    CreateTable(&'a Table),
+    DropTable(&'a Table),
+    RenameTable {
+        from: &'a Table,
+        to: &'a Table,
+    },
+    AddColumn {
+        table: &'a Table,
+        field: &'a Field,
+    },
+    DropColumn {
+        table: &'a Table,
+        field: &'a Field,
+    },
+    RenameColumn {
+        table: &'a Table,
+        from: &'a Field,
+        to: &'a Field,
+    },
+    /// the type, the nullability or the default value of the column has changed
+    AlterColumn {
+        table: &'a Table,
+        from: &'a Field,
+        to: &'a Field,
+    },
+    AddConstraint {
+        table: &'a Table,
+        constraint: &'a Constraint,
+    },
+    DropConstraint {
+        table: &'a Table,
+        constraint: &'a Constraint,
+    },
+    AddForeignKey {
+        table: &'a Table,
+        foreign_key: &'a ForeignKey,
+    },
+    DropForeignKey {
+        table: &'a Table,
+        foreign_key: &'a ForeignKey,
+    },
+    /// only the name of the constraint has changed, e.g. because the table has been renamed
+    RenameConstraint {
+        table: &'a Table,
+        from: &'a Constraint,
+        to: &'a Constraint,
+    },
+    RenameForeignKey {
+        table: &'a Table,
+        from: &'a ForeignKey,
+        to: &'a ForeignKey,
+    },
+}
+
+impl<'a> Change<'a> {
+    /// Writes the sql statements of the change, fails if the dialect can't express the change
+    /// with `ALTER TABLE`.
+    pub fn transform<W: Write>(&self, dialect: Dialect, buffer: &mut W) -> Result<()> {
+        match self {
+            Change::CreateSchema(schema) => {
+                if dialect.supports_schemas() {
+                    writeln!(buffer, "CREATE SCHEMA IF NOT EXISTS {};", schema)?;
+                }
+            }
+            Change::CreateTable(table) => table.transform(buffer)?,
+            Change::DropTable(table) => writeln!(buffer, "DROP TABLE {};", table.qualified_name())?,
+            Change::RenameTable { from, to } => transform_rename_table(from, to, dialect, buffer)?,
+            Change::AddColumn { table, field } => {
+                write!(buffer, "ALTER TABLE {} ADD COLUMN ", table.qualified_name())?;
+                field.transform(buffer)?;
+                writeln!(buffer, ";")?;
+            }
+            Change::DropColumn { table, field } => writeln!(
+                buffer,
+                "ALTER TABLE {} DROP COLUMN {};",
+                table.qualified_name(),
+                field.name
+            )?,
+            Change::RenameColumn { table, from, to } => writeln!(
+                buffer,
+                "ALTER TABLE {} RENAME COLUMN {} TO {};",
+                table.qualified_name(),
+                from.name,
+                to.name
+            )?,
+            Change::AlterColumn { table, from, to } => {
+                transform_alter_column(table, from, to, dialect, buffer)?
+            }
+            Change::AddConstraint { table, constraint } => {
+                unsupported_by_sqlite(table, &constraint.name, dialect)?;
+
+                write!(buffer, "ALTER TABLE {} ADD ", table.qualified_name())?;
+                constraint.transform(buffer)?;
+                writeln!(buffer, ";")?;
+            }
+            Change::DropConstraint { table, constraint } => {
+                unsupported_by_sqlite(table, &constraint.name, dialect)?;
+
+                let drop = match (dialect, &constraint.kind) {
+                    (Dialect::MySql, ConstraintKind::PrimaryKey(_)) => "PRIMARY KEY".to_string(),
+                    (Dialect::MySql, ConstraintKind::Unique(_)) => {
+                        format!("INDEX {}", constraint.name)
+                    }
+                    (Dialect::MySql, ConstraintKind::Check(_)) => {
+                        format!("CHECK {}", constraint.name)
+                    }
+                    _ => format!("CONSTRAINT {}", constraint.name),
+                };
+
+                writeln!(
+                    buffer,
+                    "ALTER TABLE {} DROP {};",
+                    table.qualified_name(),
+                    drop
+                )?;
+            }
+            Change::AddForeignKey { table, foreign_key } => {
+                unsupported_by_sqlite(table, &foreign_key.constraint, dialect)?;
+
+                write!(buffer, "ALTER TABLE {} ADD ", table.qualified_name())?;
+                foreign_key.transform(buffer)?;
+                writeln!(buffer, ";")?;
+            }
+            Change::DropForeignKey { table, foreign_key } => {
+                unsupported_by_sqlite(table, &foreign_key.constraint, dialect)?;
+
+                let keyword = match dialect {
+                    Dialect::MySql => "FOREIGN KEY",
+                    _ => "CONSTRAINT",
+                };
+
+                writeln!(
+                    buffer,
+                    "ALTER TABLE {} DROP {} {};",
+                    table.qualified_name(),
+                    keyword,
+                    foreign_key.constraint
+                )?;
+            }
+            Change::RenameConstraint { table, from, to } => match (dialect, &to.kind) {
+                // the primary key of mysql is always called `PRIMARY`
+                (Dialect::MySql, ConstraintKind::PrimaryKey(_)) => (),
+                (Dialect::MySql, ConstraintKind::Unique(_)) => writeln!(
+                    buffer,
+                    "ALTER TABLE {} RENAME INDEX {} TO {};",
+                    table.qualified_name(),
+                    from.name,
+                    to.name
+                )?,
+                (Dialect::MySql, ConstraintKind::Check(_)) => {
+                    Change::DropConstraint {
+                        table,
+                        constraint: from,
+                    }
+                    .transform(dialect, buffer)?;
+                    Change::AddConstraint {
+                        table,
+                        constraint: to,
+                    }
+                    .transform(dialect, buffer)?;
+                }
+                _ => transform_rename_constraint(table, &from.name, &to.name, dialect, buffer)?,
+            },
+            Change::RenameForeignKey { table, from, to } => match dialect {
+                Dialect::MySql => {
+                    Change::DropForeignKey {
+                        table,
+                        foreign_key: from,
+                    }
+                    .transform(dialect, buffer)?;
+                    Change::AddForeignKey {
+                        table,
+                        foreign_key: to,
+                    }
+                    .transform(dialect, buffer)?;
+                }
+                _ => transform_rename_constraint(
+                    table,
+                    &from.constraint,
+                    &to.constraint,
+                    dialect,
+                    buffer,
+                )?,
+            },
+        }
+
+        Ok(())
+    }
+}
+
+fn transform_rename_table<W: Write>(
+    from: &Table,
+    to: &Table,
+    dialect: Dialect,
+    buffer: &mut W,
+) -> Result<()> {
+    match dialect {
+        Dialect::Generic => writeln!(
+            buffer,
+            "ALTER TABLE {} RENAME TO {};",
+            from.qualified_name(),
+            to.qualified_name()
+        )?,
+        Dialect::MySql => writeln!(
+            buffer,
+            "RENAME TABLE {} TO {};",
+            from.qualified_name(),
+            to.qualified_name()
+        )?,
+        // `RENAME TO` only takes the name, moving the table is done with `SET SCHEMA`
+        Dialect::Postgres => {
+            if from.schema != to.schema {
+                writeln!(
+                    buffer,
+                    "ALTER TABLE {} SET SCHEMA {};",
+                    from.qualified_name(),
+                    to.schema().unwrap_or("public")
+                )?;
+            }
+
+            if from.name != to.name {
+                writeln!(
+                    buffer,
+                    "ALTER TABLE {} RENAME TO {};",
+                    qualified_name(to.schema(), &from.name),
+                    to.name
+                )?;
+            }
+        }
+        Dialect::Sqlite => {
+            if from.schema != to.schema {
+                bail!(
+                    "Error: Sqlite can't move the table {:?} into another schema, the table has to be recreated",
+                    from.qualified_name()
+                );
+            }
+
+            writeln!(
+                buffer,
+                "ALTER TABLE {} RENAME TO {};",
+                from.qualified_name(),
+                to.name
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn transform_rename_constraint<W: Write>(
+    table: &Table,
+    from: &str,
+    to: &str,
+    dialect: Dialect,
+    buffer: &mut W,
+) -> Result<()> {
+    // the name of a constraint has no effect in sqlite, so the old one is kept
+    if dialect == Dialect::Sqlite {
+        writeln!(
+            buffer,
+            "-- Sqlite can't rename the constraint {} of table {} to {}",
+            from,
+            table.qualified_name(),
+            to
+        )?;
+    } else {
+        writeln!(
+            buffer,
+            "ALTER TABLE {} RENAME CONSTRAINT {} TO {};",
+            table.qualified_name(),
+            from,
+            to
+        )?;
+    }
+
+    Ok(())
+}
+
+fn transform_alter_column<W: Write>(
+    table: &Table,
+    from: &Field,
+    to: &Field,
+    dialect: Dialect,
+    buffer: &mut W,
+) -> Result<()> {
+    match dialect {
+        Dialect::Generic | Dialect::Postgres => {
+            if from.datatype != to.datatype {
+                write!(
+                    buffer,
+                    "ALTER TABLE {} ALTER COLUMN {} SET DATA TYPE ",
+                    table.qualified_name(),
+                    to.name
+                )?;
+                to.datatype.transform(buffer)?;
+                writeln!(buffer, ";")?;
+            }
+
+            if from.nullable != to.nullable {
+                writeln!(
+                    buffer,
+                    "ALTER TABLE {} ALTER COLUMN {} {} NOT NULL;",
+                    table.qualified_name(),
+                    to.name,
+                    if to.nullable { "DROP" } else { "SET" }
+                )?;
+            }
+
+            if from.default != to.default {
+                match &to.default {
+                    Some(default) => writeln!(
+                        buffer,
+                        "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {};",
+                        table.qualified_name(),
+                        to.name,
+                        default
+                    )?,
+                    None => writeln!(
+                        buffer,
+                        "ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT;",
+                        table.qualified_name(),
+                        to.name
+                    )?,
+                }
+            }
+        }
+        // `MODIFY COLUMN` replaces the whole definition of the column
+        Dialect::MySql => {
+            write!(buffer, "ALTER TABLE {} MODIFY COLUMN ", table.qualified_name())?;
+            to.transform(buffer)?;
+            writeln!(buffer, ";")?;
+        }
+        Dialect::Sqlite => bail!(
+            "Error: Sqlite can't alter the column {:?} of table {:?}, the table has to be recreated",
+            to.name,
+            table.qualified_name()
+        ),
+    }
+
+    Ok(())
+}
+
+/// Sqlite can't add or drop constraints of an existing table.
+fn unsupported_by_sqlite(table: &Table, constraint: &str, dialect: Dialect) -> Result<()> {
+    if dialect == Dialect::Sqlite {
+        bail!(
+            "Error: Sqlite can't change the constraint {:?} of table {:?}, the table has to be recreated",
+            constraint,
+            table.qualified_name()
+        );
+    }
+
+    Ok(())
+}
+
+/// Ordered list of changes which migrate the database from one version of the schema to
+/// another, created with [`diff`].
+#[derive(Debug, Clone, Default)]
+pub struct Migration<'a> {
+    pub(crate) changes: Vec<Change<'a>>,
+}
+
+impl<'a> Migration<'a> {
+    pub fn changes(&self) -> &Vec<Change<'a>> {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Writes the sql statements of all changes, see [`Change::transform`]. Nothing is written
+    /// if one of the changes fails.
+    pub fn transform<W: Write>(&self, dialect: Dialect, buffer: &mut W) -> Result<()> {
+        let mut rendered = Vec::new();
+        for change in &self.changes {
+            change.transform(dialect, &mut rendered)?;
+        }
+        buffer.write_all(&rendered)?;
+
+        Ok(())
+    }
+}
+
+/// Changes of a single table, collected separately so that the migration can be ordered by the
+/// kind of change.
+#[derive(Default)]
+struct TableChanges<'a> {
+    drop_foreign_keys: Vec<Change<'a>>,
+    drop_constraints: Vec<Change<'a>>,
+    drop_columns: Vec<Change<'a>>,
+    rename_columns: Vec<Change<'a>>,
+    rename_constraints: Vec<Change<'a>>,
+    add_columns: Vec<Change<'a>>,
+    alter_columns: Vec<Change<'a>>,
+    add_constraints: Vec<Change<'a>>,
+    add_foreign_keys: Vec<Change<'a>>,
+}
+
+/// Computes the changes from `old` to `new`.
+///
+/// Tables, fields and `@foreign_key()` fields annotated with `@renamed_from(old_name)` are
+/// renamed instead of dropped and created again, as long as `old_name` exists in `old` and no
+/// longer exists in `new`. The annotations are also used if they are part of `old`, so that
+/// `diff(new, old)` reverts the migration.
+///
+/// The changes are ordered so that they can be executed one after another: foreign keys,
+/// constraints, columns and tables are dropped first, then tables and columns are renamed, and
+/// at last everything new is created and altered.
+pub fn diff<'a>(old: &'a TableCollection, new: &'a TableCollection) -> Migration<'a> {
+    let mut pairs = Vec::new();
+    let mut matched = HashSet::new();
+    let mut created = Vec::new();
+    let mut renamed = Vec::new();
+    let mut table_renames = HashMap::new();
+
+    for new_table in dependency_order(new) {
+        let name = new_table.qualified_name();
+
+        let old_table = match new_table.renamed_from() {
+            Some(from) if !new.contains_key(from) && old.contains_key(from) => old.get(from),
+            _ => old.get(&name).or_else(|| {
+                // the annotation is part of the old schema if the migration is reverted
+                old.values().find(|old_table| {
+                    old_table.renamed_from() == Some(name.as_str())
+                        && !new.contains_key(&old_table.qualified_name())
+                })
+            }),
+        };
+
+        match old_table {
+            Some(old_table) if matched.insert(old_table.qualified_name()) => {
+                if old_table.qualified_name() != name {
+                    renamed.push(Change::RenameTable {
+                        from: old_table,
+                        to: new_table,
+                    });
+                    table_renames.insert(old_table.qualified_name(), name);
+                }
+
+                pairs.push((old_table, new_table));
+            }
+            _ => created.push(Change::CreateTable(new_table)),
+        }
+    }
+
+    let old_schemas = old
+        .values()
+        .filter_map(Table::schema)
+        .collect::<HashSet<_>>();
+    let created_schemas = new
+        .values()
+        .filter_map(Table::schema)
+        .filter(|schema| !old_schemas.contains(schema))
+        .collect::<BTreeSet<_>>();
+
+    let mut dropped = dependency_order(old)
+        .into_iter()
+        .filter(|table| !matched.contains(&table.qualified_name()))
+        .map(Change::DropTable)
+        .collect::<Vec<_>>();
+    // a table can only be dropped after the tables which reference it
+    dropped.reverse();
+
+    let mut table_changes = TableChanges::default();
+    for (old_table, new_table) in pairs {
+        diff_table(old_table, new_table, &table_renames, &mut table_changes);
+    }
+
+    let changes = table_changes
+        .drop_foreign_keys
+        .into_iter()
+        .chain(table_changes.drop_constraints)
+        .chain(table_changes.drop_columns)
+        .chain(dropped)
+        .chain(renamed)
+        .chain(table_changes.rename_columns)
+        .chain(table_changes.rename_constraints)
+        .chain(created_schemas.into_iter().map(Change::CreateSchema))
+        .chain(created)
+        .chain(table_changes.add_columns)
+        .chain(table_changes.alter_columns)
+        .chain(table_changes.add_constraints)
+        .chain(table_changes.add_foreign_keys)
+        .collect();
+
+    Migration { changes }
+}
+
+fn diff_table<'a>(
+    old: &'a Table,
+    new: &'a Table,
+    table_renames: &HashMap<String, String>,
+    changes: &mut TableChanges<'a>,
+) {
+    let column_renames = column_renames(old, new);
+
+    // fields are stored in a map, sorting them keeps the migration stable
+    let mut field_names = new.fields.keys().collect::<Vec<_>>();
+    field_names.sort();
+
+    let mut matched = HashSet::new();
+    for name in field_names {
+        let new_field = &new.fields[name];
+        let old_name = column_renames
+            .get(name.as_str())
+            .copied()
+            .unwrap_or(name.as_str());
+
+        let old_field = match old.fields.get(old_name) {
+            Some(old_field) => old_field,
+            None => {
+                changes.add_columns.push(Change::AddColumn {
+                    table: new,
+                    field: new_field,
+                });
+                continue;
+            }
+        };
+        matched.insert(old_name);
+
+        if old_name != name {
+            changes.rename_columns.push(Change::RenameColumn {
+                table: new,
+                from: old_field,
+                to: new_field,
+            });
+        }
+
+        if old_field.datatype != new_field.datatype
+            || old_field.nullable != new_field.nullable
+            || old_field.default != new_field.default
+        {
+            changes.alter_columns.push(Change::AlterColumn {
+                table: new,
+                from: old_field,
+                to: new_field,
+            });
+        }
+    }
+
+    let mut dropped = old
+        .fields
+        .values()
+        .filter(|field| !matched.contains(field.name.as_str()))
+        .collect::<Vec<_>>();
+    dropped.sort_by(|a, b| a.name.cmp(&b.name));
+    changes.drop_columns.extend(
+        dropped
+            .into_iter()
+            .map(|field| Change::DropColumn { table: old, field }),
+    );
+
+    // constraints of the old table are compared with the renamed columns
+    let renamed_column = |column: &String| {
+        column_renames
+            .iter()
+            .find(|(_, old_name)| *old_name == column)
+            .map(|(new_name, _)| new_name.to_string())
+            .unwrap_or_else(|| column.clone())
+    };
+    let rename_columns = |columns: &Vec<String>| columns.iter().map(renamed_column).collect();
+
+    let constraint_matches = |old_constraint: &Constraint, new_constraint: &Constraint| {
+        let old_kind = match &old_constraint.kind {
+            ConstraintKind::PrimaryKey(columns) => {
+                ConstraintKind::PrimaryKey(rename_columns(columns))
+            }
+            ConstraintKind::Unique(columns) => ConstraintKind::Unique(rename_columns(columns)),
+            ConstraintKind::Check(expression) => ConstraintKind::Check(expression.clone()),
+        };
+
+        old_kind == new_constraint.kind
+    };
+
+    let (dropped, renamed, added) = match_constraints(
+        &old.constraints,
+        &new.constraints,
+        |item| &item.name,
+        constraint_matches,
+    );
+    changes.drop_constraints.extend(
+        dropped
+            .into_iter()
+            .map(|constraint| Change::DropConstraint {
+                table: old,
+                constraint,
+            }),
+    );
+    changes
+        .rename_constraints
+        .extend(
+            renamed
+                .into_iter()
+                .map(|(from, to)| Change::RenameConstraint {
+                    table: new,
+                    from,
+                    to,
+                }),
+        );
+    changes
+        .add_constraints
+        .extend(added.into_iter().map(|constraint| Change::AddConstraint {
+            table: new,
+            constraint,
+        }));
+
+    let foreign_key_matches = |old_foreign_key: &ForeignKey, new_foreign_key: &ForeignKey| {
+        let old_table = table_renames
+            .get(&old_foreign_key.table)
+            .unwrap_or(&old_foreign_key.table);
+
+        old_table == &new_foreign_key.table
+            && rename_columns(&old_foreign_key.columns) == new_foreign_key.columns
+            && old_foreign_key.references == new_foreign_key.references
+    };
+
+    let (dropped, renamed, added) = match_constraints(
+        &old.foreign_keys,
+        &new.foreign_keys,
+        |item| &item.constraint,
+        foreign_key_matches,
+    );
+    changes
+        .drop_foreign_keys
+        .extend(
+            dropped
+                .into_iter()
+                .map(|foreign_key| Change::DropForeignKey {
+                    table: old,
+                    foreign_key,
+                }),
+        );
+    changes
+        .rename_constraints
+        .extend(
+            renamed
+                .into_iter()
+                .map(|(from, to)| Change::RenameForeignKey {
+                    table: new,
+                    from,
+                    to,
+                }),
+        );
+    changes
+        .add_foreign_keys
+        .extend(added.into_iter().map(|foreign_key| Change::AddForeignKey {
+            table: new,
+            foreign_key,
+        }));
+}
+
+/// Pairs the old and new constraints for which `matches` is true, constraints with the same
+/// name are paired first and every other pair is a rename.
+///
+/// Returns `(dropped, renamed, added)`.
+fn match_constraints<'a, T>(
+    old: &'a [T],
+    new: &'a [T],
+    name: impl Fn(&T) -> &String,
+    matches: impl Fn(&T, &T) -> bool,
+) -> (Vec<&'a T>, Vec<(&'a T, &'a T)>, Vec<&'a T>) {
+    let mut dropped = old.iter().collect::<Vec<_>>();
+    let mut added = Vec::new();
+    let mut unnamed = Vec::new();
+
+    for new_item in new {
+        match dropped
+            .iter()
+            .position(|old_item| name(old_item) == name(new_item) && matches(old_item, new_item))
+        {
+            Some(index) => {
+                dropped.remove(index);
+            }
+            None => unnamed.push(new_item),
+        }
+    }
+
+    let mut renamed = Vec::new();
+    for new_item in unnamed {
+        match dropped
+            .iter()
+            .position(|old_item| matches(old_item, new_item))
+        {
+            Some(index) => renamed.push((dropped.remove(index), new_item)),
+            None => added.push(new_item),
+        }
+    }
+
+    (dropped, renamed, added)
+}
+
+/// Returns the renamed columns of a table as `new name -> old name`, columns generated for a
+/// renamed `@foreign_key()` field are renamed in the order of the primary key.
+fn column_renames<'a>(old: &'a Table, new: &'a Table) -> HashMap<&'a str, &'a str> {
+    let mut renames = HashMap::new();
+
+    let mut insert = |from: &'a str, to: &'a str| {
+        if from != to && old.fields.contains_key(from) && !new.fields.contains_key(from) {
+            renames.insert(to, from);
+        }
+    };
+
+    for field in new.fields.values() {
+        if let Some(from) = field.renamed_from() {
+            insert(from, &field.name);
+        }
+    }
+
+    // the annotations are part of the old table if the migration is reverted
+    for field in old.fields.values() {
+        if let Some(to) = field.renamed_from() {
+            insert(&field.name, to);
+        }
+    }
+
+    for new_foreign_key in &new.foreign_keys {
+        let old_foreign_key = old.foreign_keys.iter().find(|item| {
+            new_foreign_key.renamed_from() == Some(item.name.as_str())
+                || item.renamed_from() == Some(new_foreign_key.name.as_str())
+        });
+
+        if let Some(old_foreign_key) = old_foreign_key {
+            for (from, to) in old_foreign_key
+                .columns
+                .iter()
+                .zip(new_foreign_key.columns.iter())
+            {
+                insert(from, to);
+            }
+        }
+    }
+
+    renames
+}
+
+#[cfg(test)]
+mod tests {
+    mod diff {
+        use crate::dialect::Dialect;
+        use crate::diff::{diff, Change};
+        use crate::parse_str;
+
+        fn render(old: &str, new: &str, dialect: Dialect) -> String {
+            let old = parse_str(old).unwrap();
+            let new = parse_str(new).unwrap();
+
+            let mut buffer = Vec::new();
+            diff(&old, &new).transform(dialect, &mut buffer).unwrap();
+
+            String::from_utf8(buffer).unwrap()
+        }
+
+        #[test]
+        fn just_works() {
+            let schema = "@primary_key(id) table People { int id, varchar(64) name, };";
+
+            let old = parse_str(schema).unwrap();
+            let new = parse_str(schema).unwrap();
+            assert!(diff(&old, &new).is_empty());
+
+            assert_eq!(
+                render(
+                    schema,
+                    "@primary_key(id) table People { int id, varchar(128) name, date birth, };",
+                    Dialect::Postgres
+                ),
+                "ALTER TABLE People ADD COLUMN birth date NOT NULL;\n\
+                 ALTER TABLE People ALTER COLUMN name SET DATA TYPE varchar(128);\n"
+            );
+
+            assert_eq!(
+                render(
+                    schema,
+                    "@primary_key(id) table People { int id, varchar(128) name, };",
+                    Dialect::MySql
+                ),
+                "ALTER TABLE People MODIFY COLUMN name varchar(128) NOT NULL;\n"
+            );
+
+            assert_eq!(
+                render(
+                    schema,
+                    "@primary_key(id) table People { int id, @nullable @default('none') varchar(64) name, };",
+                    Dialect::Postgres
+                ),
+                "ALTER TABLE People ALTER COLUMN name DROP NOT NULL;\n\
+                 ALTER TABLE People ALTER COLUMN name SET DEFAULT 'none';\n"
+            );
+
+            assert_eq!(render(schema, "", Dialect::Generic), "DROP TABLE People;\n");
+        }
+
+        #[test]
+        fn renames() {
+            let old = "@primary_key(id) table People { int id, varchar(64) name, };\n\
+                       @primary_key(id) table Car { int id, @foreign_key() People owner, };";
+            let new = "@primary_key(id) @renamed_from(People) table Human { int id, @renamed_from(name) varchar(64) full_name, };\n\
+                       @primary_key(id) table Car { int id, @renamed_from(owner) @foreign_key() Human driver, };";
+
+            let old = parse_str(old).unwrap();
+            let new = parse_str(new).unwrap();
+            let migration = diff(&old, &new);
+
+            assert!(migration.changes().iter().any(|change| matches!(
+                change,
+                Change::RenameTable { from, to } if from.qualified_name() == "People" && to.qualified_name() == "Human"
+            )));
+            assert!(!migration
+                .changes()
+                .iter()
+                .any(|change| matches!(change, Change::DropTable(_) | Change::CreateTable(_))));
+
+            let mut buffer = Vec::new();
+            migration.transform(Dialect::Postgres, &mut buffer).unwrap();
+            let sql = String::from_utf8(buffer).unwrap();
+
+            assert_eq!(
+                sql,
+                "ALTER TABLE People RENAME TO Human;\n\
+                 ALTER TABLE Human RENAME COLUMN name TO full_name;\n\
+                 ALTER TABLE Car RENAME COLUMN owner_id TO driver_id;\n\
+                 ALTER TABLE Human RENAME CONSTRAINT pk_People TO pk_Human;\n\
+                 ALTER TABLE Car RENAME CONSTRAINT fk_Car_owner TO fk_Car_driver;\n"
+            );
+
+            let mut buffer = Vec::new();
+            diff(&new, &old)
+                .transform(Dialect::Postgres, &mut buffer)
+                .unwrap();
+            let sql = String::from_utf8(buffer).unwrap();
+
+            assert_eq!(
+                sql,
+                "ALTER TABLE Human RENAME TO People;\n\
+                 ALTER TABLE People RENAME COLUMN full_name TO name;\n\
+                 ALTER TABLE Car RENAME COLUMN driver_id TO owner_id;\n\
+                 ALTER TABLE People RENAME CONSTRAINT pk_Human TO pk_People;\n\
+                 ALTER TABLE Car RENAME CONSTRAINT fk_Car_driver TO fk_Car_owner;\n"
+            );
+        }
+
+        #[test]
+        fn errors() {
+            let old = parse_str("@primary_key(id) table People { int id, };").unwrap();
+            let new =
+                parse_str("@primary_key(id) table People { bigint id, date birth, };").unwrap();
+
+            // the added column can be written, but not the altered one
+            let mut buffer = Vec::new();
+            assert!(diff(&old, &new)
+                .transform(Dialect::Sqlite, &mut buffer)
+                .is_err());
+            assert!(buffer.is_empty());
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use anyhow::Result;
+
+use crate::loader::lower_source;
+use crate::naming::NamingConvention;
+use crate::syntax::{Element, Node, NodeKind, SyntaxTree, Token, TokenKind, Trivia, TriviaKind};
+
+const INDENT: &str = "    ";
+
+/// Formats tsql source, `format_str(format_str(x)) == format_str(x)` for every valid source.
+///
+/// Statements are separated by a blank line, annotations are written on their own line in the
+/// order they have been written in, the fields of a table are indented with four spaces and
+/// their names are aligned. Comments and single blank lines between fields are kept. The
+/// expressions of `@check(...)` and `@default(...)` are kept as they are, as they end up in the
+/// sql.
+pub fn format_str(content: &str) -> Result<String> {
+    let tree = SyntaxTree::parse(content)?;
+    // only formats valid sources, the tables themselves aren't resolved
+    lower_source(&tree, content, None, &NamingConvention::default())?;
+
+    let mut formatter = Formatter {
+        lines: Vec::new(),
+        pending: Vec::new(),
+        line_closed: false,
+        collected: None,
+        indent: 0,
+    };
+    formatter.statements(&tree);
+
+    Ok(formatter.render())
+}
+
+/// Line breaks in front of the token, behind the last comment in front of it.
+fn newlines(token: Option<&Token>) -> usize {
+    token.map_or(0, |token| {
+        token
+            .leading_trivia()
+            .iter()
+            .rev()
+            .take_while(|trivia| trivia.kind() == TriviaKind::Whitespace)
+            .map(|trivia| trivia.text().matches('\n').count())
+            .sum()
+    })
+}
+
+/// Normalizes the whitespace of a list like `(a ,b, name=c)` to `(a, b, name = c)`, a group
+/// with comments is kept as it is.
+fn normalize_list(group: &str) -> String {
+    let mut normalized = String::with_capacity(group.len());
+    let mut quote = None;
+    let mut space = false;
+    let mut chars = group.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            normalized.push(c);
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        match c {
+            '/' if chars.peek() == Some(&'/') => return group.to_string(),
+            c if c.is_whitespace() => space = true,
+            ',' => {
+                normalized.push(',');
+                space = true;
+            }
+            '=' => {
+                if !normalized.ends_with(' ') {
+                    normalized.push(' ');
+                }
+                normalized.push('=');
+                space = true;
+            }
+            ')' => {
+                normalized.push(')');
+                space = false;
+            }
+            _ => {
+                if space && !normalized.ends_with('(') && !normalized.ends_with(' ') {
+                    normalized.push(' ');
+                }
+                space = false;
+
+                if c == '\'' || c == '"' {
+                    quote = Some(c);
+                }
+                normalized.push(c);
+            }
+        }
+    }
+
+    normalized
+}
+
+/// Arguments of the annotation, the expressions of `@check` and `@default` are kept as they
+/// are.
+fn annotation_arguments(annotation: &str, group: &str) -> String {
+    match annotation {
+        "@check" | "@default" => group.to_string(),
+        _ => normalize_list(group),
+    }
+}
+
+#[derive(Debug)]
+enum Line<'a> {
+    Blank,
+    Text {
+        indent: usize,
+        text: String,
+        comment: Option<&'a str>,
+    },
+    /// a field whose name is aligned with the other fields of the table
+    Field {
+        indent: usize,
+        datatype: String,
+        name: &'a str,
+        width: usize,
+        comment: Option<&'a str>,
+    },
+}
+
+struct Formatter<'a> {
+    lines: Vec<Line<'a>>,
+    /// comments on their own line, which are written in front of the next line, values:
+    /// `(comment, blank line in front)`
+    pending: Vec<(&'a str, bool)>,
+    /// whether the last line ends with the last consumed token, so that a comment directly
+    /// behind the token stays on that line
+    line_closed: bool,
+    /// start of the token whose comments have been collected last, as the comments in front
+    /// of a statement are collected before it's written
+    collected: Option<usize>,
+    indent: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn statements(&mut self, tree: &'a SyntaxTree) {
+        let mut previous_import = None;
+
+        for statement in tree.statements() {
+            self.collect_comments(statement.first_token());
+            let import = statement.kind() == NodeKind::Import;
+
+            // imports are grouped, everything else is separated by a blank line
+            if previous_import.is_some() && !(import && previous_import == Some(true)) {
+                self.blank();
+            }
+
+            match statement.kind() {
+                NodeKind::Import => self.import(statement),
+                NodeKind::Naming => self.naming(statement),
+                NodeKind::Schema => self.schema(statement),
+                _ => self.table(statement),
+            }
+
+            previous_import = Some(import);
+        }
+
+        self.comments(tree.trailing_trivia());
+        self.flush_pending(0);
+    }
+
+    /// `import "file.tsql";` or `import { A, B } from "file.tsql";`
+    fn import(&mut self, node: &'a Node) {
+        let blank = newlines(node.first_token()) >= 2;
+        let mut text = String::new();
+
+        for token in node.tokens() {
+            let token = self.token(token);
+
+            if !text.is_empty() && token != "," && token != ";" {
+                text.push(' ');
+            }
+            text.push_str(token);
+        }
+
+        self.push(self.text(text), blank);
+    }
+
+    /// `@naming(...);`
+    fn naming(&mut self, node: &'a Node) {
+        let blank = newlines(node.first_token()) >= 2;
+        let mut text = String::new();
+
+        for token in node.tokens() {
+            let kind = token.kind();
+            let token = self.token(token);
+
+            match kind {
+                TokenKind::Group => text.push_str(&normalize_list(token)),
+                _ => text.push_str(token),
+            }
+        }
+
+        self.push(self.text(text), blank);
+    }
+
+    /// `schema name { tables };`
+    fn schema(&mut self, node: &'a Node) {
+        let blank = newlines(node.first_token()) >= 2;
+        let name = node.name();
+        let mut first_table = true;
+
+        for element in node.children() {
+            let token = match element {
+                Element::Node(table) => {
+                    self.collect_comments(table.first_token());
+
+                    if !first_table {
+                        self.blank();
+                    }
+                    first_table = false;
+
+                    self.table(table);
+                    continue;
+                }
+                Element::Token(token) => token,
+            };
+
+            let text = self.token(token);
+            match text {
+                "{" => {
+                    let name = name.map_or("", Token::text);
+                    self.push(self.text(format!("schema {} {{", name)), blank);
+                    self.indent += 1;
+                }
+                "}" => {
+                    self.flush_pending(self.indent);
+                    self.indent -= 1;
+                }
+                // the name is written with the `{`, the `;` is optional behind a schema
+                _ => (),
+            }
+        }
+
+        self.push(self.text("};".to_string()), false);
+    }
+
+    /// Annotations and `table name { fields };`
+    fn table(&mut self, node: &'a Node) {
+        let mut blank = newlines(node.first_token()) >= 2;
+        let name = node.name();
+        let mut start = self.lines.len();
+
+        for element in node.children() {
+            let token = match element {
+                Element::Node(child) if child.kind() == NodeKind::Annotation => {
+                    let text = self.annotation(child);
+                    self.push(self.text(text), blank);
+                    blank = false;
+                    continue;
+                }
+                Element::Node(field) => {
+                    self.field(field);
+                    continue;
+                }
+                Element::Token(token) => token,
+            };
+
+            match self.token(token) {
+                "{" => {
+                    let name = name.map_or("", Token::text);
+                    self.push(self.text(format!("table {} {{", name)), blank);
+                    self.indent += 1;
+                    start = self.lines.len();
+                }
+                "}" => {
+                    self.flush_pending(self.indent);
+                    self.indent -= 1;
+                }
+                // `table` and the name are written with the `{`
+                _ => (),
+            }
+        }
+
+        self.push(self.text("};".to_string()), false);
+
+        let fields = &mut self.lines[start..];
+        let width = fields
+            .iter()
+            .filter_map(|line| match line {
+                Line::Field { datatype, .. } => Some(datatype.len()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default();
+        for line in fields {
+            if let Line::Field { width: field, .. } = line {
+                *field = width;
+            }
+        }
+    }
+
+    /// Annotations on their own line and `type name,`
+    fn field(&mut self, node: &'a Node) {
+        let mut blank = newlines(node.first_token()) >= 2;
+
+        let mut annotations = Vec::new();
+        let mut datatype = String::new();
+        let mut name = "";
+
+        for element in node.children() {
+            match element {
+                Element::Node(child) if child.kind() == NodeKind::Annotation => {
+                    annotations.push(self.annotation(child))
+                }
+                Element::Node(child) => {
+                    if !annotations.is_empty() {
+                        self.push(self.text(annotations.join(" ")), blank);
+                        blank = false;
+                    }
+
+                    for token in child.tokens() {
+                        let kind = token.kind();
+                        let token = self.token(token);
+
+                        match kind {
+                            TokenKind::Group => datatype.push_str(&normalize_list(token)),
+                            _ => datatype.push_str(token),
+                        }
+                    }
+                }
+                Element::Token(token) => {
+                    let kind = token.kind();
+                    let token = self.token(token);
+
+                    if kind == TokenKind::Word {
+                        name = token;
+                    }
+                }
+            }
+        }
+
+        let line = Line::Field {
+            indent: self.indent,
+            datatype,
+            name,
+            width: 0,
+            comment: None,
+        };
+        self.push(line, blank);
+    }
+
+    /// `@name(arguments) as name`
+    fn annotation(&mut self, node: &'a Node) -> String {
+        let annotation = node.name().map_or("", Token::text);
+        let mut text = String::new();
+
+        for token in node.tokens() {
+            let kind = token.kind();
+            let token = self.token(token);
+
+            match kind {
+                TokenKind::Annotation => text.push_str(token),
+                TokenKind::Group => text.push_str(&annotation_arguments(annotation, token)),
+                // `as` and the constraint name
+                _ => {
+                    text.push(' ');
+                    text.push_str(token);
+                }
+            }
+        }
+
+        text
+    }
+
+    fn text(&self, text: String) -> Line<'a> {
+        Line::Text {
+            indent: self.indent,
+            text,
+            comment: None,
+        }
+    }
+
+    /// Consumes the token and the comments in front of it.
+    fn token(&mut self, token: &'a Token) -> &'a str {
+        self.collect_comments(Some(token));
+        self.line_closed = false;
+
+        token.text()
+    }
+
+    /// Consumes the comments in front of the token, unless they have been consumed already.
+    fn collect_comments(&mut self, token: Option<&'a Token>) {
+        let Some(token) = token else {
+            return;
+        };
+
+        if self.collected != Some(token.span().start) {
+            self.collected = Some(token.span().start);
+            self.comments(token.leading_trivia());
+        }
+    }
+
+    /// A comment directly behind the last line stays there, the others are written in front
+    /// of the next line.
+    fn comments(&mut self, trivia: &'a [Trivia]) {
+        let mut newlines = 0;
+
+        for trivia in trivia {
+            let comment = match trivia.kind() {
+                TriviaKind::Whitespace => {
+                    newlines += trivia.text().matches('\n').count();
+                    continue;
+                }
+                TriviaKind::Comment => trivia.text().trim_end(),
+            };
+
+            let last = match self.lines.last_mut() {
+                Some(Line::Text { comment, .. }) | Some(Line::Field { comment, .. }) => {
+                    Some(comment)
+                }
+                _ => None,
+            };
+
+            match last {
+                Some(last) if newlines == 0 && self.line_closed && last.is_none() => {
+                    *last = Some(comment)
+                }
+                _ => self.pending.push((comment, newlines >= 2)),
+            }
+            newlines = 0;
+        }
+    }
+
+    fn flush_pending(&mut self, indent: usize) {
+        for (comment, blank) in std::mem::take(&mut self.pending) {
+            if blank {
+                self.blank();
+            }
+
+            self.lines.push(Line::Text {
+                indent,
+                text: comment.to_string(),
+                comment: None,
+            });
+        }
+    }
+
+    fn push(&mut self, line: Line<'a>, blank: bool) {
+        let indent = match &line {
+            Line::Blank => self.indent,
+            Line::Text { indent, .. } | Line::Field { indent, .. } => *indent,
+        };
+        self.flush_pending(indent);
+
+        if blank {
+            self.blank();
+        }
+
+        self.lines.push(line);
+        self.line_closed = true;
+    }
+
+    /// Adds a blank line, unless it's the start of the file or of a block.
+    fn blank(&mut self) {
+        match self.lines.last() {
+            None | Some(Line::Blank) => (),
+            Some(Line::Text { text, .. }) if text.ends_with('{') => (),
+            _ => self.lines.push(Line::Blank),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut output = String::new();
+
+        let lines = match self.lines.last() {
+            Some(Line::Blank) => &self.lines[..self.lines.len() - 1],
+            _ => &self.lines[..],
+        };
+
+        for line in lines {
+            let comment = match line {
+                Line::Blank => {
+                    output.push('\n');
+                    continue;
+                }
+                Line::Text {
+                    indent,
+                    text,
+                    comment,
+                } => {
+                    output.push_str(&INDENT.repeat(*indent));
+                    output.push_str(text);
+                    comment
+                }
+                Line::Field {
+                    indent,
+                    datatype,
+                    name,
+                    width,
+                    comment,
+                } => {
+                    output.push_str(&INDENT.repeat(*indent));
+                    output.push_str(&format!("{:width$} {},", datatype, name, width = width));
+                    comment
+                }
+            };
+
+            if let Some(comment) = comment {
+                output.push(' ');
+                output.push_str(comment);
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod format_str {
+        use crate::formatter::format_str;
+
+        #[test]
+        fn just_works() {
+            let source = "// people
+import {Human,Termin} from \"people.tsql\";
+import \"cars.tsql\";
+@naming(column=camel_case);
+@primary_key(id)   as pk_person @unique(id,name) @check(length(name)  > 0)
+table Person {   int id, // the id
+    varchar(64) name,
+
+
+    @nullable @default('a, b')   char(8) nickname,
+  @foreign_key(columns=(owner_id),name=fk_owner) Human owner,
+    // the end
+};
+schema billing { table Invoice { decimal(10,2) amount, }; }";
+
+            let expected = "// people
+import { Human, Termin } from \"people.tsql\";
+import \"cars.tsql\";
+
+@naming(column = camel_case);
+
+@primary_key(id) as pk_person
+@unique(id, name)
+@check(length(name)  > 0)
+table Person {
+    int         id, // the id
+    varchar(64) name,
+
+    @nullable @default('a, b')
+    char(8)     nickname,
+    @foreign_key(columns = (owner_id), name = fk_owner)
+    Human       owner,
+    // the end
+};
+
+schema billing {
+    table Invoice {
+        decimal(10, 2) amount,
+    };
+};
+";
+
+            let formatted = format_str(source).unwrap();
+            assert_eq!(formatted, expected);
+            assert_eq!(format_str(&formatted).unwrap(), expected);
+        }
+
+        #[test]
+        fn errors() {
+            assert!(format_str("table A { int a };").is_err());
+            assert!(format_str("table A { int a, }").is_err());
+        }
+    }
+}
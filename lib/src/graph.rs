@@ -0,0 +1,304 @@
+//! Entity relationship diagrams of the resolved tables, as [mermaid](https://mermaid.js.org)
+//! or [graphviz](https://graphviz.org) dot.
+//!
+//! Every table is drawn with its columns, their types and whether they are part of the primary
+//! key (`PK`) or of a foreign key (`FK`). A foreign key is an edge to the referenced table, its
+//! cardinality follows from the columns: the referenced row is optional if one of them is
+//! nullable, and at most one row references it if they are unique.
+
+use std::io::Write;
+use std::str::FromStr;
+
+use anyhow::{bail, Error, Result};
+
+use crate::types::{ConstraintKind, DataType, Field, ForeignKey, Table, TableCollection};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// `erDiagram` of mermaid, e.g. for markdown
+    #[default]
+    Mermaid,
+    /// a `digraph` of graphviz
+    Dot,
+}
+
+impl FromStr for GraphFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            "dot" => Ok(GraphFormat::Dot),
+            _ => bail!("Error: unknown graph format {:?}", s),
+        }
+    }
+}
+
+/// Writes the diagram of the tables, see the [module](self).
+pub fn write_graph<W: Write>(
+    tables: &TableCollection,
+    format: GraphFormat,
+    buffer: &mut W,
+) -> Result<()> {
+    match format {
+        GraphFormat::Mermaid => write_mermaid(tables, buffer),
+        GraphFormat::Dot => write_dot(tables, buffer),
+    }
+}
+
+/// An edge of the diagram.
+struct Relation<'a> {
+    /// referencing table
+    table: &'a Table,
+    foreign_key: &'a ForeignKey,
+    /// values: `(qualified name of the referenced table, referenced field)`
+    target: &'a (String, String),
+    /// the referenced row is optional, as a column is nullable
+    optional: bool,
+    /// at most one row references the same row, as the columns are unique
+    unique: bool,
+}
+
+fn relations(tables: &TableCollection) -> Vec<Relation<'_>> {
+    let mut relations = Vec::new();
+
+    for table in tables.values() {
+        for foreign_key in table.foreign_keys() {
+            let fields = foreign_key
+                .columns()
+                .iter()
+                .filter_map(|column| table.get_field(column))
+                .collect::<Vec<_>>();
+
+            let Some(target) = fields
+                .first()
+                .and_then(|field| field.foreign_key_reference.as_ref())
+            else {
+                continue;
+            };
+
+            let unique = table
+                .constraints()
+                .iter()
+                .any(|constraint| match constraint.kind() {
+                    ConstraintKind::PrimaryKey(columns) | ConstraintKind::Unique(columns) => {
+                        columns
+                            .iter()
+                            .all(|column| foreign_key.columns().contains(column))
+                    }
+                    ConstraintKind::Check(_) => false,
+                });
+
+            relations.push(Relation {
+                table,
+                foreign_key,
+                target,
+                optional: fields.iter().any(|field| field.is_nullable()),
+                unique,
+            });
+        }
+    }
+
+    relations
+}
+
+/// `PK`, `FK` or both, separated by `, `.
+fn keys(table: &Table, field: &Field) -> String {
+    let mut keys = Vec::with_capacity(2);
+    if table.primary_keys().iter().any(|key| key == field.name()) {
+        keys.push("PK");
+    }
+    if field.foreign_key_reference.is_some() {
+        keys.push("FK");
+    }
+
+    keys.join(", ")
+}
+
+fn write_mermaid<W: Write>(tables: &TableCollection, buffer: &mut W) -> Result<()> {
+    // an entity can't contain a `.`, a table of a schema is labeled with its qualified name
+    let entity = |name: &str| name.replace('.', "_");
+
+    writeln!(buffer, "erDiagram")?;
+
+    for (qualified_name, table) in tables {
+        match table.schema() {
+            Some(_) => writeln!(
+                buffer,
+                "    {}[\"{}\"] {{",
+                entity(qualified_name),
+                qualified_name
+            )?,
+            None => writeln!(buffer, "    {} {{", qualified_name)?,
+        }
+
+        for field in table.fields() {
+            write!(buffer, "        ")?;
+
+            // a type can't contain `,` or spaces, the full type is written as a comment instead
+            match field.datatype() {
+                datatype @ DataType::Decimal(_, _) => {
+                    write!(buffer, "{} {}", datatype.name(), field.name())?
+                }
+                datatype => write!(buffer, "{} {}", datatype, field.name())?,
+            }
+
+            let keys = keys(table, field);
+            if !keys.is_empty() {
+                write!(buffer, " {}", keys)?;
+            }
+
+            if let datatype @ DataType::Decimal(_, _) = field.datatype() {
+                write!(buffer, " \"{}\"", datatype)?;
+            }
+            writeln!(buffer)?;
+        }
+
+        writeln!(buffer, "    }}")?;
+    }
+
+    for relation in relations(tables) {
+        writeln!(
+            buffer,
+            "    {} {}--{} {} : \"{}\"",
+            entity(&relation.target.0),
+            if relation.optional { "|o" } else { "||" },
+            if relation.unique { "o|" } else { "o{" },
+            entity(&relation.table.qualified_name()),
+            relation.foreign_key.name()
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_dot<W: Write>(tables: &TableCollection, buffer: &mut W) -> Result<()> {
+    writeln!(buffer, "digraph tsql {{")?;
+    writeln!(buffer, "    rankdir=LR;")?;
+    writeln!(buffer, "    node [shape=plaintext];")?;
+
+    for (qualified_name, table) in tables {
+        writeln!(buffer)?;
+        writeln!(buffer, "    \"{}\" [label=<", qualified_name)?;
+        writeln!(
+            buffer,
+            "        <table border=\"0\" cellborder=\"1\" cellspacing=\"0\">"
+        )?;
+        writeln!(
+            buffer,
+            "            <tr><td colspan=\"3\"><b>{}</b></td></tr>",
+            qualified_name
+        )?;
+
+        for field in table.fields() {
+            writeln!(
+                buffer,
+                "            <tr><td port=\"{0}\" align=\"left\">{0}</td><td align=\"left\">{1}</td><td>{2}</td></tr>",
+                field.name(),
+                field.datatype(),
+                keys(table, field)
+            )?;
+        }
+
+        writeln!(buffer, "        </table>")?;
+        writeln!(buffer, "    >];")?;
+    }
+
+    let relations = relations(tables);
+    if !relations.is_empty() {
+        writeln!(buffer)?;
+    }
+
+    for relation in relations {
+        // the tail is drawn at the referencing table, the head at the referenced one
+        writeln!(
+            buffer,
+            "    \"{}\":\"{}\" -> \"{}\":\"{}\" [dir=both, arrowtail={}, arrowhead={}, label=\"{}\"];",
+            relation.table.qualified_name(),
+            relation.foreign_key.columns()[0],
+            relation.target.0,
+            relation.target.1,
+            if relation.unique { "teeodot" } else { "crowodot" },
+            if relation.optional { "teeodot" } else { "teetee" },
+            relation.foreign_key.name()
+        )?;
+    }
+
+    writeln!(buffer, "}}")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    mod write_graph {
+        use crate::graph::{write_graph, GraphFormat};
+        use crate::parse_str;
+
+        const SCHEMA: &str = "@primary_key(id) table Human { int id, varchar(32) name, };
+            @primary_key(plate)
+            table Car { varchar(16) plate, @nullable @foreign_key() Human owner, };
+            @primary_key(human_id)
+            table Passport { @foreign_key() Human human, decimal(10, 2) fee, };
+            schema billing {
+                @primary_key(id)
+                table Invoice { int id, @foreign_key() Human customer, };
+            };";
+
+        #[test]
+        fn just_works() {
+            let tables = parse_str(SCHEMA).unwrap();
+
+            let mut out = Vec::new();
+            write_graph(&tables, GraphFormat::Mermaid, &mut out).unwrap();
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                "erDiagram
+    Car {
+        varchar(16) plate PK
+        int owner_id FK
+    }
+    Human {
+        int id PK
+        varchar(32) name
+    }
+    Passport {
+        int human_id PK, FK
+        decimal fee \"decimal(10, 2)\"
+    }
+    billing_Invoice[\"billing.Invoice\"] {
+        int id PK
+        int customer_id FK
+    }
+    Human |o--o{ Car : \"owner\"
+    Human ||--o| Passport : \"human\"
+    Human ||--o{ billing_Invoice : \"customer\"
+"
+            );
+
+            let mut out = Vec::new();
+            write_graph(&tables, GraphFormat::Dot, &mut out).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert!(out.starts_with("digraph tsql {\n"));
+            assert!(out.contains(
+                "<tr><td port=\"human_id\" align=\"left\">human_id</td><td align=\"left\">int</td><td>PK, FK</td></tr>"
+            ));
+            assert!(out.contains(
+                "\"Car\":\"owner_id\" -> \"Human\":\"id\" [dir=both, arrowtail=crowodot, arrowhead=teeodot, label=\"owner\"];"
+            ));
+            assert!(out.contains(
+                "\"Passport\":\"human_id\" -> \"Human\":\"id\" [dir=both, arrowtail=teeodot, arrowhead=teetee, label=\"human\"];"
+            ));
+            assert!(out.contains("\"billing.Invoice\":\"customer_id\" -> \"Human\":\"id\""));
+        }
+
+        #[test]
+        fn errors() {
+            assert_eq!("dot".parse::<GraphFormat>().unwrap(), GraphFormat::Dot);
+            assert_eq!(
+                "svg".parse::<GraphFormat>().unwrap_err().to_string(),
+                "Error: unknown graph format \"svg\""
+            );
+        }
+    }
+}
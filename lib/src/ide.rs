@@ -0,0 +1,907 @@
+//! Analysis of the tsql files of a workspace for editors, which is used by `tsql lsp`.
+//!
+//! Everything works on the [`SyntaxTree`] of the files, so that it keeps working while a file
+//! can't be resolved. Offsets and [`Span`]s are byte offsets into the content of a file.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use crate::ddl::tsql_identifier;
+use crate::loader::{imported_from, Imports, Loader};
+use crate::naming::NamingConvention;
+use crate::syntax::{Node, NodeKind, Span, SyntaxTree, Token, TokenKind};
+use crate::types::{qualified_name, unqualified_name, TableCollection, BUILTIN_TYPES};
+use crate::TransformSQL;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Replaces the text of `span` inside of the file `path` with `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub path: PathBuf,
+    pub span: Span,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Type,
+    Table,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// e.g. `varchar(length)` or the file a table is defined in
+    pub detail: String,
+}
+
+/// Something which can be defined and referenced, tables with the same name in unrelated
+/// files are different symbols.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Symbol {
+    /// file the table is defined in and qualified name of the table
+    Table(PathBuf, String),
+    /// file and qualified name of the table, name of the field
+    Field(PathBuf, String, String),
+}
+
+/// Definition or reference of a [`Symbol`].
+#[derive(Debug)]
+struct Occurrence<'a> {
+    symbol: Symbol,
+    path: &'a Path,
+    /// only the name itself, e.g. `Invoice` of `billing.Invoice`
+    span: Span,
+    definition: bool,
+}
+
+/// Definitions and references of the files without syntax errors.
+#[derive(Debug, Default)]
+struct Index<'a> {
+    occurrences: Vec<Occurrence<'a>>,
+    /// qualified names of the tables defined in each file
+    defined: BTreeMap<&'a Path, HashSet<String>>,
+    imports: BTreeMap<&'a Path, Imports>,
+}
+
+impl<'a> Index<'a> {
+    fn symbol_at(&self, path: &Path, offset: usize) -> Option<&Occurrence<'a>> {
+        self.occurrences
+            .iter()
+            .find(|item| item.path == path && item.span.contains(offset))
+    }
+
+    /// File which defines the table `name` as it is seen from the file `path`: the file
+    /// itself or an imported file, the same rule as [`crate::parse_file`] uses, so that a
+    /// reference only resolves if the file resolves on its own.
+    fn resolve(&self, path: &Path, name: &str) -> Option<PathBuf> {
+        let defines = |file: &Path, name: &str| {
+            self.defined
+                .get(file)
+                .is_some_and(|tables| tables.contains(name))
+        };
+
+        if defines(path, name) {
+            return Some(path.to_path_buf());
+        }
+
+        let imports = self.imports.get(path)?;
+        imported_from(name, imports, defines).map(Path::to_path_buf)
+    }
+}
+
+/// The tsql files of a workspace, with the unsaved content of the files which are open in the
+/// editor.
+#[derive(Debug, Default)]
+pub struct Workspace {
+    naming: NamingConvention,
+    /// content of every file by its canonical path, or by the path as it has been given if the
+    /// file doesn't exist
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl Workspace {
+    pub fn new(naming: NamingConvention) -> Self {
+        Workspace {
+            naming,
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Reads every `.tsql` file inside of the directory and its subdirectories.
+    pub fn add_dir(&mut self, path: &Path) -> Result<()> {
+        let mut entries = path
+            .read_dir()?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+
+        for entry in entries {
+            if entry.is_dir() {
+                self.add_dir(&entry)?;
+            } else if entry
+                .extension()
+                .is_some_and(|extension| extension == "tsql")
+            {
+                let content = read_to_string(&entry)?;
+                self.set_file(&entry, content);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn set_file(&mut self, path: &Path, content: String) {
+        self.files.insert(canonical(path), content);
+    }
+
+    pub fn remove_file(&mut self, path: &Path) {
+        self.files.remove(&canonical(path));
+    }
+
+    pub fn file(&self, path: &Path) -> Option<&str> {
+        self.files.get(&canonical(path)).map(String::as_str)
+    }
+
+    /// Syntax errors of the file, or the first error while resolving its tables together
+    /// with the files it imports.
+    pub fn diagnostics(&self, path: &Path) -> Vec<Diagnostic> {
+        let path = canonical(path);
+        let Some(content) = self.files.get(&path) else {
+            return Vec::new();
+        };
+
+        let error = match SyntaxTree::parse(content) {
+            Ok(_) => match self.resolve(&path) {
+                Ok(_) => return Vec::new(),
+                Err(err) => err,
+            },
+            Err(err) => err,
+        };
+
+        let message = error.to_string();
+        vec![Diagnostic {
+            span: error_span(&message, &path, content),
+            message,
+        }]
+    }
+
+    /// Definition of the table or field at `offset`.
+    pub fn definition(&self, path: &Path, offset: usize) -> Option<Location> {
+        let path = canonical(path);
+        let index = self.index();
+        let symbol = &index.symbol_at(&path, offset)?.symbol;
+
+        index
+            .occurrences
+            .iter()
+            .find(|item| item.definition && &item.symbol == symbol)
+            .map(|item| Location {
+                path: item.path.to_path_buf(),
+                span: item.span,
+            })
+    }
+
+    /// Every reference of the table or field at `offset` inside of the workspace.
+    pub fn references(
+        &self,
+        path: &Path,
+        offset: usize,
+        include_definition: bool,
+    ) -> Vec<Location> {
+        let path = canonical(path);
+        let index = self.index();
+        let Some(symbol) = index.symbol_at(&path, offset).map(|item| &item.symbol) else {
+            return Vec::new();
+        };
+
+        index
+            .occurrences
+            .iter()
+            .filter(|item| &item.symbol == symbol && (include_definition || !item.definition))
+            .map(|item| Location {
+                path: item.path.to_path_buf(),
+                span: item.span,
+            })
+            .collect()
+    }
+
+    /// Span of the name at `offset`, if it can be renamed.
+    pub fn rename_span(&self, path: &Path, offset: usize) -> Option<Span> {
+        let path = canonical(path);
+
+        self.index().symbol_at(&path, offset).map(|item| item.span)
+    }
+
+    /// Renames the table or field at `offset` in every file of the workspace. A table keeps
+    /// its schema, so `name` has to be unqualified.
+    pub fn rename(&self, path: &Path, offset: usize, name: &str) -> Result<Vec<TextEdit>> {
+        let path = canonical(path);
+        let index = self.index();
+        let Some(symbol) = index.symbol_at(&path, offset).map(|item| &item.symbol) else {
+            bail!("Error: There is no table or field at the cursor");
+        };
+
+        tsql_identifier(name)?;
+
+        match symbol {
+            Symbol::Table(_, table) => {
+                if BUILTIN_TYPES.iter().any(|(datatype, _)| *datatype == name) {
+                    bail!("Error: The type {:?} can't be used as a table name", name);
+                }
+
+                let schema = table.rsplit_once('.').map(|(schema, _)| schema);
+                let renamed = qualified_name(schema, name);
+
+                // the new name mustn't be visible already where the table is used
+                if index
+                    .occurrences
+                    .iter()
+                    .filter(|item| &item.symbol == symbol)
+                    .any(|item| index.resolve(item.path, &renamed).is_some())
+                {
+                    bail!("Error: Table {:?} already exists", renamed);
+                }
+            }
+            Symbol::Field(file, table, _) => {
+                let renamed = Symbol::Field(file.clone(), table.clone(), name.to_string());
+
+                if index
+                    .occurrences
+                    .iter()
+                    .any(|item| item.definition && item.symbol == renamed)
+                {
+                    bail!("Error: Table {:?} already has a field {:?}", table, name);
+                }
+            }
+        }
+
+        Ok(index
+            .occurrences
+            .iter()
+            .filter(|item| &item.symbol == symbol)
+            .map(|item| TextEdit {
+                path: item.path.to_path_buf(),
+                span: item.span,
+                text: name.to_string(),
+            })
+            .collect())
+    }
+
+    /// Markdown with the columns of the table or field at `offset` and their sql types.
+    pub fn hover(&self, path: &Path, offset: usize) -> Option<String> {
+        let path = canonical(path);
+        let symbol = self.index().symbol_at(&path, offset)?.symbol.clone();
+        let tables = self.resolve(&path).ok()?;
+
+        let (title, columns) = match &symbol {
+            Symbol::Table(_, name) => {
+                let table = tables.get(name)?;
+
+                (format!("table {}", name), table.fields().collect())
+            }
+            Symbol::Field(_, name, field) => {
+                let table = tables.get(name)?;
+
+                match table.get_field(field) {
+                    Some(field) => (format!("{}.{}", name, field.name()), vec![field]),
+                    None => {
+                        let foreign_key = table
+                            .foreign_keys()
+                            .iter()
+                            .find(|item| item.name() == field)?;
+
+                        let fields = foreign_key
+                            .columns()
+                            .iter()
+                            .filter_map(|column| table.get_field(column))
+                            .collect();
+
+                        (
+                            format!(
+                                "{}.{} references {}({})",
+                                name,
+                                field,
+                                foreign_key.table(),
+                                foreign_key.references().join(", ")
+                            ),
+                            fields,
+                        )
+                    }
+                }
+            }
+        };
+
+        let mut hover = format!("**{}**\n\n```sql\n", title);
+        for column in columns {
+            let mut definition = Vec::new();
+            column.transform(&mut definition).ok()?;
+
+            hover.push_str(&String::from_utf8(definition).ok()?);
+            hover.push('\n');
+        }
+        hover.push_str("```");
+
+        Some(hover)
+    }
+
+    /// Type names and the tables of the workspace, the editor filters them by what has been
+    /// typed.
+    pub fn completions(&self) -> Vec<Completion> {
+        let types = BUILTIN_TYPES.iter().map(|(label, detail)| Completion {
+            label: label.to_string(),
+            kind: CompletionKind::Type,
+            detail: detail.to_string(),
+        });
+
+        let tables = self
+            .index()
+            .occurrences
+            .into_iter()
+            .filter(|item| item.definition)
+            .filter_map(|item| match item.symbol {
+                Symbol::Table(_, table) => Some(Completion {
+                    label: table,
+                    kind: CompletionKind::Table,
+                    detail: item
+                        .path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                }),
+                Symbol::Field(..) => None,
+            });
+
+        types.chain(tables).collect()
+    }
+
+    /// Resolves the tables of the file and of the files it imports.
+    fn resolve(&self, path: &Path) -> Result<TableCollection> {
+        let mut loader = Loader::new(self.naming.clone());
+        for (file, content) in &self.files {
+            loader.overlay(file.clone(), content.clone());
+        }
+
+        loader.add_file(path)?;
+        loader.finish()
+    }
+
+    fn index(&self) -> Index<'_> {
+        let trees = self
+            .files
+            .iter()
+            .filter_map(|(path, content)| Some((path.as_path(), SyntaxTree::parse(content).ok()?)))
+            .collect::<Vec<_>>();
+
+        let mut index = Index::default();
+        for (path, tree) in &trees {
+            let defined = tables(tree)
+                .into_iter()
+                .filter_map(|(schema, table)| Some(table_name(schema, table.name()?.text())))
+                .collect();
+            index.defined.insert(path, defined);
+
+            let directory = path.parent().unwrap_or(Path::new(""));
+            let imports = imports(tree)
+                .into_iter()
+                .map(|(file, names)| {
+                    let names = names
+                        .map(|names| names.iter().map(|name| name.text().to_string()).collect());
+                    (canonical(&directory.join(file)), names)
+                })
+                .collect();
+            index.imports.insert(path, imports);
+        }
+
+        let mut occurrences = Vec::new();
+        for (path, tree) in &trees {
+            let directory = path.parent().unwrap_or(Path::new(""));
+
+            for (file, names) in imports(tree) {
+                let file = canonical(&directory.join(file));
+
+                for name in names.into_iter().flatten() {
+                    if index.resolve(&file, name.text()).as_ref() == Some(&file) {
+                        occurrences.push(Occurrence {
+                            symbol: Symbol::Table(file.clone(), name.text().to_string()),
+                            path,
+                            span: name_span(name),
+                            definition: false,
+                        });
+                    }
+                }
+            }
+
+            for (schema, table) in tables(tree) {
+                table_occurrences(path, schema, table, &index, &mut occurrences);
+            }
+        }
+        index.occurrences = occurrences;
+
+        index
+    }
+}
+
+/// Canonical path of the file, or the path itself if the file doesn't exist.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Imported file of every import statement with the imported names, `None` if every table
+/// is imported.
+fn imports(tree: &SyntaxTree) -> Vec<(&str, Option<Vec<&Token>>)> {
+    let mut imports = Vec::new();
+
+    for statement in tree.statements() {
+        if statement.kind() != NodeKind::Import {
+            continue;
+        }
+
+        let tokens = statement.tokens();
+        let Some(file) = tokens
+            .iter()
+            .find(|token| token.kind() == TokenKind::String)
+        else {
+            continue;
+        };
+
+        let names = tokens.iter().any(|token| token.text() == "{").then(|| {
+            tokens
+                .iter()
+                .filter(|token| {
+                    token.kind() == TokenKind::Word && !["import", "from"].contains(&token.text())
+                })
+                .copied()
+                .collect()
+        });
+
+        imports.push((file.text().trim_matches('"'), names));
+    }
+
+    imports
+}
+
+/// Table nodes of the tree with the schema block they are written in.
+fn tables(tree: &SyntaxTree) -> Vec<(Option<&str>, &Node)> {
+    let mut tables = Vec::new();
+
+    for statement in tree.statements() {
+        match statement.kind() {
+            NodeKind::Table => tables.push((None, statement)),
+            NodeKind::Schema => {
+                let schema = statement.name().map(Token::text);
+                tables.extend(statement.nodes().map(|table| (schema, table)));
+            }
+            _ => (),
+        }
+    }
+
+    tables
+}
+
+/// Qualified name of a table which is written as `name` inside of the schema block `schema`.
+fn table_name(schema: Option<&str>, name: &str) -> String {
+    if name.contains('.') {
+        name.to_string()
+    } else {
+        qualified_name(schema, name)
+    }
+}
+
+/// Span of the unqualified part of a name, e.g. `Invoice` of `billing.Invoice`.
+fn name_span(token: &Token) -> Span {
+    let span = token.span();
+
+    Span {
+        start: span.end - unqualified_name(token.text()).len(),
+        end: span.end,
+    }
+}
+
+fn table_occurrences<'a>(
+    path: &'a Path,
+    schema: Option<&str>,
+    table: &Node,
+    index: &Index,
+    occurrences: &mut Vec<Occurrence<'a>>,
+) {
+    let Some(name) = table.name() else {
+        return;
+    };
+    let qualified = table_name(schema, name.text());
+
+    occurrences.push(Occurrence {
+        symbol: Symbol::Table(path.to_path_buf(), qualified.clone()),
+        path,
+        span: name_span(name),
+        definition: true,
+    });
+
+    let field =
+        |field: &str| Symbol::Field(path.to_path_buf(), qualified.clone(), field.to_string());
+
+    for node in table.nodes() {
+        match node.kind() {
+            // fields of the primary key and of unique constraints
+            NodeKind::Annotation => {
+                let annotation = node.name().map(Token::text);
+                let Some(arguments) = node.arguments() else {
+                    continue;
+                };
+
+                if matches!(annotation, Some("@primary_key" | "@unique")) {
+                    for (start, name) in identifiers(arguments.text()) {
+                        let start = arguments.span().start + start;
+
+                        occurrences.push(Occurrence {
+                            symbol: field(name),
+                            path,
+                            span: Span {
+                                start,
+                                end: start + name.len(),
+                            },
+                            definition: false,
+                        });
+                    }
+                }
+            }
+            NodeKind::Field => {
+                let Some(name) = node.name() else {
+                    continue;
+                };
+
+                occurrences.push(Occurrence {
+                    symbol: field(name.text()),
+                    path,
+                    span: name.span(),
+                    definition: true,
+                });
+
+                let datatype = node
+                    .nodes()
+                    .find(|node| node.kind() == NodeKind::DataType)
+                    .and_then(Node::name);
+
+                let Some(datatype) = datatype else {
+                    continue;
+                };
+                if BUILTIN_TYPES
+                    .iter()
+                    .any(|(name, _)| *name == datatype.text())
+                {
+                    continue;
+                }
+
+                // unqualified names reference a table of the same schema first
+                let referenced = [
+                    table_name(schema, datatype.text()),
+                    datatype.text().to_string(),
+                ]
+                .into_iter()
+                .find_map(|name| Some((index.resolve(path, &name)?, name)));
+
+                if let Some((file, referenced)) = referenced {
+                    occurrences.push(Occurrence {
+                        symbol: Symbol::Table(file, referenced),
+                        path,
+                        span: name_span(datatype),
+                        definition: false,
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Identifiers inside of the arguments of an annotation with their offset, e.g. `a` and `b` of
+/// `(a, b)`.
+fn identifiers(arguments: &str) -> Vec<(usize, &str)> {
+    let mut identifiers = Vec::new();
+    let mut start = None;
+
+    for (index, c) in arguments
+        .char_indices()
+        .chain(std::iter::once((arguments.len(), ' ')))
+    {
+        match (start, c.is_alphanumeric() || c == '_') {
+            (None, true) => start = Some(index),
+            (Some(begin), false) => {
+                let identifier = &arguments[begin..index];
+                if !identifier.starts_with(|c: char| c.is_numeric()) {
+                    identifiers.push((begin, identifier));
+                }
+
+                start = None;
+            }
+            _ => (),
+        }
+    }
+
+    identifiers
+}
+
+/// Span of the first location like `3:7` or `file.tsql:3:7` inside of the error message which
+/// points into the file, the start of the file if there is none.
+fn error_span(message: &str, path: &Path, content: &str) -> Span {
+    if message.contains("at the end of the file") {
+        return Span {
+            start: content.len(),
+            end: content.len(),
+        };
+    }
+
+    for word in message.split_whitespace() {
+        let word = word.trim_end_matches([',', ':']);
+        let mut parts = word.rsplitn(3, ':');
+
+        let (Some(Ok(column)), Some(Ok(line))) = (
+            parts.next().map(str::parse::<usize>),
+            parts.next().map(str::parse::<usize>),
+        ) else {
+            continue;
+        };
+
+        if let Some(file) = parts.next() {
+            let matches = match Path::new(file).canonicalize() {
+                Ok(file) => file == path,
+                Err(_) => path.ends_with(file),
+            };
+
+            if !matches {
+                continue;
+            }
+        }
+
+        let line_start = content
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum::<usize>();
+
+        let start = line_start
+            + content[line_start..]
+                .chars()
+                .take(column.saturating_sub(1))
+                .map(char::len_utf8)
+                .sum::<usize>();
+        let length = content[start..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(content.len() - start);
+
+        return Span {
+            start,
+            end: start + length.max(1).min(content.len() - start),
+        };
+    }
+
+    Span::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::ide::Workspace;
+    use crate::naming::NamingConvention;
+
+    const PEOPLE: &str = "@primary_key(id)
+table Human {
+    int id,
+    varchar(32) name,
+};
+";
+
+    const MAIN: &str = "import { Human } from \"people.tsql\";
+
+@primary_key(id)
+table Pet {
+    int id,
+    @foreign_key()
+    Human owner,
+};
+";
+
+    fn workspace() -> Workspace {
+        let mut workspace = Workspace::new(NamingConvention::default());
+        workspace.set_file(Path::new("people.tsql"), PEOPLE.to_string());
+        workspace.set_file(Path::new("main.tsql"), MAIN.to_string());
+
+        workspace
+    }
+
+    mod diagnostics {
+        use std::path::Path;
+
+        use crate::ide::tests::workspace;
+
+        #[test]
+        fn just_works() {
+            let mut workspace = workspace();
+            assert!(workspace.diagnostics(Path::new("main.tsql")).is_empty());
+
+            workspace.set_file(
+                Path::new("main.tsql"),
+                "table Pet {\n    int id\n};".to_string(),
+            );
+            let diagnostics = workspace.diagnostics(Path::new("main.tsql"));
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(
+                diagnostics[0].message,
+                "Error: Expected \",\" at 3:1, got \"}\""
+            );
+            assert_eq!(diagnostics[0].span.start, 23);
+        }
+    }
+
+    mod definition {
+        use std::path::Path;
+
+        use crate::ide::tests::{workspace, MAIN, PEOPLE};
+
+        #[test]
+        fn just_works() {
+            let workspace = workspace();
+
+            let offset = MAIN.find("Human owner").unwrap() + 2;
+            let location = workspace
+                .definition(Path::new("main.tsql"), offset)
+                .unwrap();
+            assert!(location.path.ends_with("people.tsql"));
+            assert_eq!(&PEOPLE[location.span.start..location.span.end], "Human");
+
+            let offset = PEOPLE.find("id,").unwrap();
+            let location = workspace
+                .definition(Path::new("people.tsql"), offset + 1)
+                .unwrap();
+            assert_eq!(location.span.start, offset);
+
+            assert_eq!(workspace.definition(Path::new("main.tsql"), 0), None);
+        }
+
+        #[test]
+        fn not_imported() {
+            let mut workspace = workspace();
+            let content = MAIN.replace("import { Human } from \"people.tsql\";\n", "");
+            workspace.set_file(Path::new("main.tsql"), content.clone());
+
+            // the loader rejects the reference, so there is nothing to navigate to
+            let offset = content.find("Human owner").unwrap() + 2;
+            assert_eq!(workspace.definition(Path::new("main.tsql"), offset), None);
+            assert_eq!(workspace.diagnostics(Path::new("main.tsql")).len(), 1);
+        }
+    }
+
+    mod references {
+        use std::path::Path;
+
+        use crate::ide::tests::{workspace, PEOPLE};
+
+        #[test]
+        fn just_works() {
+            let workspace = workspace();
+            let offset = PEOPLE.find("Human").unwrap();
+
+            let references = workspace.references(Path::new("people.tsql"), offset, true);
+            assert_eq!(references.len(), 3);
+            assert_eq!(
+                workspace
+                    .references(Path::new("people.tsql"), offset, false)
+                    .len(),
+                2
+            );
+
+            // the primary key references the field
+            let offset = PEOPLE.find("int id").unwrap() + 4;
+            assert_eq!(
+                workspace
+                    .references(Path::new("people.tsql"), offset, true)
+                    .len(),
+                2
+            );
+        }
+    }
+
+    mod rename {
+        use std::path::Path;
+
+        use crate::ide::tests::{workspace, MAIN, PEOPLE};
+
+        #[test]
+        fn just_works() {
+            let workspace = workspace();
+            let offset = PEOPLE.find("Human").unwrap();
+
+            let edits = workspace
+                .rename(Path::new("people.tsql"), offset, "Person")
+                .unwrap();
+            assert_eq!(edits.len(), 3);
+            assert!(edits.iter().all(|edit| edit.text == "Person"));
+
+            let offset = MAIN.find("owner").unwrap();
+            let edits = workspace
+                .rename(Path::new("main.tsql"), offset, "keeper")
+                .unwrap();
+            assert_eq!(edits.len(), 1);
+        }
+
+        #[test]
+        fn errors() {
+            let workspace = workspace();
+            let offset = PEOPLE.find("Human").unwrap();
+
+            assert!(workspace
+                .rename(Path::new("people.tsql"), offset, "Pet")
+                .is_err());
+            assert!(workspace
+                .rename(Path::new("people.tsql"), offset, "int")
+                .is_err());
+            assert!(workspace
+                .rename(Path::new("people.tsql"), offset, "a b")
+                .is_err());
+            assert!(workspace
+                .rename(
+                    Path::new("people.tsql"),
+                    PEOPLE.find("int id").unwrap() + 4,
+                    "name"
+                )
+                .is_err());
+            assert!(workspace.rename(Path::new("people.tsql"), 0, "a").is_err());
+        }
+    }
+
+    mod hover {
+        use std::path::Path;
+
+        use crate::ide::tests::{workspace, MAIN};
+
+        #[test]
+        fn just_works() {
+            let workspace = workspace();
+
+            let offset = MAIN.find("owner").unwrap();
+            assert_eq!(
+                workspace.hover(Path::new("main.tsql"), offset).unwrap(),
+                "**Pet.owner references Human(id)**\n\n```sql\nowner_id int NOT NULL\n```"
+            );
+
+            let offset = MAIN.find("Pet").unwrap();
+            assert_eq!(
+                workspace.hover(Path::new("main.tsql"), offset).unwrap(),
+                "**table Pet**\n\n```sql\nid int NOT NULL\nowner_id int NOT NULL\n```"
+            );
+        }
+    }
+
+    mod completions {
+        use crate::ide::tests::workspace;
+        use crate::ide::CompletionKind;
+
+        #[test]
+        fn just_works() {
+            let completions = workspace().completions();
+
+            assert!(completions
+                .iter()
+                .any(|item| item.label == "varchar" && item.kind == CompletionKind::Type));
+            assert!(completions
+                .iter()
+                .any(|item| item.label == "Human" && item.kind == CompletionKind::Table));
+        }
+    }
+}
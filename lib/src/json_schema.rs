@@ -0,0 +1,331 @@
+//! JSON Schema (draft 2020-12) of the resolved tables, e.g. to validate the payloads of an
+//! api against the database schema. Behind the `serde` feature.
+//!
+//! Every table becomes an object schema in `$defs`:
+//!
+//! - `varchar(n)`, `char(n)` and `text(n)` are strings with a `maxLength`
+//! - `uuid`, `date`, `datetime` and `time` are strings with a `format`
+//! - `int` and `bigint` are integers within the range of the sql type, `decimal(p, s)` is a
+//!   number below `10^(p - s)` with `s` decimal places
+//! - nullable fields also accept `null`, the fields of the primary key are `required`
+//! - comments become the `description`, literal defaults the `default`
+//!
+//! `@check` expressions are translated where possible: comparisons of a column with a number,
+//! `length(column)` with a number, `column IN (values)` as an `enum` and `column <> ''`, joined
+//! with `AND`. Other checks are left out, the database still enforces them.
+
+use serde_json::{json, Map, Value};
+
+use crate::check::{self, check_conditions, Condition, Literal, Operator};
+use crate::types::{ConstraintKind, DataType, Field, Table, TableCollection};
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Schema with a definition of every table in `$defs`, referenced as `#/$defs/{qualified name}`.
+pub fn json_schema(tables: &TableCollection) -> Value {
+    let definitions = tables
+        .iter()
+        .map(|(name, table)| (name.clone(), table_schema(table)))
+        .collect::<Map<_, _>>();
+
+    json!({
+        "$schema": DRAFT,
+        "$defs": definitions,
+    })
+}
+
+/// Object schema of a single table, without `$schema`.
+pub fn table_schema(table: &Table) -> Value {
+    let mut properties = Map::new();
+    for field in table.fields() {
+        properties.insert(field.name().to_string(), field_schema(field));
+    }
+
+    for constraint in table.constraints() {
+        if let ConstraintKind::Check(expression) = constraint.kind() {
+            for (column, condition) in check_conditions(expression) {
+                let rule = Rule::from_condition(condition);
+                if let (Some(Value::Object(property)), Some(rule)) =
+                    (properties.get_mut(&column), rule)
+                {
+                    rule.apply(property);
+                }
+            }
+        }
+    }
+
+    // `null` is a valid value of a nullable field, also if its values are restricted
+    for field in table.fields().filter(|field| field.is_nullable()) {
+        if let Some(Value::Array(values)) = properties[field.name()].get_mut("enum") {
+            values.push(Value::Null);
+        }
+    }
+
+    let mut schema = Map::new();
+    schema.insert("title".to_string(), json!(table.qualified_name()));
+    if let Some(comment) = table.comment() {
+        schema.insert("description".to_string(), json!(comment));
+    }
+    schema.insert("type".to_string(), json!("object"));
+    schema.insert("properties".to_string(), Value::Object(properties));
+    schema.insert("required".to_string(), json!(table.primary_keys()));
+    schema.insert("additionalProperties".to_string(), json!(false));
+
+    Value::Object(schema)
+}
+
+fn field_schema(field: &Field) -> Value {
+    let mut schema = Map::new();
+
+    let datatype = field.datatype();
+    let type_name = match datatype {
+        DataType::Int | DataType::BigInt => "integer",
+        DataType::Bool => "boolean",
+        DataType::Double | DataType::Float | DataType::Decimal(_, _) => "number",
+        DataType::Date
+        | DataType::DateTime
+        | DataType::Time
+        | DataType::Uuid
+        | DataType::VarChar(_)
+        | DataType::Char(_)
+        | DataType::Text(_) => "string",
+    };
+    schema.insert(
+        "type".to_string(),
+        match field.is_nullable() {
+            true => json!([type_name, "null"]),
+            false => json!(type_name),
+        },
+    );
+
+    match *datatype {
+        DataType::Int => {
+            schema.insert("minimum".to_string(), json!(i32::MIN));
+            schema.insert("maximum".to_string(), json!(i32::MAX));
+        }
+        DataType::BigInt => {
+            schema.insert("minimum".to_string(), json!(i64::MIN));
+            schema.insert("maximum".to_string(), json!(i64::MAX));
+        }
+        DataType::Date => {
+            schema.insert("format".to_string(), json!("date"));
+        }
+        DataType::DateTime => {
+            schema.insert("format".to_string(), json!("date-time"));
+        }
+        DataType::Time => {
+            schema.insert("format".to_string(), json!("time"));
+        }
+        DataType::Uuid => {
+            schema.insert("format".to_string(), json!("uuid"));
+        }
+        DataType::VarChar(length) | DataType::Text(length) => {
+            schema.insert("maxLength".to_string(), json!(length));
+        }
+        DataType::Char(length) => {
+            schema.insert("maxLength".to_string(), json!(length));
+        }
+        DataType::Decimal(precision, scale) => {
+            let bound = 10f64.powi(i32::from(precision) - i32::from(scale));
+            schema.insert("exclusiveMinimum".to_string(), json!(-bound));
+            schema.insert("exclusiveMaximum".to_string(), json!(bound));
+            if scale > 0 {
+                schema.insert(
+                    "multipleOf".to_string(),
+                    json!(10f64.powi(-i32::from(scale))),
+                );
+            }
+        }
+        DataType::Bool | DataType::Double | DataType::Float => (),
+    }
+
+    if let Some(comment) = field.comment() {
+        schema.insert("description".to_string(), json!(comment));
+    }
+
+    // expressions like `now()` are evaluated by the database
+    if let Some(default) = field.default().and_then(check::literal_value) {
+        schema.insert("default".to_string(), literal_value(default));
+    }
+
+    Value::Object(schema)
+}
+
+fn literal_value(literal: Literal) -> Value {
+    match literal {
+        Literal::Text(text) => json!(text),
+        Literal::Integer(integer) => json!(integer),
+        Literal::Float(float) => json!(float),
+        Literal::Bool(bool) => json!(bool),
+    }
+}
+
+/// A constraint of a single column which can be expressed in JSON Schema.
+#[derive(Debug, Clone, PartialEq)]
+enum Rule {
+    /// values: `(keyword, bound)`, e.g. `minimum` or `maxLength`
+    Bound(&'static str, Value),
+    /// values: allowed values
+    Enum(Vec<Value>),
+}
+
+impl Rule {
+    fn from_condition(condition: Condition) -> Option<Self> {
+        let (keyword, bound) = match condition {
+            Condition::Compare(Operator::NotEqual, Literal::Text(text)) if text.is_empty() => {
+                ("minLength", json!(1))
+            }
+            Condition::Compare(operator, literal @ (Literal::Integer(_) | Literal::Float(_))) => {
+                let keyword = match operator {
+                    Operator::Less => "exclusiveMaximum",
+                    Operator::LessEqual => "maximum",
+                    Operator::Greater => "exclusiveMinimum",
+                    Operator::GreaterEqual => "minimum",
+                    Operator::NotEqual => return None,
+                };
+
+                (keyword, literal_value(literal))
+            }
+            Condition::Length(operator, Literal::Integer(length)) => {
+                let length = u64::try_from(length).ok()?;
+                let (keyword, length) = match operator {
+                    Operator::Less => ("maxLength", length.checked_sub(1)?),
+                    Operator::LessEqual => ("maxLength", length),
+                    Operator::Greater => ("minLength", length + 1),
+                    Operator::GreaterEqual => ("minLength", length),
+                    Operator::NotEqual => return None,
+                };
+
+                (keyword, json!(length))
+            }
+            Condition::In(values) => {
+                return Some(Rule::Enum(values.into_iter().map(literal_value).collect()));
+            }
+            _ => return None,
+        };
+
+        Some(Rule::Bound(keyword, bound))
+    }
+
+    /// Adds the rule to the schema of the column, a bound is only replaced by a stricter one.
+    fn apply(self, property: &mut Map<String, Value>) {
+        match self {
+            Rule::Bound(keyword, bound) => {
+                let lower = keyword.starts_with("min") || keyword == "exclusiveMinimum";
+                let stricter = match (
+                    property.get(keyword).and_then(Value::as_f64),
+                    bound.as_f64(),
+                ) {
+                    (Some(existing), Some(new)) => {
+                        (lower && new > existing) || (!lower && new < existing)
+                    }
+                    _ => true,
+                };
+
+                if stricter {
+                    property.insert(keyword.to_string(), bound);
+                }
+            }
+            Rule::Enum(values) => {
+                property.insert("enum".to_string(), Value::Array(values));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod json_schema {
+        use serde_json::json;
+
+        use crate::json_schema::json_schema;
+        use crate::parse_str;
+
+        #[test]
+        fn just_works() {
+            let tables = parse_str(
+                "// a person
+                @primary_key(id)
+                @check(length(name) > 0)
+                @check((age >= 0) AND (age < 150))
+                @check(status IN ('active', 'blocked'))
+                @check(code <> '')
+                table Person {
+                    uuid id,
+                    varchar(32) name, // full name
+                    @nullable int age,
+                    @default('active') char(8) status,
+                    @nullable @default(now()) datetime created,
+                    decimal(5, 2) balance,
+                    text(64) code,
+                    @nullable varchar(8) role,
+                };",
+            )
+            .unwrap();
+
+            let schema = json_schema(&tables);
+            assert_eq!(
+                schema["$schema"],
+                "https://json-schema.org/draft/2020-12/schema"
+            );
+
+            let person = &schema["$defs"]["Person"];
+            assert_eq!(person["description"], "a person");
+            assert_eq!(person["required"], json!(["id"]));
+            assert_eq!(person["additionalProperties"], json!(false));
+
+            let properties = &person["properties"];
+            assert_eq!(
+                properties["id"],
+                json!({ "type": "string", "format": "uuid" })
+            );
+            assert_eq!(
+                properties["name"],
+                json!({ "type": "string", "maxLength": 32, "minLength": 1, "description": "full name" })
+            );
+            assert_eq!(
+                properties["age"],
+                json!({ "type": ["integer", "null"], "minimum": 0, "maximum": i32::MAX, "exclusiveMaximum": 150 })
+            );
+            assert_eq!(
+                properties["status"],
+                json!({ "type": "string", "maxLength": 8, "default": "active", "enum": ["active", "blocked"] })
+            );
+            assert_eq!(
+                properties["created"],
+                json!({ "type": ["string", "null"], "format": "date-time" })
+            );
+            assert_eq!(
+                properties["balance"],
+                json!({ "type": "number", "exclusiveMinimum": -1000.0, "exclusiveMaximum": 1000.0, "multipleOf": 0.01 })
+            );
+            assert_eq!(properties["code"]["minLength"], json!(1));
+        }
+
+        #[test]
+        fn errors() {
+            // checks which can't be expressed are left out
+            let tables = parse_str(
+                "@check((birth > '1900-01-01') AND (birth < '2100-01-01'))
+                @check(a + b > 0)
+                @check(lower(name) <> name)
+                table Person { date birth, int a, int b, varchar(8) name, };",
+            )
+            .unwrap();
+
+            let properties = &json_schema(&tables)["$defs"]["Person"]["properties"];
+            assert_eq!(
+                properties["birth"],
+                json!({ "type": "string", "format": "date" })
+            );
+            assert_eq!(
+                properties["a"],
+                json!({ "type": "integer", "minimum": i32::MIN, "maximum": i32::MAX })
+            );
+            assert_eq!(
+                properties["name"],
+                json!({ "type": "string", "maxLength": 8 })
+            );
+        }
+    }
+}
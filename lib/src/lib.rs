@@ -1,47 +1,181 @@
-#![feature(variant_count)]
-
-use std::cell::RefCell;
-use std::collections::BTreeMap;
-use std::fs::read_to_string;
+use std::collections::BTreeSet;
 use std::io::Write;
-use std::path::Path;
-use std::rc::Rc;
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Result};
-use types::{Table, TableCollection};
-
-use crate::parser::parse;
+use dialect::Dialect;
+use loader::Loader;
+use naming::NamingConvention;
+use types::{dependency_order, Table, TableCollection};
 
+pub mod build;
+pub mod builder;
+mod check;
+pub mod codegen;
+pub mod ddl;
+pub mod dialect;
+pub mod diff;
+pub mod formatter;
+pub mod graph;
+pub mod ide;
+#[cfg(feature = "serde")]
+pub mod json_schema;
+mod loader;
+pub mod migrations;
+pub mod naming;
 mod parser;
+pub mod printer;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod syntax;
 pub mod types;
 
-pub fn parse_str(mut content: &str) -> Result<TableCollection> {
-    let mut raw_tables = BTreeMap::new();
+pub fn parse_str(content: &str) -> Result<TableCollection> {
+    parse_str_with_naming(content, NamingConvention::default())
+}
 
-    while !content.is_empty() {
-        let out = parse(content);
+/// Parses the content with `naming` as the default naming convention, a `@naming(...);`
+/// directive inside the content takes precedence over it.
+///
+/// Imports are only supported by [`parse_file`] and [`parse_dir`]. The generated names aren't
+/// checked against the identifier length of a database, see [`Dialect::validate`].
+pub fn parse_str_with_naming(content: &str, naming: NamingConvention) -> Result<TableCollection> {
+    let mut loader = Loader::new(naming);
+    loader.add_str(content)?;
 
-        match out {
-            Ok((c, table)) => {
-                let name = table.name.clone();
-                raw_tables.insert(name, Rc::new(RefCell::new(table)));
+    loader.finish()
+}
 
-                content = c;
-            }
-            Err(err) => bail!("An error occurred while parsing: {:?}", err),
-        }
-    }
+/// Parses the file and every file it imports, with `import "path.tsql";` or
+/// `import { Table } from "path.tsql";`. Paths are relative to the importing file.
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<TableCollection> {
+    parse_file_with_naming(path, NamingConvention::default())
+}
+
+/// See [`parse_file`] and [`parse_str_with_naming`], a `@naming(...);` directive only applies to
+/// the file it is written in.
+pub fn parse_file_with_naming<P: AsRef<Path>>(
+    path: P,
+    naming: NamingConvention,
+) -> Result<TableCollection> {
+    let mut loader = Loader::new(naming);
+    loader.add_file(path.as_ref())?;
 
-    Table::parse_raw_tables(raw_tables)
+    loader.finish()
 }
 
-pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<TableCollection> {
-    // TODO check if the `.replace(...)` is necessary
-    let content = read_to_string(path)?.replace('\n', "");
+/// See [`parse_file_with_naming`], also returns the canonical paths of the file and of every
+/// file it imports, e.g. to rebuild when one of them changes.
+pub fn parse_file_with_sources<P: AsRef<Path>>(
+    path: P,
+    naming: NamingConvention,
+) -> Result<(TableCollection, Vec<PathBuf>)> {
+    let mut loader = Loader::new(naming);
+    loader.add_file(path.as_ref())?;
 
-    parse_str(&content)
+    let files = loader.files();
+    Ok((loader.finish()?, files))
+}
+
+/// Parses every `.tsql` file inside of the directory and its subdirectories, see [`parse_file`].
+/// The files don't have to import each other, a foreign key can reference a table of any file
+/// inside of the directory.
+pub fn parse_dir<P: AsRef<Path>>(path: P) -> Result<TableCollection> {
+    parse_dir_with_naming(path, NamingConvention::default())
+}
+
+/// See [`parse_dir`] and [`parse_file_with_naming`].
+pub fn parse_dir_with_naming<P: AsRef<Path>>(
+    path: P,
+    naming: NamingConvention,
+) -> Result<TableCollection> {
+    let mut loader = Loader::new(naming);
+    loader.add_dir(path.as_ref())?;
+
+    loader.finish()
 }
 
 pub trait TransformSQL {
     fn transform<W: Write>(&self, buffer: &mut W) -> Result<()>;
 }
+
+/// Writes a `CREATE SCHEMA` statement for every used schema, if the dialect supports schemas,
+/// followed by the tables in the order of their dependencies.
+pub fn transform_tables<W: Write>(
+    tables: &TableCollection,
+    dialect: Dialect,
+    buffer: &mut W,
+) -> Result<()> {
+    if dialect.supports_schemas() {
+        for schema in schemas(tables) {
+            writeln!(buffer, "CREATE SCHEMA IF NOT EXISTS {};", schema)?;
+        }
+    }
+
+    for table in dependency_order(tables) {
+        table.transform(buffer)?;
+    }
+
+    Ok(())
+}
+
+/// Options of [`transform_drop_tables`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DropOptions {
+    /// `DROP TABLE IF EXISTS`, doesn't fail if the table has already been dropped
+    pub if_exists: bool,
+    /// `DROP TABLE ... CASCADE`, also drops the objects which depend on the table
+    pub cascade: bool,
+    /// `DROP SCHEMA IF EXISTS` for every schema of the tables, never with `CASCADE`, so that a
+    /// schema which still contains other objects isn't dropped
+    pub drop_schemas: bool,
+}
+
+/// Writes a `DROP TABLE` statement for every table, in the reverse order of
+/// [`transform_tables`] so that a table is dropped before the tables it references. The schemas
+/// of the tables are only dropped with [`DropOptions::drop_schemas`], after their tables.
+/// Nothing is written if the dialect doesn't support the options.
+pub fn transform_drop_tables<W: Write>(
+    tables: &TableCollection,
+    dialect: Dialect,
+    options: DropOptions,
+    buffer: &mut W,
+) -> Result<()> {
+    if options.cascade && dialect == Dialect::Sqlite {
+        bail!("Error: Sqlite doesn't support dropping tables with CASCADE");
+    }
+    if options.drop_schemas && dialect == Dialect::MySql {
+        bail!("Error: MySql can't drop a schema without its content, a schema is a database");
+    }
+
+    for table in dependency_order(tables).into_iter().rev() {
+        write!(buffer, "DROP TABLE ")?;
+
+        if options.if_exists {
+            write!(buffer, "IF EXISTS ")?;
+        }
+
+        write!(buffer, "{}", table.qualified_name())?;
+
+        if options.cascade {
+            write!(buffer, " CASCADE")?;
+        }
+
+        writeln!(buffer, ";")?;
+    }
+
+    if options.drop_schemas && dialect.supports_schemas() {
+        for schema in schemas(tables) {
+            writeln!(buffer, "DROP SCHEMA IF EXISTS {};", schema)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Schemas of the tables, without duplicates.
+fn schemas(tables: &TableCollection) -> BTreeSet<&str> {
+    tables.values().filter_map(Table::schema).collect()
+}
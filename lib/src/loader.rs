@@ -0,0 +1,449 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::naming::NamingConvention;
+use crate::parser::types::{RawImport, RawTable, Statement};
+use crate::parser::Lowering;
+use crate::syntax::SyntaxTree;
+use crate::types::{format_location, RawTableCollection, Table, TableCollection};
+
+/// Tables and imports of a single file or string.
+struct Source {
+    /// path of the file as it has been given or as it has been written in the import, used
+    /// for messages
+    path: Option<PathBuf>,
+    tables: Vec<RawTable>,
+    imports: Imports,
+}
+
+/// Canonical path of every imported file and the imported table names, `None` if all tables
+/// are imported.
+pub(crate) type Imports = Vec<(PathBuf, Option<Vec<String>>)>;
+
+impl Source {
+    /// `name` has to be qualified if the table is part of a schema.
+    fn defines(&self, name: &str) -> bool {
+        self.tables
+            .iter()
+            .any(|table| table.qualified_name() == name)
+    }
+}
+
+/// Loads schema files together with the files they import and resolves them into a single
+/// [`TableCollection`].
+pub(crate) struct Loader {
+    naming: NamingConvention,
+    /// every loaded source by its canonical path, a string source has an empty path
+    sources: BTreeMap<PathBuf, Source>,
+    /// sources whose tables are all part of the result
+    entries: Vec<PathBuf>,
+    /// canonical paths of the files added with [`Loader::add_dir`], they see the tables of
+    /// each other without importing them
+    directory: HashSet<PathBuf>,
+    /// canonical paths of the files which are currently being loaded, to detect import cycles
+    stack: Vec<PathBuf>,
+    /// content of files by their canonical path which is used instead of the file on disk,
+    /// e.g. unsaved changes in an editor
+    overlay: HashMap<PathBuf, String>,
+}
+
+impl Loader {
+    /// `naming` is the default for every file, a `@naming(...);` directive only applies to
+    /// the tables of the file it is written in.
+    pub(crate) fn new(naming: NamingConvention) -> Self {
+        Loader {
+            naming,
+            sources: BTreeMap::new(),
+            entries: Vec::new(),
+            directory: HashSet::new(),
+            stack: Vec::new(),
+            overlay: HashMap::new(),
+        }
+    }
+
+    /// Uses `content` instead of the file at the canonical path `path`, the file doesn't have
+    /// to exist.
+    pub(crate) fn overlay(&mut self, path: PathBuf, content: String) {
+        self.overlay.insert(path, content);
+    }
+
+    pub(crate) fn add_str(&mut self, content: &str) -> Result<()> {
+        let (tables, raw_imports) = parse_source(content, None, &self.naming)?;
+
+        if let Some(raw_import) = raw_imports.first() {
+            bail!(
+                "Error: Can't import {:?} at {}, imports are only supported when parsing a file",
+                raw_import.path,
+                raw_import.position
+            );
+        }
+
+        let key = PathBuf::new();
+        self.sources.insert(
+            key.clone(),
+            Source {
+                path: None,
+                tables,
+                imports: Vec::new(),
+            },
+        );
+        self.entries.push(key);
+
+        Ok(())
+    }
+
+    /// Adds the file and returns its canonical path.
+    pub(crate) fn add_file(&mut self, path: &Path) -> Result<PathBuf> {
+        let canonical = match path.canonicalize() {
+            Ok(canonical) => canonical,
+            Err(_) if self.overlay.contains_key(path) => path.to_path_buf(),
+            Err(err) => bail!("Error: Can't read the file {:?}: {}", path, err),
+        };
+
+        self.load_file(path, canonical.clone())?;
+        self.entries.push(canonical.clone());
+
+        Ok(canonical)
+    }
+
+    /// Adds every `.tsql` file inside of the directory and its subdirectories, the files see
+    /// the tables of each other as if they were a single file.
+    pub(crate) fn add_dir(&mut self, path: &Path) -> Result<()> {
+        let mut entries = path
+            .read_dir()?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+
+        for entry in entries {
+            if entry.is_dir() {
+                self.add_dir(&entry)?;
+            } else if entry
+                .extension()
+                .is_some_and(|extension| extension == "tsql")
+            {
+                let canonical = self.add_file(&entry)?;
+                self.directory.insert(canonical);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_file(&mut self, path: &Path, canonical: PathBuf) -> Result<()> {
+        if let Some(index) = self.stack.iter().position(|item| item == &canonical) {
+            let cycle = self.stack[index..]
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|item| self.display_path(item))
+                .collect::<Vec<_>>();
+
+            bail!("Error: Import cycle detected: {}", cycle.join(" -> "));
+        }
+
+        if self.sources.contains_key(&canonical) {
+            return Ok(());
+        }
+
+        let content = match self.overlay.get(&canonical) {
+            Some(content) => content.clone(),
+            None => read_to_string(&canonical)
+                .map_err(|err| anyhow!("Error: Can't read the file {:?}: {}", path, err))?,
+        };
+        let (tables, raw_imports) = parse_source(&content, Some(path), &self.naming)?;
+
+        self.stack.push(canonical.clone());
+        self.sources.insert(
+            canonical.clone(),
+            Source {
+                path: Some(path.to_path_buf()),
+                tables,
+                imports: Vec::new(),
+            },
+        );
+
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let mut imports = Vec::with_capacity(raw_imports.len());
+
+        for raw_import in raw_imports {
+            let import_path = directory.join(&raw_import.path);
+            let import_canonical = match import_path.canonicalize() {
+                Ok(canonical) => canonical,
+                Err(_) if self.overlay.contains_key(&import_path) => import_path.clone(),
+                Err(err) => bail!(
+                    "Error: Can't import {:?} at {}: {}",
+                    raw_import.path,
+                    format_location(Some(path), raw_import.position),
+                    err
+                ),
+            };
+
+            self.load_file(&import_path, import_canonical.clone())?;
+            imports.push((import_canonical, raw_import.names));
+        }
+
+        self.stack.pop();
+        self.sources.get_mut(&canonical).unwrap().imports = imports;
+
+        Ok(())
+    }
+
+    /// Canonical paths of every loaded file, including the imported ones.
+    pub(crate) fn files(&self) -> Vec<PathBuf> {
+        self.sources
+            .iter()
+            .filter(|(_, source)| source.path.is_some())
+            .map(|(canonical, _)| canonical.clone())
+            .collect()
+    }
+
+    fn display_path(&self, canonical: &Path) -> String {
+        match self
+            .sources
+            .get(canonical)
+            .and_then(|source| source.path.as_ref())
+        {
+            Some(path) => path.display().to_string(),
+            None => canonical.display().to_string(),
+        }
+    }
+
+    /// Checks the loaded sources and resolves the tables of the entries, the tables they
+    /// import and every table they reference.
+    pub(crate) fn finish(mut self) -> Result<TableCollection> {
+        let defined = self
+            .sources
+            .values()
+            .flat_map(|source| source.tables.iter().map(RawTable::qualified_name))
+            .collect::<HashSet<_>>();
+
+        for source in self.sources.values_mut() {
+            for table in &mut source.tables {
+                table.qualify_references(&defined);
+            }
+        }
+
+        let mut definitions: HashMap<String, &RawTable> = HashMap::new();
+
+        for source in self.sources.values() {
+            for table in &source.tables {
+                if let Some(existing) = definitions.insert(table.qualified_name(), table) {
+                    bail!(
+                        "Error: Table {:?} is defined more than once, at {} and at {}",
+                        table.qualified_name(),
+                        format_location(existing.file.as_deref(), existing.position),
+                        format_location(table.file.as_deref(), table.position)
+                    );
+                }
+            }
+        }
+
+        for (key, source) in &self.sources {
+            self.check_visibility(key, source, &definitions)?;
+        }
+
+        let mut included = HashSet::new();
+        let mut visited = HashSet::new();
+        for entry in &self.entries {
+            self.include_source(entry, &mut included, &mut visited);
+        }
+
+        let mut queue = included.iter().cloned().collect::<Vec<_>>();
+        while let Some(name) = queue.pop() {
+            if let Some(table) = definitions.get(&name) {
+                for fk_table in table.fk_tables() {
+                    if included.insert(fk_table.clone()) {
+                        queue.push(fk_table);
+                    }
+                }
+            }
+        }
+
+        let mut raw_tables = RawTableCollection::new();
+        for source in self.sources.into_values() {
+            for table in source.tables {
+                let name = table.qualified_name();
+
+                if included.contains(&name) {
+                    raw_tables.insert(name, table);
+                }
+            }
+        }
+
+        Table::parse_raw_tables(raw_tables)
+    }
+
+    /// Checks that every table which is referenced by a foreign key is defined in or imported
+    /// into the source, or defined in another file of the directory if the source has been
+    /// added with [`Loader::add_dir`]. Tables which don't exist at all are reported while
+    /// resolving.
+    fn check_visibility(
+        &self,
+        key: &Path,
+        source: &Source,
+        definitions: &HashMap<String, &RawTable>,
+    ) -> Result<()> {
+        for (imported, names) in &source.imports {
+            for name in names.iter().flatten() {
+                if !self.sources[imported].defines(name) {
+                    bail!(
+                        "Error: {} doesn't define the imported table {:?}",
+                        self.display_path(imported),
+                        name
+                    );
+                }
+            }
+        }
+
+        let defines = |file: &Path, name: &str| {
+            self.sources
+                .get(file)
+                .is_some_and(|source| source.defines(name))
+        };
+
+        for table in &source.tables {
+            for fk_table in table.fk_tables() {
+                let visible = source.defines(&fk_table)
+                    || imported_from(&fk_table, &source.imports, defines).is_some()
+                    || (self.directory.contains(key)
+                        && self.directory.iter().any(|file| defines(file, &fk_table)));
+
+                if !visible && definitions.contains_key(&fk_table) {
+                    bail!(
+                        "Error: Table {:?} at {} references the table {:?}, which isn't imported",
+                        table.name,
+                        format_location(table.file.as_deref(), table.position),
+                        fk_table
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn include_source(
+        &self,
+        key: &PathBuf,
+        included: &mut HashSet<String>,
+        visited: &mut HashSet<PathBuf>,
+    ) {
+        if !visited.insert(key.clone()) {
+            return;
+        }
+
+        let source = &self.sources[key];
+        included.extend(source.tables.iter().map(RawTable::qualified_name));
+
+        for (imported, names) in &source.imports {
+            match names {
+                Some(names) => included.extend(names.iter().cloned()),
+                None => self.include_source(imported, included, visited),
+            }
+        }
+    }
+}
+
+/// File of `imports` which makes the table `name` visible, either imported as a whole or by
+/// name, `defines` tells whether a file defines a table. The language server resolves its
+/// references with it too, so that it doesn't navigate to tables the loader rejects.
+pub(crate) fn imported_from<'a>(
+    name: &str,
+    imports: &'a Imports,
+    defines: impl Fn(&Path, &str) -> bool,
+) -> Option<&'a Path> {
+    imports
+        .iter()
+        .find(|(file, names)| {
+            let imported = match names {
+                Some(names) => names.iter().any(|item| item == name),
+                None => true,
+            };
+
+            imported && defines(file, name)
+        })
+        .map(|(file, _)| file.as_path())
+}
+
+/// Parses a single source into tables and imports without resolving them, see [`lower_source`].
+pub(crate) fn parse_source(
+    source: &str,
+    file: Option<&Path>,
+    naming: &NamingConvention,
+) -> Result<(Vec<RawTable>, Vec<RawImport>)> {
+    let tree = SyntaxTree::parse_file(source, file)?;
+
+    lower_source(&tree, source, file, naming)
+}
+
+/// Lowers the statements of the [`SyntaxTree`] of a single source into tables and imports
+/// without resolving them, the tables get the naming convention of the source.
+pub(crate) fn lower_source(
+    tree: &SyntaxTree,
+    source: &str,
+    file: Option<&Path>,
+    naming: &NamingConvention,
+) -> Result<(Vec<RawTable>, Vec<RawImport>)> {
+    let mut naming = naming.clone();
+
+    let mut tables: Vec<RawTable> = Vec::new();
+    let mut imports = Vec::new();
+
+    for statement in Lowering::new(tree, source, file).statements()? {
+        match statement {
+            Statement::Table(mut table) => {
+                table.file = file.map(Path::to_path_buf);
+
+                tables.push(table);
+            }
+            Statement::Naming(raw_naming) => {
+                if let Some(column) = raw_naming.column {
+                    naming.column = column;
+                }
+                if let Some(primary_key) = raw_naming.primary_key {
+                    naming.primary_key = primary_key;
+                }
+                if let Some(foreign_key) = raw_naming.foreign_key {
+                    naming.foreign_key = foreign_key;
+                }
+                if let Some(unique) = raw_naming.unique {
+                    naming.unique = unique;
+                }
+                if let Some(check) = raw_naming.check {
+                    naming.check = check;
+                }
+            }
+            Statement::Schema(schema, schema_tables) => {
+                for mut table in schema_tables {
+                    if let Some(other) = &table.schema {
+                        if other != &schema {
+                            bail!(
+                                "Error: Table {:?} is part of the schema {:?}, but is defined inside of the schema {:?}",
+                                table.qualified_name(),
+                                other,
+                                schema
+                            );
+                        }
+                    }
+
+                    table.schema = Some(schema.clone());
+                    table.file = file.map(Path::to_path_buf);
+
+                    tables.push(table);
+                }
+            }
+            Statement::Import(raw_import) => imports.push(raw_import),
+        }
+    }
+
+    let naming = Rc::new(naming);
+    for table in &mut tables {
+        table.naming = naming.clone();
+    }
+
+    Ok((tables, imports))
+}
@@ -0,0 +1,156 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use crate::dialect::Dialect;
+use crate::diff::diff;
+use crate::parse_file;
+use crate::printer::print_tables;
+use crate::types::TableCollection;
+
+/// Name of the file inside of a migration which contains the resolved schema after the
+/// migration, see [`print_tables`].
+pub const SNAPSHOT_FILE: &str = "snapshot.tsql";
+pub const UP_FILE: &str = "up.sql";
+pub const DOWN_FILE: &str = "down.sql";
+
+/// A directory of migrations, every migration is a directory `0001_name` containing the
+/// [`UP_FILE`], the [`DOWN_FILE`] and the [`SNAPSHOT_FILE`].
+#[derive(Debug, Clone)]
+pub struct MigrationDir {
+    path: PathBuf,
+}
+
+impl MigrationDir {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        MigrationDir {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Directories of all migrations ordered by their number, empty if the directory doesn't
+    /// exist yet.
+    pub fn migrations(&self) -> Result<Vec<PathBuf>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut migrations = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let path = entry?.path();
+
+            if path.is_dir() && migration_number(&path).is_some() {
+                migrations.push(path);
+            }
+        }
+
+        migrations.sort_by_key(|path| migration_number(path));
+
+        Ok(migrations)
+    }
+
+    /// Resolved schema after the last migration, empty if there is no migration.
+    pub fn snapshot(&self) -> Result<TableCollection> {
+        match self.migrations()?.last() {
+            Some(migration) => parse_file(migration.join(SNAPSHOT_FILE)),
+            None => Ok(TableCollection::new()),
+        }
+    }
+
+    /// Creates the next migration from the last snapshot to `tables` and returns its directory,
+    /// `None` if nothing has changed since the last snapshot.
+    pub fn create(
+        &self,
+        name: &str,
+        tables: &TableCollection,
+        dialect: Dialect,
+    ) -> Result<Option<PathBuf>> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            bail!(
+                "Error: The name of a migration can only contain letters, digits, '_' and '-', got {:?}",
+                name
+            );
+        }
+
+        let migrations = self.migrations()?;
+        let previous = self.snapshot()?;
+
+        let up = diff(&previous, tables);
+        if up.is_empty() {
+            return Ok(None);
+        }
+        let down = diff(tables, &previous);
+
+        let number = migrations
+            .last()
+            .and_then(|migration| migration_number(migration))
+            .unwrap_or(0)
+            + 1;
+        let path = self.path.join(format!("{:04}_{}", number, name));
+
+        // everything is rendered first, so that a change the dialect can't express doesn't
+        // leave a migration without its snapshot behind
+        let mut up_sql = Vec::new();
+        up.transform(dialect, &mut up_sql)?;
+        let mut down_sql = Vec::new();
+        down.transform(dialect, &mut down_sql)?;
+        let mut snapshot = Vec::new();
+        print_tables(tables, &mut snapshot)?;
+
+        fs::create_dir_all(&path)?;
+        fs::write(path.join(UP_FILE), up_sql)?;
+        fs::write(path.join(DOWN_FILE), down_sql)?;
+        fs::write(path.join(SNAPSHOT_FILE), snapshot)?;
+
+        Ok(Some(path))
+    }
+}
+
+/// Number of the migration in `0001_name`.
+fn migration_number(path: &Path) -> Option<u32> {
+    let name = path.file_name()?.to_str()?;
+    let (number, _) = name.split_once('_')?;
+
+    number.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    mod create {
+        use crate::dialect::Dialect;
+        use crate::migrations::MigrationDir;
+        use crate::parse_str;
+
+        #[test]
+        fn errors() {
+            let path =
+                std::env::temp_dir().join(format!("tsql_migrations_errors_{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            let migrations = MigrationDir::new(&path);
+
+            let old = parse_str("@primary_key(id) table People { int id, };").unwrap();
+            let new = parse_str("@primary_key(id) table People { bigint id, };").unwrap();
+
+            let first = migrations
+                .create("init", &old, Dialect::Sqlite)
+                .unwrap()
+                .unwrap();
+
+            // sqlite can't alter a column, the failed migration leaves no directory behind
+            assert!(migrations.create("second", &new, Dialect::Sqlite).is_err());
+            assert!(!path.join("0002_second").exists());
+            assert_eq!(migrations.migrations().unwrap(), vec![first]);
+
+            std::fs::remove_dir_all(&path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{bail, Error, Result};
+
+/// Strategy used to name the columns which are generated for a `@foreign_key()` field.
+///
+/// The examples below are for the field `@foreign_key() Human human` where `Human` has the
+/// primary key `id`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum NamingStrategy {
+    /// `human_id`
+    #[default]
+    SnakeCase,
+    /// `humanId`
+    CamelCase,
+    /// `HumanId`
+    TablePk,
+    /// A custom template with the placeholders `{field}`, `{table}` and `{pk}`.
+    ///
+    /// `"{table}_{pk}"` results in `Human_id`.
+    Template(String),
+}
+
+impl NamingStrategy {
+    pub fn column_name(&self, field: &str, table: &str, pk: &str) -> String {
+        match self {
+            NamingStrategy::SnakeCase => format!("{}_{}", field, pk),
+            NamingStrategy::CamelCase => to_camel_case(&format!("{}_{}", field, pk)),
+            NamingStrategy::TablePk => format!("{}{}", table, to_pascal_case(pk)),
+            NamingStrategy::Template(template) => {
+                render_template(template, &[("field", field), ("table", table), ("pk", pk)])
+            }
+        }
+    }
+}
+
+impl FromStr for NamingStrategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "snake_case" => Ok(NamingStrategy::SnakeCase),
+            "camel_case" => Ok(NamingStrategy::CamelCase),
+            "table_pk" => Ok(NamingStrategy::TablePk),
+            template if template.contains('{') => Ok(NamingStrategy::Template(s.to_string())),
+            _ => bail!("Error: unknown naming strategy {:?}", s),
+        }
+    }
+}
+
+/// Naming rules used while resolving the tables of a schema.
+///
+/// Can be set from the outside (e.g. the cli) and overridden inside a schema file with
+/// `@naming(column = camel_case, foreign_key = "fk_{table}_{field}");`.
+///
+/// Every constraint is named by a template, the available placeholders are listed on each field.
+/// A name set with an annotation (`@primary_key(id) as pk_human`) takes precedence. The key
+/// `constraint` is accepted as an alias of `foreign_key`.
+///
+/// The names aren't checked while resolving the tables, only
+/// [`Dialect::validate`](crate::dialect::Dialect::validate) checks that they fit into the
+/// identifier length of a database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamingConvention {
+    /// Naming of the columns generated for a foreign key.
+    pub column: NamingStrategy,
+    /// placeholders: `{table}`
+    pub primary_key: String,
+    /// placeholders: `{table}`, `{field}` and `{target}`
+    pub foreign_key: String,
+    /// placeholders: `{table}` and `{columns}`, which are joined by `_`
+    pub unique: String,
+    /// placeholders: `{table}` and `{index}`, which starts at 1 for each table
+    pub check: String,
+}
+
+impl Default for NamingConvention {
+    fn default() -> Self {
+        NamingConvention {
+            column: NamingStrategy::default(),
+            primary_key: "pk_{table}".to_string(),
+            foreign_key: "fk_{table}_{field}".to_string(),
+            unique: "uq_{table}_{columns}".to_string(),
+            check: "ck_{table}_{index}".to_string(),
+        }
+    }
+}
+
+impl NamingConvention {
+    pub fn primary_key_name(&self, table: &str) -> String {
+        render_template(&self.primary_key, &[("table", table)])
+    }
+
+    pub fn foreign_key_name(&self, table: &str, field: &str, target: &str) -> String {
+        render_template(
+            &self.foreign_key,
+            &[("table", table), ("field", field), ("target", target)],
+        )
+    }
+
+    pub fn unique_name(&self, table: &str, columns: &[String]) -> String {
+        render_template(
+            &self.unique,
+            &[("table", table), ("columns", &columns.join("_"))],
+        )
+    }
+
+    pub fn check_name(&self, table: &str, index: usize) -> String {
+        render_template(
+            &self.check,
+            &[("table", table), ("index", &index.to_string())],
+        )
+    }
+}
+
+fn render_template<V: Display>(template: &str, values: &[(&str, V)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |out, (key, value)| {
+            out.replace(&format!("{{{}}}", key), &value.to_string())
+        })
+}
+
+pub(crate) fn to_pascal_case(input: &str) -> String {
+    input
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn to_camel_case(input: &str) -> String {
+    let pascal = to_pascal_case(input);
+
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod column_name {
+        use crate::naming::NamingStrategy;
+
+        #[test]
+        fn just_works() {
+            assert_eq!(
+                NamingStrategy::SnakeCase.column_name("human", "Human", "id"),
+                "human_id"
+            );
+            assert_eq!(
+                NamingStrategy::CamelCase.column_name("human", "Human", "id"),
+                "humanId"
+            );
+            assert_eq!(
+                NamingStrategy::CamelCase.column_name("main_human", "Human", "birth_date"),
+                "mainHumanBirthDate"
+            );
+            assert_eq!(
+                NamingStrategy::TablePk.column_name("human", "Human", "id"),
+                "HumanId"
+            );
+            assert_eq!(
+                NamingStrategy::Template("{table}_{pk}".to_string())
+                    .column_name("human", "Human", "id"),
+                "Human_id"
+            );
+        }
+    }
+
+    mod constraint_name {
+        use crate::naming::NamingConvention;
+
+        #[test]
+        fn just_works() {
+            let naming = NamingConvention::default();
+
+            assert_eq!(naming.primary_key_name("Human"), "pk_Human");
+            assert_eq!(
+                naming.foreign_key_name("has_appointment", "human", "Human"),
+                "fk_has_appointment_human"
+            );
+            assert_eq!(
+                naming.unique_name("Human", &["name".to_string(), "birth".to_string()]),
+                "uq_Human_name_birth"
+            );
+            assert_eq!(naming.check_name("Human", 2), "ck_Human_2");
+        }
+    }
+
+    mod from_str {
+        use crate::naming::NamingStrategy;
+
+        #[test]
+        fn just_works() {
+            assert_eq!(
+                "snake_case".parse::<NamingStrategy>().unwrap(),
+                NamingStrategy::SnakeCase
+            );
+            assert_eq!(
+                "camel_case".parse::<NamingStrategy>().unwrap(),
+                NamingStrategy::CamelCase
+            );
+            assert_eq!(
+                "table_pk".parse::<NamingStrategy>().unwrap(),
+                NamingStrategy::TablePk
+            );
+            assert_eq!(
+                "{field}{pk}".parse::<NamingStrategy>().unwrap(),
+                NamingStrategy::Template("{field}{pk}".to_string())
+            );
+        }
+
+        #[test]
+        fn errors() {
+            assert!("kebab_case".parse::<NamingStrategy>().is_err());
+        }
+    }
+}
@@ -1,12 +1,13 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use nom::bytes::complete::{tag, take_while1};
-use nom::character::complete::{multispace0, space1};
-use nom::error::context;
+use nom::bytes::complete::{tag, take_while, take_while1};
+use nom::character::complete::multispace0;
+use nom::combinator::{opt, recognize};
+use nom::error::{context, Error, ErrorKind};
 use nom::multi::separated_list0;
-use nom::sequence::{delimited, preceded, tuple};
-use nom::{IResult, Parser};
+use nom::sequence::{delimited, pair, tuple};
+use nom::{Err, IResult, Parser};
 
 /// This function takes an input string and extracts the first word from it.
 ///
@@ -26,11 +27,26 @@ pub fn get_word(input: &str) -> IResult<&str, &str> {
     )(input)
 }
 
-/// This function takes an input string and extracts the first word with a preceded whitespace from it.
+/// Extracts an identifier, which is a [`get_word`] that can be followed by digits, e.g. the
+/// generated constraint name `ck_People_1`.
+pub fn get_identifier(input: &str) -> IResult<&str, &str> {
+    context(
+        "get_identifier",
+        recognize(pair(
+            get_word,
+            take_while(|c| char::is_alphanumeric(c) || c == '_'),
+        )),
+    )(input)
+}
+
+/// Extracts an identifier which can be qualified by a schema, e.g. `billing.Invoice`.
 ///
-/// For more see [`get_word`].
-pub fn preceded_space_get_word(input: &str) -> IResult<&str, &str> {
-    context("preceded_space_get_word", preceded(space1, get_word))(input)
+/// For more see [`get_identifier`].
+pub fn get_qualified_word(input: &str) -> IResult<&str, &str> {
+    context(
+        "get_qualified_word",
+        recognize(pair(get_identifier, opt(pair(tag("."), get_identifier)))),
+    )(input)
 }
 
 // TODO add docs
@@ -52,15 +68,20 @@ pub fn build_generic_delimited<'a, F: FnMut(&'a str) -> IResult<&'a str, T>, T>(
     }
 }
 
-/// Captures `(...VALUES,)` and parses the elements with `fct` of the list
+/// Captures `(...VALUES,)` and parses the elements with `fct` of the list, whitespace around
+/// the elements is skipped.
 pub fn separated_tuple_list<'a, F: Parser<&'a str, &'a str, nom::error::Error<&'a str>>>(
     input: &'a str,
     fct: F,
-) -> IResult<&str, Vec<&str>> {
+) -> IResult<&'a str, Vec<&'a str>> {
     context(
         "separated_tuple_list",
         build_generic_delimited(
-            separated_list0(tuple((multispace0, tag(","), multispace0)), fct),
+            delimited(
+                multispace0,
+                separated_list0(tuple((multispace0, tag(","), multispace0)), fct),
+                multispace0,
+            ),
             '(',
             ')',
         ),
@@ -81,8 +102,64 @@ pub fn build_separated_tuple_list<
     move |input| separated_tuple_list(input, fct)
 }
 
+/// Captures the content between a pair of parentheses, nested parentheses are kept as they are.
+/// Parentheses inside of quotes like `')'` aren't counted.
+///
+/// ## Examples
+/// ```txt
+/// use crate::parser::helper::balanced_parentheses;
+///
+/// assert_eq!(balanced_parentheses("(a && (b || c)) d"), Ok((" d", "a && (b || c)")));
+/// ```
+pub fn balanced_parentheses(input: &str) -> IResult<&str, &str> {
+    let (content, _) = tag("(")(input)?;
+
+    let mut depth = 1;
+    let mut quote = None;
+    for (index, c) in content.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Ok((&content[index + 1..], &content[..index]));
+                }
+            }
+            (None, _) => (),
+        }
+    }
+
+    Err(Err::Error(Error::new(input, ErrorKind::Char)))
+}
+
 #[cfg(test)]
 mod tests {
+    mod get_identifier {
+        use nom::error::{Error, ErrorKind};
+        use nom::Err;
+
+        use crate::parser::helper::get_identifier;
+
+        #[test]
+        fn just_works() {
+            assert_eq!(get_identifier("Hello"), Ok(("", "Hello")));
+            assert_eq!(get_identifier("ck_Hello_12 as"), Ok((" as", "ck_Hello_12")));
+            assert_eq!(get_identifier("Hello1_a)"), Ok((")", "Hello1_a")));
+        }
+
+        #[test]
+        fn errors() {
+            assert_eq!(
+                get_identifier("1Hello"),
+                Err(Err::Error(Error::new("1Hello", ErrorKind::TakeWhile1)))
+            );
+        }
+    }
+
     mod get_word {
         use nom::error::{Error, ErrorKind};
         use nom::Err;
@@ -116,6 +193,34 @@ mod tests {
         }
     }
 
+    mod get_qualified_word {
+        use nom::error::{Error, ErrorKind};
+        use nom::Err;
+
+        use crate::parser::helper::get_qualified_word;
+
+        #[test]
+        fn just_works() {
+            assert_eq!(get_qualified_word("Invoice"), Ok(("", "Invoice")));
+            assert_eq!(
+                get_qualified_word("billing.Invoice invoice"),
+                Ok((" invoice", "billing.Invoice"))
+            );
+            assert_eq!(
+                get_qualified_word("billing. Invoice"),
+                Ok((". Invoice", "billing"))
+            );
+        }
+
+        #[test]
+        fn errors() {
+            assert_eq!(
+                get_qualified_word(".Invoice"),
+                Err(Err::Error(Error::new(".Invoice", ErrorKind::TakeWhile1)))
+            );
+        }
+    }
+
     mod build_generic_delimited {
         use nom::bytes::complete::take_while1;
         use nom::character::complete::{digit0, digit1};
@@ -193,4 +298,41 @@ mod tests {
             );
         }
     }
+
+    mod balanced_parentheses {
+        use nom::error::{Error, ErrorKind};
+        use nom::Err;
+
+        use crate::parser::helper::balanced_parentheses;
+
+        #[test]
+        fn just_works() {
+            assert_eq!(balanced_parentheses("()"), Ok(("", "")));
+            assert_eq!(balanced_parentheses("(a > 0) b"), Ok((" b", "a > 0")));
+            assert_eq!(
+                balanced_parentheses("(a && (b || c)) d"),
+                Ok((" d", "a && (b || c)"))
+            );
+            assert_eq!(
+                balanced_parentheses("(name <> ')' AND note <> \"(\") b"),
+                Ok((" b", "name <> ')' AND note <> \"(\""))
+            );
+        }
+
+        #[test]
+        fn errors() {
+            assert_eq!(
+                balanced_parentheses("a"),
+                Err(Err::Error(Error::new("a", ErrorKind::Tag)))
+            );
+            assert_eq!(
+                balanced_parentheses("(a (b)"),
+                Err(Err::Error(Error::new("(a (b)", ErrorKind::Char)))
+            );
+            assert_eq!(
+                balanced_parentheses("(a = ')"),
+                Err(Err::Error(Error::new("(a = ')", ErrorKind::Char)))
+            );
+        }
+    }
 }
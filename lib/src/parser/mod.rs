@@ -1,97 +1,503 @@
-use std::collections::HashMap;
+//! Lowers the statements of a [`SyntaxTree`] into [`Statement`]s, the arguments of the
+//! annotations and data types are parsed with the parsers of [`parser`].
 
-use nom::bytes::complete::tag;
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::{anyhow, bail, Result};
+use nom::combinator::all_consuming;
 use nom::IResult;
 
-mod helper;
+pub(crate) mod helper;
+#[allow(clippy::module_inception)]
 mod parser;
 pub mod types;
 
-use crate::parser::helper::preceded_space_get_word;
-use crate::parser::parser::{parse_table_body, parse_table_extra, parse_table_fields};
+use crate::parser::parser::{
+    is_name, parse_empty, parse_expression, parse_foreign_key, parse_names, parse_naming,
+    parse_renamed_from, parse_type_arguments, split_qualified, strip_comments,
+};
 use crate::parser::types::*;
+use crate::syntax::{Node, NodeKind, SyntaxTree, Token, TokenKind};
+use crate::types::{format_location, LineIndex, Position};
+
+/// Lowers the statements of a single source, the tables keep their spans and comments.
+pub(crate) struct Lowering<'a> {
+    tree: &'a SyntaxTree,
+    line_index: LineIndex<'a>,
+    /// path in front of the positions of the errors
+    file: Option<&'a Path>,
+}
+
+impl<'a> Lowering<'a> {
+    /// `source` is the source `tree` has been parsed from, `file` the file it has been read
+    /// from.
+    pub(crate) fn new(tree: &'a SyntaxTree, source: &'a str, file: Option<&'a Path>) -> Self {
+        Lowering {
+            tree,
+            line_index: LineIndex::new(source),
+            file,
+        }
+    }
+
+    /// Every statement in the order of the source.
+    pub(crate) fn statements(&self) -> Result<Vec<Statement>> {
+        self.tree
+            .statements()
+            .iter()
+            .map(|node| self.statement(node))
+            .collect()
+    }
+
+    fn statement(&self, node: &Node) -> Result<Statement> {
+        match node.kind() {
+            NodeKind::Import => self.import(node).map(Statement::Import),
+            NodeKind::Naming => self.naming(node).map(Statement::Naming),
+            NodeKind::Schema => {
+                let name = self.name(self.name_token(node)?, false)?;
+                let tables = node
+                    .nodes()
+                    .filter(|child| child.kind() == NodeKind::Table)
+                    .map(|table| self.table(table))
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok(Statement::Schema(name, tables))
+            }
+            NodeKind::Table => self.table(node).map(Statement::Table),
+            kind => bail!(
+                "Error: Expected a statement at {}, got {:?}",
+                self.location(node.span().start),
+                kind
+            ),
+        }
+    }
+
+    fn import(&self, node: &Node) -> Result<RawImport> {
+        let tokens = node.tokens();
+
+        // `import { A, B } from "path";`, the names are the words between the braces
+        let names = match tokens.iter().position(|token| token.text() == "}") {
+            Some(end) => Some(
+                tokens[..end]
+                    .iter()
+                    .skip(1)
+                    .filter(|token| token.kind() == TokenKind::Word)
+                    .map(|token| self.name(token, true))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            None => None,
+        };
+
+        let path = match tokens
+            .iter()
+            .find(|token| token.kind() == TokenKind::String)
+        {
+            Some(path) => path.text(),
+            None => bail!(
+                "Error: The import at {} has no path",
+                self.location(node.span().start)
+            ),
+        };
 
-pub fn parse(input: &str) -> IResult<&str, RawTable> {
-    let (input, extra) = table_extra(input)?;
+        Ok(RawImport {
+            path: path[1..path.len() - 1].to_string(),
+            names,
+            position: self.position(node.span().start),
+        })
+    }
 
-    // start of table
-    let (input, _) = tag("table")(input)?;
+    fn naming(&self, node: &Node) -> Result<RawNaming> {
+        let mut naming = RawNaming::default();
 
-    // parse name
-    let (input, name) = preceded_space_get_word(input)?;
+        for (key, value) in self.arguments(node, parse_naming)? {
+            match key.as_str() {
+                "column" => {
+                    naming.column = Some(value.parse().map_err(|err| {
+                        anyhow!("{} at {}", err, self.location(node.span().start))
+                    })?)
+                }
+                "primary_key" => naming.primary_key = Some(value),
+                // `constraint` is the key from before every kind of constraint could be named
+                "foreign_key" | "constraint" => naming.foreign_key = Some(value),
+                "unique" => naming.unique = Some(value),
+                "check" => naming.check = Some(value),
+                _ => bail!(
+                    "Error: unknown key {:?} in @naming at {}",
+                    key,
+                    self.location(node.span().start)
+                ),
+            }
+        }
 
-    // parse fields
-    let (input, fields) = table_body(input)?;
+        Ok(naming)
+    }
 
-    // end of table
-    let (input, _) = tag(";")(input)?;
+    fn table(&self, node: &Node) -> Result<RawTable> {
+        let name_token = self.name_token(node)?;
+        let (schema, name) = split_qualified(&self.name(name_token, true)?);
 
-    Ok((
-        input,
-        RawTable {
+        let mut extra = TableExtra::default();
+        let mut fields = Vec::new();
+
+        for child in node.nodes() {
+            match child.kind() {
+                NodeKind::Annotation => self.table_annotation(child, &mut extra)?,
+                NodeKind::Field => fields.push(self.field(child)?),
+                _ => (),
+            }
+        }
+
+        Ok(RawTable {
             extra,
-            name: name.to_string(),
+            name,
+            schema,
+            position: self.position(name_token.span().start),
+            file: None,
+            naming: Rc::default(),
+            span: node.span(),
+            comment: self.tree.comment(node),
             fields,
-        },
-    ))
-}
+        })
+    }
 
-fn table_extra(input: &str) -> IResult<&str, TableExtra> {
-    let (input, item) = parse_table_extra(input)?;
+    fn table_annotation(&self, node: &Node, extra: &mut TableExtra) -> Result<()> {
+        let annotation = self.name_token(node)?;
+        let constraint_name = match node.constraint_name() {
+            Some(token) => Some(self.name(token, false)?),
+            None => None,
+        };
 
-    let mut table_extra = TableExtra::default();
+        match annotation.text() {
+            "@primary_key" => {
+                extra.primary_key.extend(self.arguments(node, parse_names)?);
+                extra.primary_key_name = constraint_name;
+            }
+            "@unique" => extra
+                .unique
+                .push((self.arguments(node, parse_names)?, constraint_name)),
+            "@check" => extra
+                .check
+                .push((self.arguments(node, parse_expression)?, constraint_name)),
+            "@renamed_from" => {
+                self.without_constraint_name(node)?;
+                extra.renamed_from = Some(self.arguments(node, parse_renamed_from)?);
+            }
+            _ => bail!(
+                "Error: Unknown annotation {} of a table at {}",
+                annotation.text(),
+                self.location(annotation.span().start)
+            ),
+        }
 
-    match item {
-        Some((TagHelper::PrimaryKey, values)) => table_extra.primary_key.append(
-            &mut values
-                .iter()
-                .map(|item| item.to_string())
-                .collect::<Vec<_>>(),
-        ),
-        None => (),
+        Ok(())
     }
 
-    Ok((input, table_extra))
-}
+    fn field(&self, node: &Node) -> Result<FieldType> {
+        let name_token = self.name_token(node)?;
 
-fn parse_fields(input: &str) -> IResult<&str, HashMap<String, FieldType>> {
-    let (input, raw_list) = parse_table_fields(input)?;
-
-    let mut fields = HashMap::new();
-
-    for raw_item in raw_list {
-        let parsed_type =
-            RawDataType::parse(raw_item.field_type, raw_item.field_type_arguments).unwrap();
-
-        if let Some(field_extra) = raw_item.field_extra {
-            fields.insert(
-                raw_item.field_name.to_string(),
-                FieldType::Virtual((
-                    RawField {
-                        name: raw_item.field_name.to_string(),
-                        datatype: parsed_type,
-                    },
-                    field_extra,
-                )),
-            );
-        } else {
-            fields.insert(
-                raw_item.field_name.to_string(),
-                FieldType::Real(RawField {
-                    name: raw_item.field_name.to_string(),
-                    datatype: parsed_type,
-                }),
+        let mut field = RawField {
+            name: self.name(name_token, false)?,
+            datatype: RawDataType::Unknown,
+            position: self.position(name_token.span().start),
+            renamed_from: None,
+            nullable: false,
+            default: None,
+            span: node.span(),
+            comment: self.tree.comment(node),
+        };
+        let mut field_extra = None;
+
+        for child in node.nodes() {
+            match child.kind() {
+                NodeKind::Annotation => {
+                    self.without_constraint_name(child)?;
+
+                    let annotation = self.name_token(child)?;
+                    match annotation.text() {
+                        "@renamed_from" => {
+                            field.renamed_from = Some(self.arguments(child, parse_renamed_from)?)
+                        }
+                        "@nullable" => {
+                            // the parentheses are optional
+                            if child.arguments().is_some() {
+                                self.arguments(child, parse_empty)?;
+                            }
+                            field.nullable = true;
+                        }
+                        "@default" => {
+                            field.default = Some(self.arguments(child, parse_expression)?)
+                        }
+                        "@foreign_key" => {
+                            field_extra = Some(FieldExtra::ForeignKey(
+                                self.arguments(child, parse_foreign_key)?,
+                            ))
+                        }
+                        _ => bail!(
+                            "Error: Unknown annotation {} of a field at {}",
+                            annotation.text(),
+                            self.location(annotation.span().start)
+                        ),
+                    }
+                }
+                NodeKind::DataType => field.datatype = self.datatype(child)?,
+                _ => (),
+            }
+        }
+
+        Ok(match field_extra {
+            Some(field_extra) => FieldType::Virtual((field, field_extra)),
+            None => FieldType::Real(field),
+        })
+    }
+
+    /// Type of a field, or the referenced table of a foreign key.
+    fn datatype(&self, node: &Node) -> Result<RawDataType> {
+        let name_token = self.name_token(node)?;
+        let name = self.name(name_token, true)?;
+
+        let arguments = match node.arguments() {
+            Some(_) => self.arguments(node, parse_type_arguments)?,
+            None => Vec::new(),
+        };
+
+        match RawDataType::parse(&name, arguments.iter().map(String::as_str).collect()) {
+            Some(datatype) => Ok(datatype),
+            None => bail!(
+                "Error: Unknown type {:?} at {}",
+                node.tokens()
+                    .iter()
+                    .map(|token| token.text())
+                    .collect::<String>(),
+                self.location(name_token.span().start)
+            ),
+        }
+    }
+
+    /// Parses the arguments of an annotation or a data type with `parser`, which has to
+    /// consume all of them. Comments are removed and line breaks are replaced by spaces.
+    fn arguments<T>(&self, node: &Node, parser: impl FnMut(&str) -> IResult<&str, T>) -> Result<T> {
+        let name = self.name_token(node)?;
+        let Some(group) = node.arguments() else {
+            bail!(
+                "Error: {} at {} is missing its arguments",
+                name.text(),
+                self.location(name.span().start)
             );
+        };
+
+        let arguments = strip_comments(group.text()).replace(['\r', '\n', '\t'], " ");
+        let value = match all_consuming(parser)(&arguments) {
+            Ok((_, value)) => value,
+            Err(_) => bail!(
+                "Error: Invalid arguments {} of {} at {}",
+                group.text(),
+                name.text(),
+                self.location(group.span().start)
+            ),
+        };
+
+        Ok(value)
+    }
+
+    fn without_constraint_name(&self, node: &Node) -> Result<()> {
+        match node.constraint_name() {
+            Some(token) => bail!(
+                "Error: {} can't be named, got {:?} at {}",
+                node.name().map_or("The annotation", Token::text),
+                token.text(),
+                self.location(token.span().start)
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// See [`Node::name`], which is always there if the tree has been parsed.
+    fn name_token<'b>(&self, node: &'b Node) -> Result<&'b Token> {
+        match node.name() {
+            Some(token) => Ok(token),
+            None => bail!(
+                "Error: Expected a name at {}",
+                self.location(node.span().start)
+            ),
         }
     }
 
-    Ok((input, fields))
+    /// Text of a name token, `qualified` allows a schema in front of it.
+    fn name(&self, token: &Token, qualified: bool) -> Result<String> {
+        match is_name(token.text(), qualified) {
+            true => Ok(token.text().to_string()),
+            false => bail!(
+                "Error: Invalid name {:?} at {}",
+                token.text(),
+                self.location(token.span().start)
+            ),
+        }
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        self.line_index.position(offset)
+    }
+
+    /// Position of an error, with the path of the file in front of it.
+    fn location(&self, offset: usize) -> String {
+        format_location(self.file, self.position(offset))
+    }
 }
 
-fn table_body(input: &str) -> IResult<&str, HashMap<String, FieldType>> {
-    let (input, raw_body) = parse_table_body(input)?;
+#[cfg(test)]
+mod tests {
+    mod lowering {
+        use crate::parser::types::{FieldExtra, FieldType, RawDataType, RawForeignKey, Statement};
+        use crate::parser::Lowering;
+        use crate::syntax::SyntaxTree;
+        use crate::types::DataType;
+
+        fn lower(source: &str) -> anyhow::Result<Vec<Statement>> {
+            let tree = SyntaxTree::parse(source)?;
+
+            Lowering::new(&tree, source, None).statements()
+        }
+
+        #[test]
+        fn just_works() {
+            let source = "import { Human, billing.Invoice } from \"people.tsql\";
+@naming(column = camel_case, constraint = \"fk_{table}\");
+
+// cars
+@primary_key(id) as pk_car
+@unique(
+    plate, // the plate
+    owner_id
+)
+@check((id >= 0) && id < 150) as ck_id
+@renamed_from(Vehicle)
+table Car {
+    int id,
+    @renamed_from(number) @nullable @default(coalesce(1, 2))
+    varchar(16) plate, // on the back
+    @foreign_key(columns = (owner_id), name = fk_owner)
+    Human owner,
+    decimal(12, 3) price,
+    _ unknown,
+};
+schema billing { table Invoice { int id, }; }
+";
+            let statements = lower(source).unwrap();
+            assert_eq!(statements.len(), 4);
+
+            let Statement::Import(import) = &statements[0] else {
+                panic!("{:?}", statements[0]);
+            };
+            assert_eq!(import.path, "people.tsql");
+            assert_eq!(
+                import.names,
+                Some(vec!["Human".to_string(), "billing.Invoice".to_string()])
+            );
+
+            let Statement::Naming(naming) = &statements[1] else {
+                panic!("{:?}", statements[1]);
+            };
+            assert_eq!(naming.foreign_key.as_deref(), Some("fk_{table}"));
 
-    let (_, fields) = parse_fields(raw_body)?;
+            let Statement::Table(car) = &statements[2] else {
+                panic!("{:?}", statements[2]);
+            };
+            assert_eq!(car.name, "Car");
+            assert_eq!(car.position.to_string(), "12:7");
+            assert_eq!(car.comment.as_deref(), Some("cars"));
+            assert_eq!(car.extra.primary_key, vec!["id"]);
+            assert_eq!(car.extra.primary_key_name.as_deref(), Some("pk_car"));
+            assert_eq!(
+                car.extra.unique,
+                vec![(vec!["plate".to_string(), "owner_id".to_string()], None)]
+            );
+            assert_eq!(
+                car.extra.check,
+                vec![(
+                    "(id >= 0) && id < 150".to_string(),
+                    Some("ck_id".to_string())
+                )]
+            );
+            assert_eq!(car.extra.renamed_from.as_deref(), Some("Vehicle"));
+
+            let plate = car.fields[1].raw_field();
+            assert_eq!(plate.name, "plate");
+            assert_eq!(plate.datatype, RawDataType::Builtin(DataType::VarChar(16)));
+            assert_eq!(plate.position.to_string(), "15:17");
+            assert_eq!(plate.renamed_from.as_deref(), Some("number"));
+            assert!(plate.nullable);
+            assert_eq!(plate.default.as_deref(), Some("coalesce(1, 2)"));
+            assert_eq!(plate.comment.as_deref(), Some("on the back"));
+
+            match &car.fields[2] {
+                FieldType::Virtual((field, FieldExtra::ForeignKey(foreign_key))) => {
+                    assert_eq!(
+                        field.datatype,
+                        RawDataType::ForeignKeyTable("Human".to_string())
+                    );
+                    assert_eq!(
+                        foreign_key,
+                        &RawForeignKey {
+                            columns: vec!["owner_id".to_string()],
+                            name: Some("fk_owner".to_string()),
+                        }
+                    );
+                }
+                field => panic!("{:?}", field),
+            }
+            assert_eq!(
+                car.fields[3].raw_field().datatype,
+                RawDataType::Builtin(DataType::Decimal(12, 3))
+            );
+            assert_eq!(car.fields[4].raw_field().datatype, RawDataType::Unknown);
 
-    Ok((input, fields))
+            let Statement::Schema(schema, tables) = &statements[3] else {
+                panic!("{:?}", statements[3]);
+            };
+            assert_eq!(schema, "billing");
+            assert_eq!(tables[0].name, "Invoice");
+        }
+
+        #[test]
+        fn errors() {
+            let error = |source: &str| lower(source).unwrap_err().to_string();
+
+            assert_eq!(
+                error("@index(id) table A { int id, };"),
+                "Error: Unknown annotation @index of a table at 1:1"
+            );
+            assert_eq!(
+                error("table A {\n    @unique(id) int id,\n};"),
+                "Error: Unknown annotation @unique of a field at 2:5"
+            );
+            assert_eq!(
+                error("@primary_key table A { int id, };"),
+                "Error: @primary_key at 1:1 is missing its arguments"
+            );
+            assert_eq!(
+                error("@primary_key(1id) table A { int id, };"),
+                "Error: Invalid arguments (1id) of @primary_key at 1:13"
+            );
+            assert_eq!(
+                error("table A { @foreign_key() as fk B b, };"),
+                "Error: @foreign_key can't be named, got \"fk\" at 1:29"
+            );
+            assert_eq!(
+                error("table A { int(3) id, };"),
+                "Error: Unknown type \"int(3)\" at 1:11"
+            );
+            assert_eq!(
+                error("table A.B.C { int id, };"),
+                "Error: Invalid name \"A.B.C\" at 1:7"
+            );
+            assert_eq!(
+                error("@naming(order = snake_case);"),
+                "Error: unknown key \"order\" in @naming at 1:1"
+            );
+            assert_eq!(
+                error("@naming(column = kebab_case);"),
+                "Error: unknown naming strategy \"kebab_case\" at 1:1"
+            );
+        }
+    }
 }
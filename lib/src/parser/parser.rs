@@ -1,244 +1,353 @@
-use nom::bytes::complete::{tag, take_while1};
-use nom::character::complete::{digit1, space0, space1};
-use nom::combinator::{opt, value};
+//! Parsers of the arguments of annotations and data types, the statements themselves are parsed
+//! by [`crate::syntax`]. Every parser gets the whole group including its parentheses, e.g.
+//! `(columns = (a, b))`, and returns owned values.
+
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while};
+use nom::character::complete::{digit1, multispace0};
+use nom::combinator::{map, value};
 use nom::multi::separated_list0;
-use nom::sequence::{pair, preceded, separated_pair, terminated, tuple};
+use nom::sequence::{delimited, preceded, separated_pair, tuple};
 use nom::IResult;
 
-use crate::parser::helper::{build_generic_delimited, build_separated_tuple_list, get_word};
-use crate::parser::types::{FieldExtra, TagHelper};
+use crate::parser::helper::{
+    balanced_parentheses, build_generic_delimited, build_separated_tuple_list, get_identifier,
+    get_qualified_word, get_word,
+};
+use crate::parser::types::RawForeignKey;
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct RawParsedField<'a> {
-    pub field_extra: Option<FieldExtra>,
-    pub field_type: &'a str,
-    pub field_type_arguments: Vec<&'a str>,
-    pub field_name: &'a str,
+/// Parses the names of `(id, name)`, e.g. of `@primary_key(...)` or `@unique(...)`.
+pub fn parse_names(input: &str) -> IResult<&str, Vec<String>> {
+    map(build_separated_tuple_list(get_identifier), |names| {
+        names.iter().map(|name| name.to_string()).collect()
+    })(input)
 }
 
-fn parse_single_table_field(input: &str) -> IResult<&str, RawParsedField> {
-    let (input, out): (
-        &str,
-        (
-            (Option<FieldExtra>, Option<()>, &str, Option<Vec<&str>>),
-            &str,
-        ),
-    ) = preceded(
-        space1,
-        separated_pair(
-            tuple((
-                opt(preceded(
-                    opt(space1),
-                    value(FieldExtra::ForeignKey, tag("@foreign_key()")),
-                )),
-                opt(value((), space1)),
-                // type
-                get_word,
-                // arguments
-                opt(build_separated_tuple_list(digit1)),
-            )),
-            space1,
-            // field name
-            get_word,
-        ),
-    )(input)?;
-
-    Ok((
-        input,
-        RawParsedField {
-            field_extra: out.0 .0,
-            field_type: out.0 .2,
-            field_type_arguments: out.0 .3.unwrap_or_default(),
-            field_name: out.1,
-        },
-    ))
+/// Parses the arguments of a data type like `(12, 3)` of `decimal(12, 3)`.
+pub fn parse_type_arguments(input: &str) -> IResult<&str, Vec<String>> {
+    map(build_separated_tuple_list(digit1), |arguments| {
+        arguments.iter().map(|item| item.to_string()).collect()
+    })(input)
+}
+
+/// Parses `()`, e.g. of `@nullable()`.
+pub fn parse_empty(input: &str) -> IResult<&str, ()> {
+    value((), tuple((tag("("), multispace0, tag(")"))))(input)
 }
 
-pub fn parse_table_fields(input: &str) -> IResult<&str, Vec<RawParsedField>> {
-    terminated(
-        separated_list0(tag(","), parse_single_table_field),
-        tag(","),
+/// Parses the expression of `@check(...)` or `@default(...)`, nested parentheses are kept as
+/// they are.
+pub fn parse_expression(input: &str) -> IResult<&str, String> {
+    map(balanced_parentheses, |expression| {
+        expression.trim().to_string()
+    })(input)
+}
+
+/// Parses `(old_name)` of `@renamed_from(...)`, the old name can be qualified by a schema.
+pub fn parse_renamed_from(input: &str) -> IResult<&str, String> {
+    map(
+        build_generic_delimited(
+            delimited(multispace0, get_qualified_word, multispace0),
+            '(',
+            ')',
+        ),
+        str::to_string,
     )(input)
 }
 
-pub fn parse_table_body(input: &str) -> IResult<&str, &str> {
-    const OPENING_BRACKET: char = '{';
-    const CLOSING_BRACKET: char = '}';
+enum ForeignKeyArgument<'a> {
+    Columns(Vec<&'a str>),
+    Name(&'a str),
+}
 
-    preceded(
-        space0,
+/// Parses `()`, `(columns = (a, b))` and `(name = fk_name)` of `@foreign_key(...)`.
+pub fn parse_foreign_key(input: &str) -> IResult<&str, RawForeignKey> {
+    map(
         build_generic_delimited(
-            take_while1(|c| c != CLOSING_BRACKET),
-            OPENING_BRACKET,
-            CLOSING_BRACKET,
+            delimited(
+                multispace0,
+                separated_list0(
+                    tuple((multispace0, tag(","), multispace0)),
+                    alt((
+                        map(
+                            preceded(
+                                tuple((tag("columns"), multispace0, tag("="), multispace0)),
+                                build_separated_tuple_list(get_identifier),
+                            ),
+                            ForeignKeyArgument::Columns,
+                        ),
+                        map(
+                            preceded(
+                                tuple((tag("name"), multispace0, tag("="), multispace0)),
+                                get_identifier,
+                            ),
+                            ForeignKeyArgument::Name,
+                        ),
+                    )),
+                ),
+                multispace0,
+            ),
+            '(',
+            ')',
         ),
+        |arguments| {
+            let mut foreign_key = RawForeignKey::default();
+
+            for argument in arguments {
+                match argument {
+                    ForeignKeyArgument::Columns(columns) => {
+                        foreign_key.columns = columns.iter().map(|item| item.to_string()).collect()
+                    }
+                    ForeignKeyArgument::Name(name) => foreign_key.name = Some(name.to_string()),
+                }
+            }
+
+            foreign_key
+        },
     )(input)
 }
 
-pub fn parse_table_extra(input: &str) -> IResult<&str, Option<(TagHelper, Vec<&str>)>> {
-    opt(preceded(
-        tag("@"),
-        pair(
-            value(TagHelper::PrimaryKey, tag("primary_key")),
-            build_separated_tuple_list(get_word),
+/// Captures a string literal like `"fk_{table}"` and returns the value without the quotes.
+fn string_literal(input: &str) -> IResult<&str, &str> {
+    delimited(tag("\""), take_while(|c| c != '"'), tag("\""))(input)
+}
+
+/// Parses the key-value pairs of `(column = camel_case, foreign_key = "fk_{table}")` of
+/// `@naming(...)`.
+pub fn parse_naming(input: &str) -> IResult<&str, Vec<(String, String)>> {
+    map(
+        build_generic_delimited(
+            delimited(
+                multispace0,
+                separated_list0(
+                    tuple((multispace0, tag(","), multispace0)),
+                    separated_pair(
+                        get_word,
+                        tuple((multispace0, tag("="), multispace0)),
+                        alt((string_literal, get_word)),
+                    ),
+                ),
+                multispace0,
+            ),
+            '(',
+            ')',
         ),
-    ))(input)
+        |items| {
+            items
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        },
+    )(input)
+}
+
+/// Parses the optional `schema.` in front of a name and returns `(schema, name)`.
+pub fn split_qualified(input: &str) -> (Option<String>, String) {
+    match input.split_once('.') {
+        Some((schema, name)) => (Some(schema.to_string()), name.to_string()),
+        None => (None, input.to_string()),
+    }
+}
+
+/// Checks a name of a table, a field or a constraint, `qualified` allows a schema in front of
+/// it.
+pub fn is_name(input: &str, qualified: bool) -> bool {
+    let parsed = match qualified {
+        true => get_qualified_word(input),
+        false => get_identifier(input),
+    };
+
+    matches!(parsed, Ok(("", _)))
+}
+
+/// Removes the `// comments` of the arguments, quoted strings like `'http://'` aren't comments.
+pub fn strip_comments(input: &str) -> String {
+    let mut stripped = String::with_capacity(input.len());
+    let mut quote = None;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '/' && chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|next| *next != '\n') {
+                    chars.next();
+                }
+                continue;
+            }
+            None => (),
+        }
+
+        stripped.push(c);
+    }
+
+    stripped
 }
 
 #[cfg(test)]
 mod tests {
-    mod parse_single_table_field {
-        use crate::parser::parser::{parse_single_table_field, RawParsedField};
-        use crate::parser::types::FieldExtra;
+    mod parse_names {
+        use crate::parser::parser::parse_names;
 
         #[test]
         fn just_works() {
-            let out = parse_single_table_field("  int number");
-            assert!(out.is_ok());
-            let out = out.unwrap();
-            assert_eq!(out.0, "");
+            assert_eq!(parse_names("()"), Ok(("", vec![])));
             assert_eq!(
-                out.1,
-                RawParsedField {
-                    field_extra: None,
-                    field_type: "int",
-                    field_type_arguments: Vec::new(),
-                    field_name: "number"
-                }
+                parse_names("(id, other_field)"),
+                Ok(("", vec!["id".to_string(), "other_field".to_string()]))
             );
-
-            let out = parse_single_table_field("  varchar(512) text");
-            assert!(out.is_ok());
-            let out = out.unwrap();
-            assert_eq!(out.0, "");
             assert_eq!(
-                out.1,
-                RawParsedField {
-                    field_extra: None,
-                    field_type: "varchar",
-                    field_type_arguments: vec!["512"],
-                    field_name: "text"
-                }
+                parse_names("(\n    id,\n    name\n)"),
+                Ok(("", vec!["id".to_string(), "name".to_string()]))
             );
+        }
+
+        #[test]
+        fn errors() {
+            assert!(parse_names("id").is_err());
+            assert!(parse_names("(1id)").is_err());
+        }
+    }
+
+    mod parse_type_arguments {
+        use crate::parser::parser::parse_type_arguments;
 
-            let out = parse_single_table_field("  decimal(12, 3) number");
-            assert!(out.is_ok());
-            let out = out.unwrap();
-            assert_eq!(out.0, "");
+        #[test]
+        fn just_works() {
             assert_eq!(
-                out.1,
-                RawParsedField {
-                    field_extra: None,
-                    field_type: "decimal",
-                    field_type_arguments: vec!["12", "3"],
-                    field_name: "number"
-                }
+                parse_type_arguments("(512)"),
+                Ok(("", vec!["512".to_string()]))
             );
-
-            let out = parse_single_table_field("  @foreign_key()  int number");
-            assert!(out.is_ok());
-            let out = out.unwrap();
-            assert_eq!(out.0, "");
             assert_eq!(
-                out.1,
-                RawParsedField {
-                    field_extra: Some(FieldExtra::ForeignKey),
-                    field_type: "int",
-                    field_type_arguments: Vec::new(),
-                    field_name: "number"
-                }
+                parse_type_arguments("(12, 3)"),
+                Ok(("", vec!["12".to_string(), "3".to_string()]))
             );
         }
+
+        #[test]
+        fn errors() {
+            assert!(parse_type_arguments("(a)").is_err());
+        }
     }
 
-    mod parse_table_fields {
-        use crate::parser::parser::{parse_table_fields, RawParsedField};
-        use crate::parser::types::FieldExtra;
+    mod parse_expression {
+        use crate::parser::parser::parse_expression;
 
         #[test]
         fn just_works() {
-            let out = parse_table_fields(
-                "  int number,  varchar(512) text,  @foreign_key()  _ other_table,",
+            assert_eq!(
+                parse_expression("( (age >= 0) && age < 150 )"),
+                Ok(("", "(age >= 0) && age < 150".to_string()))
             );
-            assert!(out.is_ok());
-            let out = out.unwrap();
-            assert_eq!(out.0, "");
-            assert_eq!(out.1.len(), 3);
             assert_eq!(
-                out.1,
-                vec![
-                    RawParsedField {
-                        field_extra: None,
-                        field_type: "int",
-                        field_type_arguments: vec![],
-                        field_name: "number"
-                    },
-                    RawParsedField {
-                        field_extra: None,
-                        field_type: "varchar",
-                        field_type_arguments: vec!["512"],
-                        field_name: "text"
-                    },
-                    RawParsedField {
-                        field_extra: Some(FieldExtra::ForeignKey),
-                        field_type: "_",
-                        field_type_arguments: vec![],
-                        field_name: "other_table"
-                    }
-                ]
-            )
+                parse_expression("(coalesce(1, 2))"),
+                Ok(("", "coalesce(1, 2)".to_string()))
+            );
         }
     }
 
-    mod parse_table_body {
-        use crate::parser::parser::parse_table_body;
+    mod parse_renamed_from {
+        use crate::parser::parser::parse_renamed_from;
 
         #[test]
         fn just_works() {
-            assert_eq!(parse_table_body("{abc()[]_%$§%}"), Ok(("", "abc()[]_%$§%")));
-
             assert_eq!(
-                parse_table_body(" {abc()[]_%$§%}"),
-                Ok(("", "abc()[]_%$§%"))
+                parse_renamed_from("(amount)"),
+                Ok(("", "amount".to_string()))
             );
+            assert_eq!(
+                parse_renamed_from("( billing.People )"),
+                Ok(("", "billing.People".to_string()))
+            );
+        }
 
-            assert_eq!(parse_table_body("{    }"), Ok(("", "    ")));
+        #[test]
+        fn errors() {
+            assert!(parse_renamed_from("()").is_err());
+            assert!(parse_renamed_from("(a, b)").is_err());
         }
     }
 
-    mod parse_table_extra {
-        use crate::parser::parser::parse_table_extra;
-        use crate::parser::types::TagHelper;
+    mod parse_foreign_key {
+        use crate::parser::parser::parse_foreign_key;
+        use crate::parser::types::RawForeignKey;
 
         #[test]
         fn just_works() {
-            assert_eq!(
-                parse_table_extra("table People {};"),
-                Ok(("table People {};", None))
-            );
+            assert_eq!(parse_foreign_key("()"), Ok(("", RawForeignKey::default())));
 
             assert_eq!(
-                parse_table_extra("@primary_key()  table People {};"),
-                Ok(("  table People {};", Some((TagHelper::PrimaryKey, vec![]))))
+                parse_foreign_key("(columns = (human_id))"),
+                Ok((
+                    "",
+                    RawForeignKey {
+                        columns: vec!["human_id".to_string()],
+                        name: None,
+                    }
+                ))
             );
 
             assert_eq!(
-                parse_table_extra("@primary_key(id)  table People {  int id, };"),
+                parse_foreign_key("(columns = (from, to), name = fk_termin)"),
                 Ok((
-                    "  table People {  int id, };",
-                    Some((TagHelper::PrimaryKey, vec!["id"]))
+                    "",
+                    RawForeignKey {
+                        columns: vec!["from".to_string(), "to".to_string()],
+                        name: Some("fk_termin".to_string()),
+                    }
                 ))
             );
+        }
+
+        #[test]
+        fn errors() {
+            assert!(parse_foreign_key("(columns = human_id)").is_err());
+            assert!(parse_foreign_key("(table = Human)").is_err());
+        }
+    }
+
+    mod parse_naming {
+        use crate::parser::parser::parse_naming;
+
+        #[test]
+        fn just_works() {
+            assert_eq!(parse_naming("()"), Ok(("", vec![])));
+
+            assert_eq!(
+                parse_naming("(column = camel_case)"),
+                Ok(("", vec![("column".to_string(), "camel_case".to_string())]))
+            );
 
             assert_eq!(
-                parse_table_extra(
-                    "@primary_key(id, other_field)  table People {  int id, int other_field, };"
-                ),
+                parse_naming("(column = \"{table}{pk}\", foreign_key = \"fk_{table}_{field}\")"),
                 Ok((
-                    "  table People {  int id, int other_field, };",
-                    Some((TagHelper::PrimaryKey, vec!["id", "other_field"]))
+                    "",
+                    vec![
+                        ("column".to_string(), "{table}{pk}".to_string()),
+                        ("foreign_key".to_string(), "fk_{table}_{field}".to_string())
+                    ]
                 ))
             );
         }
+
+        #[test]
+        fn errors() {
+            assert!(parse_naming("(column)").is_err());
+            assert!(parse_naming("column = camel_case").is_err());
+        }
+    }
+
+    mod strip_comments {
+        use crate::parser::parser::strip_comments;
+
+        #[test]
+        fn just_works() {
+            assert_eq!(
+                strip_comments("(a > 0 // positive\n && url != 'http://')"),
+                "(a > 0 \n && url != 'http://')"
+            );
+        }
     }
 }
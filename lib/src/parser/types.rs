@@ -1,31 +1,74 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::naming::{NamingConvention, NamingStrategy};
+use crate::syntax::Span;
+use crate::types::{qualified_name, DataType, Position};
 
 #[derive(Debug)]
 pub struct RawTable {
     pub extra: TableExtra,
 
     pub name: String,
-
-    pub fields: HashMap<String, FieldType>,
+    pub schema: Option<String>,
+    pub position: Position,
+    /// file in which the table is defined, `None` if it has been parsed from a string
+    pub file: Option<PathBuf>,
+    /// naming convention of the file in which the table is defined
+    pub naming: Rc<NamingConvention>,
+    /// span of the table inside of its source, set while lowering the syntax tree
+    pub span: Span,
+    pub comment: Option<String>,
+
+    /// in the order of declaration, can contain duplicates
+    pub fields: Vec<FieldType>,
 }
 
 impl RawTable {
-    pub fn has_fk(&self) -> bool {
-        !self.fk_tables().is_empty()
+    /// `schema.name` or `name` if the table isn't part of a schema.
+    pub fn qualified_name(&self) -> String {
+        qualified_name(self.schema.as_deref(), &self.name)
+    }
+
+    /// Replaces unqualified foreign key references with a table of the same schema, if such a
+    /// table is part of `defined`.
+    pub fn qualify_references(&mut self, defined: &HashSet<String>) {
+        let schema = match &self.schema {
+            Some(schema) => schema.clone(),
+            None => return,
+        };
+
+        for field_type in &mut self.fields {
+            if let FieldType::Virtual((field, FieldExtra::ForeignKey(_))) = field_type {
+                if let RawDataType::ForeignKeyTable(table_name) = &mut field.datatype {
+                    let qualified = qualified_name(Some(&schema), table_name);
+
+                    if !table_name.contains('.') && defined.contains(&qualified) {
+                        *table_name = qualified;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn get_field(&self, name: &str) -> Option<&FieldType> {
+        self.fields
+            .iter()
+            .find(|field_type| field_type.raw_field().name == name)
     }
 
     pub fn fk_tables(&self) -> Vec<String> {
         let mut table_names = Vec::with_capacity(self.fields.len());
 
-        for field_type in self.fields.values() {
+        for field_type in &self.fields {
             match field_type {
                 FieldType::Real(_) => (),
-                FieldType::Virtual((field, FieldExtra::ForeignKey)) => match &field.datatype {
-                    RawDataType::ForeignKeyTable(table_name) => {
+                FieldType::Virtual((field, FieldExtra::ForeignKey(_))) => {
+                    if let RawDataType::ForeignKeyTable(table_name) = &field.datatype {
                         table_names.push(table_name.clone())
                     }
-                    _ => (),
-                },
+                }
             };
         }
 
@@ -39,84 +82,106 @@ pub enum FieldType {
     Virtual((RawField, FieldExtra)),
 }
 
+impl FieldType {
+    pub fn raw_field(&self) -> &RawField {
+        match self {
+            FieldType::Real(raw_field) | FieldType::Virtual((raw_field, _)) => raw_field,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct RawField {
     pub name: String,
     pub datatype: RawDataType,
+    pub position: Position,
+    /// name of the field before it has been renamed, see [`crate::diff`]
+    pub renamed_from: Option<String>,
+    pub nullable: bool,
+    /// default value as it has been written in the schema
+    pub default: Option<String>,
+    /// span of the field inside of its source, set while lowering the syntax tree
+    pub span: Span,
+    pub comment: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum RawDataType {
     Unknown,
-    Int,
-    Bool,
-    BigInt,
-    Date,
-    DateTime,
-    Time,
-    Double,
-    Float,
-    Uuid,
-
-    VarChar(u16),
-    Char(u8),
-    Text(u16),
-
-    Decimal(u8, u8),
-
+    Builtin(DataType),
     ForeignKeyTable(String),
 }
 
 impl RawDataType {
     pub fn parse(input: &str, argument: Vec<&str>) -> Option<Self> {
+        if let Some(datatype) = DataType::from_name(input, &argument) {
+            return Some(RawDataType::Builtin(datatype));
+        }
+
         match (input, argument.len()) {
-            ("int", 0) => Some(RawDataType::Int),
-            ("bool", 0) => Some(RawDataType::Bool),
-            ("bigint", 0) => Some(RawDataType::BigInt),
-            ("date", 0) => Some(RawDataType::Date),
-            ("datetime", 0) => Some(RawDataType::DateTime),
-            ("time", 0) => Some(RawDataType::Time),
-            ("double", 0) => Some(RawDataType::Double),
-            ("float", 0) => Some(RawDataType::Float),
-            ("uuid", 0) => Some(RawDataType::Uuid),
             ("_", 0) => Some(RawDataType::Unknown),
-
-            ("varchar", 1) => match argument[0].parse() {
-                Ok(l) => Some(RawDataType::VarChar(l)),
-                _ => None,
-            },
-            ("char", 1) => match argument[0].parse() {
-                Ok(l) => Some(RawDataType::Char(l)),
-                _ => None,
-            },
-            ("text", 1) => match argument[0].parse() {
-                Ok(l) => Some(RawDataType::Text(l)),
-                _ => None,
-            },
-
-            ("decimal", 2) => match (argument[0].parse(), argument[1].parse()) {
-                (Ok(precision), Ok(scale)) => Some(RawDataType::Decimal(precision, scale)),
-                _ => None,
-            },
-
             (item, 0) => Some(RawDataType::ForeignKeyTable(item.to_string())),
-
             _ => None,
         }
     }
 }
 
+impl From<DataType> for RawDataType {
+    fn from(datatype: DataType) -> Self {
+        RawDataType::Builtin(datatype)
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct TableExtra {
     pub primary_key: Vec<String>,
+    pub primary_key_name: Option<String>,
+    /// values: `(fields, constraint name)`
+    pub unique: Vec<(Vec<String>, Option<String>)>,
+    /// values: `(expression, constraint name)`
+    pub check: Vec<(String, Option<String>)>,
+    /// name of the table before it has been renamed, see [`crate::diff`]
+    pub renamed_from: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FieldExtra {
-    ForeignKey,
+    ForeignKey(RawForeignKey),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawForeignKey {
+    /// column names overriding the ones generated by the [`NamingStrategy`]
+    pub columns: Vec<String>,
+    /// name of the constraint overriding the generated one
+    pub name: Option<String>,
+}
+
+/// Values of a `@naming(...);` directive, `None` if the key hasn't been set.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RawNaming {
+    pub column: Option<NamingStrategy>,
+    pub primary_key: Option<String>,
+    pub foreign_key: Option<String>,
+    pub unique: Option<String>,
+    pub check: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum TagHelper {
-    PrimaryKey,
+/// `import "path";` or `import { names } from "path";`
+#[derive(Debug)]
+pub struct RawImport {
+    /// as written in the schema, relative to the importing file
+    pub path: String,
+    /// `None` if every table of the file is imported
+    pub names: Option<Vec<String>>,
+    pub position: Position,
+}
+
+#[derive(Debug)]
+pub enum Statement {
+    Table(RawTable),
+    Naming(RawNaming),
+    Import(RawImport),
+    /// values: `(name, tables)` of a `schema name { ... }` block
+    Schema(String, Vec<RawTable>),
 }
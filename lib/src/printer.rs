@@ -0,0 +1,170 @@
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::formatter::format_str;
+use crate::naming::NamingConvention;
+use crate::types::{unqualified_name, ConstraintKind, ForeignKey, Table, TableCollection};
+
+/// Writes the tables as tsql, which resolves to the same tables if it's parsed again. The
+/// output is formatted like [`format_str`] formats it and keeps the order of the fields.
+///
+/// Every generated name is written explicitly, e.g. `@primary_key(id) as pk_People` and
+/// `@foreign_key(columns = (owner_id), name = fk_Car_owner)`, so that the output doesn't
+/// depend on the naming convention it's parsed with. `@renamed_from(...)` isn't written, as a
+/// rename only applies to a single migration.
+pub fn print_tables<W: Write>(tables: &TableCollection, buffer: &mut W) -> Result<()> {
+    print(tables, None, buffer)
+}
+
+/// See [`print_tables`], but names which `naming` would generate anyway are left out, e.g.
+/// `@foreign_key() People owner` instead of listing the columns and the constraint name.
+pub fn print_tables_with_naming<W: Write>(
+    tables: &TableCollection,
+    naming: &NamingConvention,
+    buffer: &mut W,
+) -> Result<()> {
+    print(tables, Some(naming), buffer)
+}
+
+fn print<W: Write>(
+    tables: &TableCollection,
+    naming: Option<&NamingConvention>,
+    buffer: &mut W,
+) -> Result<()> {
+    let mut printed = Vec::new();
+    for (index, table) in tables.values().enumerate() {
+        if index != 0 {
+            writeln!(printed)?;
+        }
+
+        print_table(table, naming, &mut printed)?;
+    }
+
+    // the layout, e.g. the aligned field names, is left to the formatter
+    let formatted = format_str(&String::from_utf8(printed)?)?;
+    buffer.write_all(formatted.as_bytes())?;
+
+    Ok(())
+}
+
+fn print_table<W: Write>(
+    table: &Table,
+    naming: Option<&NamingConvention>,
+    buffer: &mut W,
+) -> Result<()> {
+    // `None` if the name is the one `naming` generates
+    let explicit = |name: &str, generated: &dyn Fn(&NamingConvention) -> String| match naming {
+        Some(naming) if generated(naming) == name => None,
+        _ => Some(name.to_string()),
+    };
+
+    let mut check_index = 0;
+    for constraint in &table.constraints {
+        let name = match &constraint.kind {
+            ConstraintKind::PrimaryKey(fields) => {
+                write!(buffer, "@primary_key({})", fields.join(", "))?;
+
+                explicit(&constraint.name, &|naming| {
+                    naming.primary_key_name(&table.name)
+                })
+            }
+            ConstraintKind::Unique(fields) => {
+                write!(buffer, "@unique({})", fields.join(", "))?;
+
+                explicit(&constraint.name, &|naming| {
+                    naming.unique_name(&table.name, fields)
+                })
+            }
+            ConstraintKind::Check(expression) => {
+                write!(buffer, "@check({})", expression)?;
+
+                check_index += 1;
+                explicit(&constraint.name, &|naming| {
+                    naming.check_name(&table.name, check_index)
+                })
+            }
+        };
+
+        match name {
+            Some(name) => writeln!(buffer, " as {}", name)?,
+            None => writeln!(buffer)?,
+        }
+    }
+
+    writeln!(buffer, "table {} {{", table.qualified_name())?;
+
+    // the columns of a foreign key are written as the `@foreign_key()` field in place of its
+    // first column
+    for field in table.fields() {
+        if field.foreign_key_reference.is_none() {
+            if field.nullable {
+                writeln!(buffer, "    @nullable")?;
+            }
+
+            if let Some(default) = &field.default {
+                writeln!(buffer, "    @default({})", default)?;
+            }
+
+            writeln!(buffer, "    {} {},", field.datatype, field.name)?;
+        } else if let Some(foreign_key) = table
+            .foreign_keys
+            .iter()
+            .find(|foreign_key| foreign_key.columns.first() == Some(&field.name))
+        {
+            print_foreign_key(table, foreign_key, naming, buffer)?;
+        }
+    }
+
+    writeln!(buffer, "}};")?;
+
+    Ok(())
+}
+
+fn print_foreign_key<W: Write>(
+    table: &Table,
+    foreign_key: &ForeignKey,
+    naming: Option<&NamingConvention>,
+    buffer: &mut W,
+) -> Result<()> {
+    let target = unqualified_name(&foreign_key.table);
+
+    let mut arguments = Vec::with_capacity(2);
+
+    let generated_columns = naming.map(|naming| {
+        foreign_key
+            .references
+            .iter()
+            .map(|reference| {
+                naming
+                    .column
+                    .column_name(&foreign_key.name, target, reference)
+            })
+            .collect::<Vec<_>>()
+    });
+    if generated_columns.as_ref() != Some(&foreign_key.columns) {
+        arguments.push(format!("columns = ({})", foreign_key.columns.join(", ")));
+    }
+
+    let generated_name =
+        naming.map(|naming| naming.foreign_key_name(&table.name, &foreign_key.name, target));
+    if generated_name.as_ref() != Some(&foreign_key.constraint) {
+        arguments.push(format!("name = {}", foreign_key.constraint));
+    }
+
+    // self references are always nullable
+    let nullable = foreign_key.table != table.qualified_name()
+        && foreign_key
+            .columns
+            .first()
+            .and_then(|column| table.fields.get(column))
+            .is_some_and(|field| field.nullable);
+
+    if nullable {
+        writeln!(buffer, "    @nullable")?;
+    }
+    writeln!(buffer, "    @foreign_key({})", arguments.join(", "))?;
+    writeln!(buffer, "    {} {},", foreign_key.table, foreign_key.name)?;
+
+    Ok(())
+}
@@ -0,0 +1,225 @@
+//! Serde representation of the resolved tables, behind the `serde` feature.
+//!
+//! The representation only contains the resolved schema, positions and spans inside of the
+//! source are left out so that e.g. a snapshot doesn't change if a file is formatted. Types are
+//! written as in tsql, e.g. `"varchar(32)"`.
+
+use std::collections::HashMap;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::parser::types::TableExtra;
+use crate::types::{Constraint, ConstraintKind, DataType, Field, ForeignKey, Table};
+
+#[derive(Serialize, Deserialize)]
+struct TableRepr {
+    name: String,
+    #[serde(default)]
+    schema: Option<String>,
+    #[serde(default)]
+    comment: Option<String>,
+    #[serde(default)]
+    renamed_from: Option<String>,
+    fields: Vec<FieldRepr>,
+    #[serde(default)]
+    foreign_keys: Vec<ForeignKey>,
+    #[serde(default)]
+    constraints: Vec<Constraint>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FieldRepr {
+    name: String,
+    datatype: DataType,
+    #[serde(default)]
+    nullable: bool,
+    #[serde(default)]
+    default: Option<String>,
+    #[serde(default)]
+    comment: Option<String>,
+    #[serde(default)]
+    renamed_from: Option<String>,
+    /// referenced table and field of a column generated for a foreign key
+    #[serde(default)]
+    references: Option<ReferenceRepr>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReferenceRepr {
+    table: String,
+    field: String,
+}
+
+impl Serialize for Table {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TableRepr {
+            name: self.name.clone(),
+            schema: self.schema.clone(),
+            comment: self.comment.clone(),
+            renamed_from: self.extra.renamed_from.clone(),
+            fields: self.fields().map(FieldRepr::from).collect(),
+            foreign_keys: self.foreign_keys.clone(),
+            constraints: self.constraints.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Table {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = TableRepr::deserialize(deserializer)?;
+
+        let mut fields = HashMap::with_capacity(repr.fields.len());
+        let mut field_order = Vec::with_capacity(repr.fields.len());
+        for field in repr.fields {
+            let field = Field::from(field);
+            field_order.push(field.name.clone());
+
+            if let Some(field) = fields.insert(field.name.clone(), field) {
+                return Err(D::Error::custom(format!(
+                    "Table {:?} has more than one field with the name {:?}",
+                    repr.name, field.name
+                )));
+            }
+        }
+
+        let primary_key = repr
+            .constraints
+            .iter()
+            .find_map(|constraint| match &constraint.kind {
+                ConstraintKind::PrimaryKey(fields) => Some(fields.clone()),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        Ok(Table {
+            extra: TableExtra {
+                primary_key,
+                renamed_from: repr.renamed_from,
+                ..Default::default()
+            },
+            name: repr.name,
+            schema: repr.schema,
+            comment: repr.comment,
+            fields,
+            field_order,
+            foreign_keys: repr.foreign_keys,
+            constraints: repr.constraints,
+            ..Default::default()
+        })
+    }
+}
+
+impl From<&Field> for FieldRepr {
+    fn from(field: &Field) -> Self {
+        FieldRepr {
+            name: field.name.clone(),
+            datatype: field.datatype,
+            nullable: field.nullable,
+            default: field.default.clone(),
+            comment: field.comment.clone(),
+            renamed_from: field.renamed_from.clone(),
+            references: field
+                .foreign_key_reference
+                .as_ref()
+                .map(|(table, field)| ReferenceRepr {
+                    table: table.clone(),
+                    field: field.clone(),
+                }),
+        }
+    }
+}
+
+impl From<FieldRepr> for Field {
+    fn from(repr: FieldRepr) -> Self {
+        Field {
+            name: repr.name,
+            datatype: repr.datatype,
+            nullable: repr.nullable,
+            default: repr.default,
+            position: Default::default(),
+            span: Default::default(),
+            comment: repr.comment,
+            foreign_key_reference: repr
+                .references
+                .map(|reference| (reference.table, reference.field)),
+            renamed_from: repr.renamed_from,
+        }
+    }
+}
+
+impl Serialize for DataType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for DataType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod serialize {
+        use crate::diff::diff;
+        use crate::parse_str;
+        use crate::types::{DataType, TableCollection};
+
+        #[test]
+        fn just_works() {
+            let tables = parse_str(
+                "@primary_key(id) table Human { int id, @nullable decimal(10, 2) height, };
+                // the car of a human
+                @primary_key(plate) @unique(owner_id) as uq_owner
+                table Car { varchar(16) plate, @foreign_key() Human owner, };",
+            )
+            .unwrap();
+
+            let json = serde_json::to_value(&tables).unwrap();
+            assert_eq!(json["Car"]["comment"], "the car of a human");
+            assert_eq!(json["Human"]["fields"][1]["datatype"], "decimal(10, 2)");
+            assert_eq!(json["Car"]["fields"][1]["name"], "owner_id");
+            assert_eq!(
+                json["Car"]["fields"][1]["references"],
+                serde_json::json!({ "table": "Human", "field": "id" })
+            );
+            assert_eq!(
+                json["Car"]["constraints"][1],
+                serde_json::json!({ "name": "uq_owner", "kind": "unique", "value": ["owner_id"] })
+            );
+
+            let parsed = serde_json::from_value::<TableCollection>(json).unwrap();
+            assert!(diff(&tables, &parsed).is_empty());
+            assert_eq!(parsed["Car"].primary_keys(), &vec!["plate"]);
+
+            let (table, field) = parsed["Car"]
+                .get_field("owner_id")
+                .unwrap()
+                .references(&parsed)
+                .unwrap();
+            assert_eq!((table.name(), field.name()), ("Human", "id"));
+
+            assert_eq!(
+                serde_json::from_str::<DataType>("\"varchar(32)\"").unwrap(),
+                DataType::VarChar(32)
+            );
+        }
+
+        #[test]
+        fn errors() {
+            assert!(serde_json::from_str::<DataType>("\"varchar\"").is_err());
+            assert!(serde_json::from_str::<DataType>("\"decimal(10, 2\"").is_err());
+
+            let duplicate = r#"{ "name": "Human", "fields": [
+                { "name": "id", "datatype": "int" },
+                { "name": "id", "datatype": "bigint" }
+            ] }"#;
+            assert!(serde_json::from_str::<crate::types::Table>(duplicate).is_err());
+        }
+    }
+}
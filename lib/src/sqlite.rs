@@ -0,0 +1,239 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use rusqlite::{Connection, OpenFlags};
+
+use crate::ddl::{
+    parse_create_table, parse_datatype, resolve_tables, tsql_identifier, SqlColumn, SqlForeignKey,
+    SqlTable,
+};
+use crate::types::TableCollection;
+
+/// Reads the tables of a sqlite database file, e.g. to compare the database with the tsql it
+/// has been created from.
+///
+/// The structure is read from `PRAGMA table_info`, `foreign_key_list` and `index_list`, only
+/// the constraint names and check constraints are taken from the `CREATE TABLE` statements in
+/// `sqlite_master`, as sqlite doesn't keep them anywhere else. Like [`crate::ddl::parse_sql`]
+/// everything which can't be expressed in tsql is reported in the returned warnings.
+pub fn read_database<P: AsRef<Path>>(path: P) -> Result<(TableCollection, Vec<String>)> {
+    let path = path.as_ref();
+    if !path.is_file() {
+        bail!("Error: The database {:?} doesn't exist", path);
+    }
+
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut warnings = Vec::new();
+
+    let mut statement = connection.prepare(
+        "SELECT name, sql FROM sqlite_master \
+         WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )?;
+    let definitions = statement
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<(String, Option<String>)>>>()?;
+
+    let mut tables = Vec::with_capacity(definitions.len());
+    for (name, sql) in definitions {
+        tables.push(read_table(
+            &connection,
+            &name,
+            sql.as_deref(),
+            &mut warnings,
+        )?);
+    }
+
+    let tables = resolve_tables(tables, &mut warnings)?;
+
+    Ok((tables, warnings))
+}
+
+fn read_table(
+    connection: &Connection,
+    name: &str,
+    sql: Option<&str>,
+    warnings: &mut Vec<String>,
+) -> Result<SqlTable> {
+    // only used for the names and check constraints, the rest is read from the pragmas
+    let declared = match sql.map(|sql| parse_create_table(sql, &mut Vec::new())) {
+        Some(Ok(Some(declared))) => declared,
+        _ => {
+            warnings.push(format!(
+                "Couldn't read the constraint names and checks of table {:?}",
+                name
+            ));
+            SqlTable::default()
+        }
+    };
+
+    let mut table = SqlTable {
+        name: tsql_identifier(name)?,
+        check: declared.check,
+        ..Default::default()
+    };
+
+    let mut statement = connection.prepare(
+        "SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?1) ORDER BY cid",
+    )?;
+    let columns = statement
+        .query_map([name], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, bool>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, u32>(4)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut primary_key = Vec::new();
+    for (column, datatype, not_null, default, pk_index) in columns {
+        if pk_index > 0 {
+            primary_key.push((pk_index, column.clone()));
+        }
+
+        table.columns.push(SqlColumn {
+            datatype: parse_datatype(name, &column, &datatype, warnings)?,
+            name: tsql_identifier(&column)?,
+            // primary keys can't be null in tsql, even if sqlite allows it
+            nullable: !not_null && pk_index == 0,
+            default,
+        });
+    }
+
+    if !primary_key.is_empty() {
+        primary_key.sort();
+
+        table.primary_key = Some((
+            primary_key.into_iter().map(|(_, column)| column).collect(),
+            declared.primary_key.and_then(|(_, name)| name),
+        ));
+    }
+
+    read_foreign_keys(connection, &mut table, &declared.foreign_keys, warnings)?;
+    read_unique(connection, &mut table, &declared.unique, warnings)?;
+
+    Ok(table)
+}
+
+fn read_foreign_keys(
+    connection: &Connection,
+    table: &mut SqlTable,
+    declared: &[SqlForeignKey],
+    warnings: &mut Vec<String>,
+) -> Result<()> {
+    let mut statement = connection.prepare(
+        "SELECT id, \"table\", \"from\", \"to\", on_update, on_delete \
+         FROM pragma_foreign_key_list(?1) ORDER BY id, seq",
+    )?;
+    let rows = statement
+        .query_map([&table.name], |row| {
+            Ok((
+                row.get::<_, u32>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut current = None;
+    for (id, target, from, to, on_update, on_delete) in rows {
+        if current != Some(id) {
+            current = Some(id);
+
+            for (event, action) in [("UPDATE", on_update), ("DELETE", on_delete)] {
+                if action != "NO ACTION" {
+                    warnings.push(format!(
+                        "Ignored \"ON {} {}\" of a foreign key in table {:?}",
+                        event, action, table.name
+                    ));
+                }
+            }
+
+            table.foreign_keys.push(SqlForeignKey {
+                name: None,
+                columns: Vec::new(),
+                table: tsql_identifier(&target)?,
+                references: Vec::new(),
+            });
+        }
+
+        let foreign_key = table.foreign_keys.last_mut().unwrap();
+        foreign_key.columns.push(tsql_identifier(&from)?);
+        if let Some(to) = to {
+            foreign_key.references.push(tsql_identifier(&to)?);
+        }
+    }
+
+    for foreign_key in &mut table.foreign_keys {
+        foreign_key.name = declared
+            .iter()
+            .find(|item| item.columns == foreign_key.columns && item.table == foreign_key.table)
+            .and_then(|item| item.name.clone());
+    }
+
+    Ok(())
+}
+
+fn read_unique(
+    connection: &Connection,
+    table: &mut SqlTable,
+    declared: &[(Vec<String>, Option<String>)],
+    warnings: &mut Vec<String>,
+) -> Result<()> {
+    let mut statement = connection.prepare(
+        "SELECT name, \"unique\", origin, partial FROM pragma_index_list(?1) ORDER BY seq",
+    )?;
+    let indexes = statement
+        .query_map([&table.name], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, bool>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, bool>(3)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut statement =
+        connection.prepare("SELECT name FROM pragma_index_info(?1) ORDER BY seqno")?;
+
+    // sqlite returns the newest index first
+    for (index, unique, origin, partial) in indexes.into_iter().rev() {
+        if origin == "pk" {
+            continue;
+        }
+
+        if !unique || partial {
+            warnings.push(format!(
+                "Skipped the index {:?} of table {:?}",
+                index, table.name
+            ));
+            continue;
+        }
+
+        let columns = statement
+            .query_map([&index], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .iter()
+            .map(|column| tsql_identifier(column))
+            .collect::<Result<Vec<_>>>()?;
+
+        let name = if origin == "u" {
+            declared
+                .iter()
+                .find(|(fields, _)| fields == &columns)
+                .and_then(|(_, name)| name.clone())
+        } else {
+            Some(tsql_identifier(&index)?)
+        };
+
+        table.unique.push((columns, name));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,894 @@
+//! Lossless concrete syntax tree of tsql source, for tools like formatters and editors which
+//! have to work on the file as it has been written.
+//!
+//! Every [`Token`] keeps its text, its [`Span`] and the whitespace and comments in front of it,
+//! so that writing the tree with [`std::fmt::Display`] results in exactly the parsed source.
+//! The tables themselves are resolved from the statements of the tree, see
+//! [`crate::parse_str`].
+
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::types::{format_location, LineIndex};
+
+/// Byte range `start..end` inside of the parsed source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Whether `offset` is inside of the span, the end included so that a cursor directly
+    /// behind a name is still part of it.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset <= self.end
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// identifiers, keywords and numbers, e.g. `table` or `billing.Invoice`
+    Word,
+    /// e.g. `@primary_key`
+    Annotation,
+    /// content in balanced parentheses including them, e.g. `(a, b)` or `(length(a) > 0)`
+    Group,
+    /// e.g. `"people.tsql"`
+    String,
+    /// `{`, `}`, `,`, `;` or `=`
+    Symbol,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    /// `// comment` until the end of the line, without the line break
+    Comment,
+}
+
+/// Whitespace or a comment in front of a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    kind: TriviaKind,
+    text: String,
+    span: Span,
+}
+
+impl Trivia {
+    pub fn kind(&self) -> TriviaKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    kind: TokenKind,
+    text: String,
+    span: Span,
+    /// whitespace and comments between the previous token and this one
+    leading: Vec<Trivia>,
+}
+
+impl Token {
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Span of the token without its leading trivia.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn leading_trivia(&self) -> &[Trivia] {
+        &self.leading
+    }
+
+    fn is(&self, kind: TokenKind, text: &str) -> bool {
+        self.kind == kind && self.text == text
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for trivia in &self.leading {
+            write!(f, "{}", trivia.text)?;
+        }
+
+        write!(f, "{}", self.text)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// `import "file.tsql";` or `import { A, B } from "file.tsql";`
+    Import,
+    /// `@naming(...);`
+    Naming,
+    /// `schema name { tables };`
+    Schema,
+    /// annotations and `table name { fields };`
+    Table,
+    /// `@name(arguments) as constraint_name`, the arguments and the name are optional
+    Annotation,
+    /// annotations and `type name,`
+    Field,
+    /// `type(arguments)` of a field, or the referenced table of a foreign key
+    DataType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element {
+    Node(Node),
+    Token(Token),
+}
+
+impl Element {
+    pub fn span(&self) -> Span {
+        match self {
+            Element::Node(node) => node.span,
+            Element::Token(token) => token.span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    kind: NodeKind,
+    /// from the start of the first to the end of the last token
+    span: Span,
+    children: Vec<Element>,
+}
+
+impl Node {
+    fn new(kind: NodeKind, children: Vec<Element>) -> Self {
+        let mut node = Node {
+            kind,
+            span: Span::default(),
+            children,
+        };
+
+        let tokens = node.tokens();
+        node.span = Span {
+            start: tokens.first().map_or(0, |token| token.span.start),
+            end: tokens.last().map_or(0, |token| token.span.end),
+        };
+
+        node
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn children(&self) -> &[Element] {
+        &self.children
+    }
+
+    /// Direct child nodes, e.g. the fields of a table.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|element| match element {
+            Element::Node(node) => Some(node),
+            Element::Token(_) => None,
+        })
+    }
+
+    /// Every token of the node and its children in the order of the source.
+    pub fn tokens(&self) -> Vec<&Token> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a Token>) {
+        for element in &self.children {
+            match element {
+                Element::Node(node) => node.collect_tokens(tokens),
+                Element::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// First token of the node, e.g. the first annotation in front of a table.
+    pub fn first_token(&self) -> Option<&Token> {
+        match self.children.first()? {
+            Element::Node(node) => node.first_token(),
+            Element::Token(token) => Some(token),
+        }
+    }
+
+    /// First token which ends at or behind `offset`, the children are sorted by their spans.
+    fn token_from(&self, offset: usize) -> Option<&Token> {
+        let index = self
+            .children
+            .partition_point(|element| element.span().end < offset);
+
+        match self.children.get(index)? {
+            Element::Node(node) => node.token_from(offset),
+            Element::Token(token) => Some(token),
+        }
+    }
+
+    /// Tokens which aren't part of a child node.
+    fn own_tokens(&self) -> impl Iterator<Item = &Token> {
+        self.children.iter().filter_map(|element| match element {
+            Element::Token(token) => Some(token),
+            Element::Node(_) => None,
+        })
+    }
+
+    /// Name of the node: the name of a schema, table or field, the type of a [`NodeKind::DataType`]
+    /// and the annotation itself of a [`NodeKind::Annotation`] or [`NodeKind::Naming`], e.g.
+    /// `@primary_key`.
+    pub fn name(&self) -> Option<&Token> {
+        let mut tokens = self.own_tokens();
+
+        match self.kind {
+            NodeKind::Schema | NodeKind::Table => tokens
+                .skip_while(|token| {
+                    !token.is(TokenKind::Word, "schema") && !token.is(TokenKind::Word, "table")
+                })
+                .nth(1),
+            NodeKind::Field | NodeKind::DataType => {
+                tokens.find(|token| token.kind == TokenKind::Word)
+            }
+            NodeKind::Annotation | NodeKind::Naming => {
+                tokens.find(|token| token.kind == TokenKind::Annotation)
+            }
+            NodeKind::Import => None,
+        }
+    }
+
+    /// Arguments of an annotation or a data type including the parentheses, e.g. `(id)`.
+    pub fn arguments(&self) -> Option<&Token> {
+        self.children.iter().find_map(|element| match element {
+            Element::Token(token) if token.kind == TokenKind::Group => Some(token),
+            _ => None,
+        })
+    }
+
+    /// Constraint name of an annotation like `@primary_key(id) as pk_human`.
+    pub fn constraint_name(&self) -> Option<&Token> {
+        let tokens = self.own_tokens();
+
+        match self.kind {
+            NodeKind::Annotation => tokens
+                .skip_while(|token| !token.is(TokenKind::Word, "as"))
+                .nth(1),
+            _ => None,
+        }
+    }
+
+    /// Innermost node which contains `offset`, the node itself if none of its children does.
+    pub fn node_at(&self, offset: usize) -> Option<&Node> {
+        if !self.span.contains(offset) {
+            return None;
+        }
+
+        Some(
+            self.nodes()
+                .find_map(|node| node.node_at(offset))
+                .unwrap_or(self),
+        )
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for element in &self.children {
+            match element {
+                Element::Node(node) => write!(f, "{}", node)?,
+                Element::Token(token) => write!(f, "{}", token)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Statements of a source, see the [module](self) for more.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxTree {
+    statements: Vec<Node>,
+    /// whitespace and comments behind the last statement
+    trailing: Vec<Trivia>,
+}
+
+impl SyntaxTree {
+    /// Parses the structure of the source, which doesn't check e.g. the arguments of the
+    /// annotations or whether the referenced tables exist.
+    pub fn parse(source: &str) -> Result<Self> {
+        Self::parse_file(source, None)
+    }
+
+    /// See [`SyntaxTree::parse`], the positions in the errors start with the path of `file`.
+    pub(crate) fn parse_file(source: &str, file: Option<&Path>) -> Result<Self> {
+        let line_index = LineIndex::new(source);
+        let (mut tokens, trailing) = tokenize(source, &line_index, file)?;
+        tokens.reverse();
+
+        let mut parser = Parser {
+            tokens,
+            line_index,
+            file,
+            children: Vec::new(),
+        };
+
+        let mut statements = Vec::new();
+        while !parser.tokens.is_empty() {
+            statements.push(parser.statement()?);
+        }
+
+        Ok(SyntaxTree {
+            statements,
+            trailing,
+        })
+    }
+
+    /// [`NodeKind::Import`], [`NodeKind::Naming`], [`NodeKind::Schema`] and
+    /// [`NodeKind::Table`] nodes in the order of the source.
+    pub fn statements(&self) -> &[Node] {
+        &self.statements
+    }
+
+    pub fn trailing_trivia(&self) -> &[Trivia] {
+        &self.trailing
+    }
+
+    /// Every token in the order of the source.
+    pub fn tokens(&self) -> Vec<&Token> {
+        self.statements
+            .iter()
+            .flat_map(|statement| statement.tokens())
+            .collect()
+    }
+
+    /// Token which contains `offset`, see [`Span::contains`].
+    pub fn token_at(&self, offset: usize) -> Option<&Token> {
+        self.token_from(offset)
+            .filter(|token| token.span.contains(offset))
+    }
+
+    /// First token which ends at or behind `offset`, found with a binary search on every level
+    /// of the tree.
+    fn token_from(&self, offset: usize) -> Option<&Token> {
+        let index = self
+            .statements
+            .partition_point(|statement| statement.span.end < offset);
+
+        self.statements.get(index)?.token_from(offset)
+    }
+
+    /// Innermost node which contains `offset`.
+    pub fn node_at(&self, offset: usize) -> Option<&Node> {
+        self.statements
+            .iter()
+            .find_map(|statement| statement.node_at(offset))
+    }
+
+    /// Comment of a node without the `//`: the comment lines directly above the node and a
+    /// comment behind it on the same line, e.g. behind the `,` of a field.
+    pub fn comment(&self, node: &Node) -> Option<String> {
+        let first = node.first_token()?;
+        let leading = first.leading_trivia();
+
+        let mut lines = Vec::new();
+        for (index, trivia) in leading.iter().enumerate().rev() {
+            match trivia.kind {
+                // an empty line separates the comment from the node
+                TriviaKind::Whitespace if trivia.text.matches('\n').count() > 1 => break,
+                TriviaKind::Whitespace => (),
+                TriviaKind::Comment => {
+                    let own_line = match index.checked_sub(1) {
+                        Some(previous) => leading[previous].text.contains('\n'),
+                        None => trivia.span.start == 0,
+                    };
+
+                    // otherwise it belongs to the end of the previous line
+                    if !own_line {
+                        break;
+                    }
+                    lines.push(comment_text(&trivia.text));
+                }
+            }
+        }
+        lines.reverse();
+
+        // tokens don't overlap, so the next token is the first one ending behind the node
+        let following = match self.token_from(node.span.end + 1) {
+            Some(token) => token.leading_trivia(),
+            None => &self.trailing,
+        };
+        for trivia in following {
+            match trivia.kind {
+                TriviaKind::Whitespace if trivia.text.contains('\n') => break,
+                TriviaKind::Whitespace => (),
+                TriviaKind::Comment => {
+                    lines.push(comment_text(&trivia.text));
+                    break;
+                }
+            }
+        }
+
+        match lines.is_empty() {
+            true => None,
+            false => Some(lines.join("\n")),
+        }
+    }
+}
+
+fn comment_text(comment: &str) -> &str {
+    comment.trim_start_matches('/').trim()
+}
+
+impl Display for SyntaxTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for statement in &self.statements {
+            write!(f, "{}", statement)?;
+        }
+
+        for trivia in &self.trailing {
+            write!(f, "{}", trivia.text)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+fn word_length(input: &str) -> usize {
+    input
+        .find(|c: char| !is_word_char(c))
+        .unwrap_or(input.len())
+}
+
+/// Splits the source into tokens, returns the trivia behind the last token separately.
+fn tokenize(
+    source: &str,
+    line_index: &LineIndex,
+    file: Option<&Path>,
+) -> Result<(Vec<Token>, Vec<Trivia>)> {
+    let mut tokens = Vec::new();
+    let mut trivia = Vec::new();
+    let mut offset = 0;
+
+    while let Some(c) = source[offset..].chars().next() {
+        let rest = &source[offset..];
+
+        let (length, kind) = if c.is_whitespace() {
+            (
+                rest.find(|c: char| !c.is_whitespace())
+                    .unwrap_or(rest.len()),
+                Err(TriviaKind::Whitespace),
+            )
+        } else if rest.starts_with("//") {
+            (
+                rest.find('\n').unwrap_or(rest.len()),
+                Err(TriviaKind::Comment),
+            )
+        } else {
+            let length = match c {
+                '@' => 1 + word_length(&rest[1..]),
+                '(' => match group_length(rest) {
+                    Some(length) => length,
+                    None => bail!(
+                        "Error: The parentheses at {} aren't closed",
+                        format_location(file, line_index.position(offset))
+                    ),
+                },
+                '"' | '\'' => match rest[1..].find(c) {
+                    Some(index) => index + 2,
+                    None => bail!(
+                        "Error: The string at {} isn't closed",
+                        format_location(file, line_index.position(offset))
+                    ),
+                },
+                '{' | '}' | ',' | ';' | '=' => 1,
+                c if is_word_char(c) => word_length(rest),
+                _ => bail!(
+                    "Error: Unexpected character {:?} at {}",
+                    c,
+                    format_location(file, line_index.position(offset))
+                ),
+            };
+
+            let kind = match c {
+                '@' => TokenKind::Annotation,
+                '(' => TokenKind::Group,
+                '"' | '\'' => TokenKind::String,
+                c if is_word_char(c) => TokenKind::Word,
+                _ => TokenKind::Symbol,
+            };
+
+            (length, Ok(kind))
+        };
+
+        let text = rest[..length].to_string();
+        let span = Span {
+            start: offset,
+            end: offset + length,
+        };
+
+        match kind {
+            Ok(kind) => tokens.push(Token {
+                kind,
+                text,
+                span,
+                leading: std::mem::take(&mut trivia),
+            }),
+            Err(kind) => trivia.push(Trivia { kind, text, span }),
+        }
+
+        offset += length;
+    }
+
+    Ok((tokens, trivia))
+}
+
+/// Length of the group at the start of `input` including both parentheses, parentheses in
+/// strings and comments don't count.
+fn group_length(input: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut comment = false;
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match (quote, c) {
+            _ if comment => comment = c != '\n',
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '/') if chars.peek().map(|(_, c)| *c) == Some('/') => comment = true,
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(index + 1);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    None
+}
+
+struct Parser<'a> {
+    /// remaining tokens in reverse order
+    tokens: Vec<Token>,
+    line_index: LineIndex<'a>,
+    file: Option<&'a Path>,
+    /// children of the node which is currently parsed
+    children: Vec<Element>,
+}
+
+impl<'a> Parser<'a> {
+    fn statement(&mut self) -> Result<Node> {
+        if self.at(TokenKind::Word, "import") {
+            self.import()
+        } else if self.at(TokenKind::Annotation, "@naming") {
+            self.naming()
+        } else if self.at(TokenKind::Word, "schema") {
+            self.schema()
+        } else {
+            self.table()
+        }
+    }
+
+    fn import(&mut self) -> Result<Node> {
+        let outer = self.start();
+
+        self.expect(TokenKind::Word, Some("import"))?;
+        if self.at(TokenKind::Symbol, "{") {
+            self.bump();
+
+            loop {
+                self.expect(TokenKind::Word, None)?;
+
+                if !self.at(TokenKind::Symbol, ",") {
+                    break;
+                }
+                self.bump();
+            }
+
+            self.expect(TokenKind::Symbol, Some("}"))?;
+            self.expect(TokenKind::Word, Some("from"))?;
+        }
+        self.expect(TokenKind::String, None)?;
+        self.expect(TokenKind::Symbol, Some(";"))?;
+
+        Ok(self.finish(NodeKind::Import, outer))
+    }
+
+    fn naming(&mut self) -> Result<Node> {
+        let outer = self.start();
+
+        self.expect(TokenKind::Annotation, Some("@naming"))?;
+        self.expect(TokenKind::Group, None)?;
+        self.expect(TokenKind::Symbol, Some(";"))?;
+
+        Ok(self.finish(NodeKind::Naming, outer))
+    }
+
+    fn schema(&mut self) -> Result<Node> {
+        let outer = self.start();
+
+        self.expect(TokenKind::Word, Some("schema"))?;
+        self.expect(TokenKind::Word, None)?;
+        self.expect(TokenKind::Symbol, Some("{"))?;
+        while !self.tokens.is_empty() && !self.at(TokenKind::Symbol, "}") {
+            let table = self.table()?;
+            self.children.push(Element::Node(table));
+        }
+        self.expect(TokenKind::Symbol, Some("}"))?;
+
+        // the `;` is optional behind a schema
+        if self.at(TokenKind::Symbol, ";") {
+            self.bump();
+        }
+
+        Ok(self.finish(NodeKind::Schema, outer))
+    }
+
+    fn table(&mut self) -> Result<Node> {
+        let outer = self.start();
+
+        while self.at_kind(TokenKind::Annotation) {
+            let annotation = self.annotation()?;
+            self.children.push(Element::Node(annotation));
+        }
+        self.expect(TokenKind::Word, Some("table"))?;
+        self.expect(TokenKind::Word, None)?;
+        self.expect(TokenKind::Symbol, Some("{"))?;
+        while !self.tokens.is_empty() && !self.at(TokenKind::Symbol, "}") {
+            let field = self.field()?;
+            self.children.push(Element::Node(field));
+        }
+        self.expect(TokenKind::Symbol, Some("}"))?;
+        self.expect(TokenKind::Symbol, Some(";"))?;
+
+        Ok(self.finish(NodeKind::Table, outer))
+    }
+
+    fn annotation(&mut self) -> Result<Node> {
+        let outer = self.start();
+
+        self.expect(TokenKind::Annotation, None)?;
+
+        // arguments have to follow the annotation directly
+        if self
+            .tokens
+            .last()
+            .is_some_and(|token| token.kind == TokenKind::Group && token.leading.is_empty())
+        {
+            self.bump();
+        }
+
+        if self.at(TokenKind::Word, "as") {
+            self.bump();
+            self.expect(TokenKind::Word, None)?;
+        }
+
+        Ok(self.finish(NodeKind::Annotation, outer))
+    }
+
+    fn field(&mut self) -> Result<Node> {
+        let outer = self.start();
+
+        while self.at_kind(TokenKind::Annotation) {
+            let annotation = self.annotation()?;
+            self.children.push(Element::Node(annotation));
+        }
+
+        let datatype = self.start();
+        self.expect(TokenKind::Word, None)?;
+        if self.at_kind(TokenKind::Group) {
+            self.bump();
+        }
+        let datatype = self.finish(NodeKind::DataType, datatype);
+        self.children.push(Element::Node(datatype));
+
+        self.expect(TokenKind::Word, None)?;
+        self.expect(TokenKind::Symbol, Some(","))?;
+
+        Ok(self.finish(NodeKind::Field, outer))
+    }
+
+    /// Starts a new node, returns the children of the outer node.
+    fn start(&mut self) -> Vec<Element> {
+        std::mem::take(&mut self.children)
+    }
+
+    /// Finishes the current node and continues with the children of the outer node.
+    fn finish(&mut self, kind: NodeKind, outer: Vec<Element>) -> Node {
+        let children = std::mem::replace(&mut self.children, outer);
+
+        Node::new(kind, children)
+    }
+
+    fn at(&self, kind: TokenKind, text: &str) -> bool {
+        self.tokens.last().is_some_and(|token| token.is(kind, text))
+    }
+
+    fn at_kind(&self, kind: TokenKind) -> bool {
+        self.tokens.last().is_some_and(|token| token.kind == kind)
+    }
+
+    fn bump(&mut self) {
+        if let Some(token) = self.tokens.pop() {
+            self.children.push(Element::Token(token));
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, text: Option<&str>) -> Result<()> {
+        let expected = match text {
+            Some(text) => format!("{:?}", text),
+            None => format!("{:?}", kind).to_lowercase(),
+        };
+
+        match self.tokens.last() {
+            Some(token)
+                if token.kind == kind && (text.is_none() || text == Some(token.text.as_str())) =>
+            {
+                self.bump();
+
+                Ok(())
+            }
+            Some(token) => bail!(
+                "Error: Expected {} at {}, got {:?}",
+                expected,
+                format_location(self.file, self.line_index.position(token.span.start)),
+                token.text
+            ),
+            None => bail!("Error: Expected {} at the end of the file", expected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod parse {
+        use crate::syntax::{NodeKind, SyntaxTree, TokenKind, TriviaKind};
+
+        #[test]
+        fn just_works() {
+            let source = "// header
+import { Human } from \"people.tsql\";
+
+@primary_key(id) as pk_car // the key
+table Car {
+    int id,
+    @nullable
+    varchar(16) plate,
+    @foreign_key() Human owner,
+};
+schema billing { table Invoice { int id, }; }
+// end
+";
+            let tree = SyntaxTree::parse(source).unwrap();
+            assert_eq!(tree.to_string(), source);
+
+            let kinds = tree
+                .statements()
+                .iter()
+                .map(|statement| statement.kind())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                kinds,
+                vec![NodeKind::Import, NodeKind::Table, NodeKind::Schema]
+            );
+
+            let car = &tree.statements()[1];
+            assert_eq!(car.name().unwrap().text(), "Car");
+            assert_eq!(
+                car.to_string().trim(),
+                &source[car.span().start..car.span().end]
+            );
+
+            let annotation = car.nodes().next().unwrap();
+            assert_eq!(annotation.kind(), NodeKind::Annotation);
+            assert_eq!(annotation.name().unwrap().text(), "@primary_key");
+            assert_eq!(annotation.arguments().unwrap().text(), "(id)");
+            assert_eq!(annotation.constraint_name().unwrap().text(), "pk_car");
+
+            let fields = car
+                .nodes()
+                .filter(|node| node.kind() == NodeKind::Field)
+                .map(|node| node.name().unwrap().text())
+                .collect::<Vec<_>>();
+            assert_eq!(fields, vec!["id", "plate", "owner"]);
+
+            let offset = source.find("Human owner").unwrap();
+            let datatype = tree.node_at(offset).unwrap();
+            assert_eq!(datatype.kind(), NodeKind::DataType);
+            assert_eq!(datatype.name().unwrap().text(), "Human");
+            assert_eq!(tree.token_at(offset).unwrap().kind(), TokenKind::Word);
+            // the end of a name is still part of it, whitespace and comments aren't
+            let offset = source.find("plate,").unwrap() + "plate".len();
+            assert_eq!(tree.token_at(offset).unwrap().text(), "plate");
+            assert!(tree.token_at(source.find("// the key").unwrap()).is_none());
+
+            let table = tree
+                .tokens()
+                .into_iter()
+                .find(|token| token.text() == "table")
+                .unwrap();
+            assert_eq!(table.leading_trivia()[1].kind(), TriviaKind::Comment);
+            assert_eq!(table.leading_trivia()[1].text(), "// the key");
+
+            assert_eq!(tree.trailing_trivia()[1].text(), "// end");
+        }
+
+        #[test]
+        fn errors() {
+            assert_eq!(
+                SyntaxTree::parse("table A {\n    int a\n};")
+                    .unwrap_err()
+                    .to_string(),
+                "Error: Expected \",\" at 3:1, got \"}\""
+            );
+            assert_eq!(
+                SyntaxTree::parse("table A { int a, }")
+                    .unwrap_err()
+                    .to_string(),
+                "Error: Expected \";\" at the end of the file"
+            );
+            assert!(SyntaxTree::parse("@check(a > 0 table A {};").is_err());
+            assert!(SyntaxTree::parse("table A { int a, }; #").is_err());
+        }
+    }
+
+    mod comment {
+        use crate::syntax::SyntaxTree;
+
+        #[test]
+        fn just_works() {
+            let source = "// header\n\n// people\n// and pets\ntable A {\n    int a, // the key\n    // unused\n\n    int b,\n    int c,\n}; // end\n";
+            let tree = SyntaxTree::parse(source).unwrap();
+
+            let table = &tree.statements()[0];
+            assert_eq!(
+                tree.comment(table).as_deref(),
+                Some("people\nand pets\nend")
+            );
+
+            let fields = table
+                .nodes()
+                .map(|node| tree.comment(node))
+                .collect::<Vec<_>>();
+            assert_eq!(fields, vec![Some("the key".to_string()), None, None]);
+        }
+    }
+}
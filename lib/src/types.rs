@@ -1,23 +1,145 @@
-use std::cell::RefCell;
-use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
 use std::io::Write;
-use std::rc::Rc;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use anyhow::{bail, Result};
-use static_assertions::const_assert_eq;
+use anyhow::{bail, Error, Result};
+use static_assertions::assert_impl_all;
 
 use crate::parser::types::{FieldExtra, FieldType, RawDataType, RawField, RawTable, TableExtra};
+use crate::syntax::Span;
 use crate::TransformSQL;
 
 pub type GenericCollection<T> = BTreeMap<String, T>;
 pub type TableCollection = GenericCollection<Table>;
-pub(crate) type RawTableCollection = GenericCollection<Rc<RefCell<RawTable>>>;
+pub(crate) type RawTableCollection = GenericCollection<RawTable>;
 
-fn get_first_element<K: Ord, V>(collection: &BTreeMap<K, V>) -> Option<(&K, &V)> {
-    let key = collection.keys().next()?;
-    let item = collection.get(key)?;
+// resolved tables only hold owned data, so they can be shared across threads, e.g. in an `Arc`
+assert_impl_all!(TableCollection: Send, Sync);
 
-    Some((key, item))
+/// Location of a table or field inside of the parsed content, `line` and `column` start at 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    pub(crate) offset: usize,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+impl Position {
+    /// Byte offset from the start of the content.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Joins `schema` and `name` to `schema.name`, or returns `name` if there is no schema.
+pub(crate) fn qualified_name(schema: Option<&str>, name: &str) -> String {
+    match schema {
+        Some(schema) => format!("{}.{}", schema, name),
+        None => name.to_string(),
+    }
+}
+
+/// Returns `name` from `schema.name`.
+pub(crate) fn unqualified_name(qualified_name: &str) -> &str {
+    match qualified_name.split_once('.') {
+        Some((_, name)) => name,
+        None => qualified_name,
+    }
+}
+
+/// Returns the tables ordered so that every table comes after the tables it references.
+/// Tables without a dependency between them are ordered by their qualified name.
+pub fn dependency_order(tables: &TableCollection) -> Vec<&Table> {
+    let mut ordered: Vec<&Table> = Vec::with_capacity(tables.len());
+    let mut done: HashSet<&str> = HashSet::with_capacity(tables.len());
+
+    while ordered.len() < tables.len() {
+        let resolvable = tables
+            .iter()
+            .filter(|(name, _)| !done.contains(name.as_str()))
+            .filter(|(name, table)| {
+                table.foreign_keys.iter().all(|foreign_key| {
+                    &foreign_key.table == *name || done.contains(foreign_key.table.as_str())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // a resolved collection doesn't contain cycles, but tables can be created by hand
+        if resolvable.is_empty() {
+            ordered.extend(
+                tables
+                    .iter()
+                    .filter(|(name, _)| !done.contains(name.as_str()))
+                    .map(|(_, table)| table),
+            );
+            break;
+        }
+
+        for (name, table) in resolvable {
+            done.insert(name);
+            ordered.push(table);
+        }
+    }
+
+    ordered
+}
+
+/// Formats `position` prefixed with the file if there is one, e.g. `schema.tsql:3:7`.
+pub(crate) fn format_location(file: Option<&Path>, position: Position) -> String {
+    match file {
+        Some(file) => format!("{}:{}", file.display(), position),
+        None => position.to_string(),
+    }
+}
+
+/// Lookup table from byte offsets to lines and columns.
+pub(crate) struct LineIndex<'a> {
+    content: &'a str,
+    /// byte offsets at which a line starts
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(content: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        LineIndex {
+            content,
+            line_starts,
+        }
+    }
+
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+        let line_start = self.line_starts[line];
+
+        Position {
+            offset,
+            line: line + 1,
+            column: self.content[line_start..offset].chars().count() + 1,
+        }
+    }
 }
 
 #[derive(Debug, Default)]
@@ -25,116 +147,256 @@ pub struct Table {
     pub(crate) extra: TableExtra,
 
     pub(crate) name: String,
+    pub(crate) schema: Option<String>,
+    pub(crate) position: Position,
+    pub(crate) file: Option<PathBuf>,
+    pub(crate) span: Span,
+    pub(crate) comment: Option<String>,
 
     pub(crate) fields: HashMap<String, Field>,
+    /// names of the fields in the order of declaration
+    pub(crate) field_order: Vec<String>,
+
+    pub(crate) foreign_keys: Vec<ForeignKey>,
+
+    pub(crate) constraints: Vec<Constraint>,
 }
 
 impl Table {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn get_field(&self, key: &str) -> Option<&Field> {
         self.fields.get(key)
     }
 
-    pub fn primary_keys(&self) -> &Vec<String> {
-        &self.extra.primary_key
+    /// Fields in the order of declaration, the fields generated for a foreign key take the place
+    /// of the `@foreign_key()` field.
+    pub fn fields(&self) -> impl Iterator<Item = &Field> {
+        self.field_order.iter().map(|name| &self.fields[name])
     }
 
-    pub(crate) fn parse_raw_tables(mut raw_tables: RawTableCollection) -> Result<TableCollection> {
-        let mut parsed = TableCollection::new();
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Database schema of the table, `None` for the default schema.
+    pub fn schema(&self) -> Option<&str> {
+        self.schema.as_deref()
+    }
 
-        let mut raw_tables_order: VecDeque<Rc<RefCell<RawTable>>> =
-            VecDeque::with_capacity(raw_tables.len());
+    /// `schema.name` or `name` if the table isn't part of a schema, used as the key inside of
+    /// a [`TableCollection`].
+    pub fn qualified_name(&self) -> String {
+        qualified_name(self.schema.as_deref(), &self.name)
+    }
 
-        // TODO try to combine the two `while` loops
-        while let Some((name, table)) = get_first_element(&raw_tables) {
-            let name = name.clone();
-            let table = table.clone();
+    /// File in which the table is defined, `None` if it has been parsed from a string.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
 
-            let has_fk = table.borrow().has_fk();
+    /// Byte range of the table including its annotations inside of its source, see
+    /// [`Table::file`].
+    pub fn span(&self) -> Span {
+        self.span
+    }
 
-            if has_fk {
-                let fk_tables = table.borrow().fk_tables();
+    /// `//` comment lines directly above the table, see [`crate::syntax::SyntaxTree::comment`].
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
 
-                // TODO why `.clone()`?
-                let ordered_tables = raw_tables_order
-                    .iter()
-                    .map(|item| item.borrow().name.clone())
-                    .collect::<Vec<_>>();
-
-                // TODO merge the next `if` body with the following `for` loop
-                let mut all_fk_are_ordered = true;
-                for fk_table in fk_tables {
-                    if !ordered_tables.contains(&fk_table) {
-                        all_fk_are_ordered = false;
-                        break;
-                    }
-                }
+    /// Qualified name of the table before it has been renamed with `@renamed_from(old_name)`,
+    /// an unqualified old name is part of the same schema as the table.
+    pub fn renamed_from(&self) -> Option<&str> {
+        self.extra.renamed_from.as_deref()
+    }
+
+    pub fn primary_keys(&self) -> &Vec<String> {
+        &self.extra.primary_key
+    }
+
+    pub fn foreign_keys(&self) -> &Vec<ForeignKey> {
+        &self.foreign_keys
+    }
 
-                if !all_fk_are_ordered {
-                    // TODO check for loop inside raw_tables with table
-                    raw_tables.remove(&name);
-                    raw_tables.insert(name, table);
+    /// Foreign key generated by the `@foreign_key()` field with the name `name`.
+    pub fn get_foreign_key(&self, name: &str) -> Option<&ForeignKey> {
+        self.foreign_keys.iter().find(|item| item.name == name)
+    }
 
-                    // TODO remove `continue`
-                    continue;
+    /// Primary key, unique and check constraints of the table, see [`Table::foreign_keys`] for
+    /// the foreign keys.
+    pub fn constraints(&self) -> &Vec<Constraint> {
+        &self.constraints
+    }
+
+    pub(crate) fn parse_raw_tables(mut raw_tables: RawTableCollection) -> Result<TableCollection> {
+        for table in raw_tables.values() {
+            for fk_table in table.fk_tables() {
+                if !raw_tables.contains_key(&fk_table) {
+                    bail!(
+                        "Error: Table {:?} at {} references the table {:?}, which doesn't exist",
+                        table.name,
+                        format_location(table.file.as_deref(), table.position),
+                        fk_table
+                    );
                 }
             }
-
-            raw_tables.remove(&name);
-            raw_tables_order.push_back(table);
         }
 
-        while let Some(raw_table) = raw_tables_order.pop_front() {
-            let parsed_table = Table::parse(raw_table, &parsed)?;
+        let mut parsed = TableCollection::new();
 
-            parsed.insert(parsed_table.name.clone(), parsed_table);
+        // a table is resolved after all tables it references, self references are resolved
+        // against the table itself in `Table::parse`
+        while !raw_tables.is_empty() {
+            let resolvable = raw_tables
+                .iter()
+                .filter(|(name, table)| {
+                    table
+                        .fk_tables()
+                        .iter()
+                        .all(|fk_table| fk_table == *name || parsed.contains_key(fk_table))
+                })
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+
+            if resolvable.is_empty() {
+                bail!(
+                    "Error: The foreign keys of the tables {:?} reference each other in a cycle",
+                    raw_tables.keys().collect::<Vec<_>>()
+                );
+            }
+
+            for name in resolvable {
+                let raw_table = raw_tables.remove(&name).unwrap();
+                let parsed_table = Table::parse(&raw_table, &parsed)?;
+
+                parsed.insert(name, parsed_table);
+            }
         }
 
+        Table::validate_constraint_names(&parsed)?;
+
         Ok(parsed)
     }
 
-    pub(crate) fn parse(
-        raw: Rc<RefCell<RawTable>>,
-        parsed_tables: &TableCollection,
-    ) -> Result<Self> {
+    pub(crate) fn parse(raw: &RawTable, parsed_tables: &TableCollection) -> Result<Self> {
         let mut parsed_table = Table::default();
 
-        let raw = raw.borrow();
+        let naming = &raw.naming;
 
         parsed_table.name = raw.name.clone();
-
-        for field_type in raw.fields.values() {
+        parsed_table.schema = raw.schema.clone();
+        parsed_table.position = raw.position;
+        parsed_table.file = raw.file.clone();
+        parsed_table.span = raw.span;
+        parsed_table.comment = raw.comment.clone();
+        parsed_table.extra.renamed_from =
+            raw.extra
+                .renamed_from
+                .as_ref()
+                .map(|old_name| match old_name.contains('.') {
+                    true => old_name.clone(),
+                    false => qualified_name(raw.schema.as_deref(), old_name),
+                });
+
+        for field_type in &raw.fields {
             match field_type {
                 FieldType::Real(raw_field) => {
-                    parsed_table
-                        .fields
-                        .insert(raw_field.name.clone(), Field::parse(raw_field)?);
+                    parsed_table.insert_field(Field::parse(raw_field)?, None)?;
                 }
-                FieldType::Virtual((raw_field, FieldExtra::ForeignKey)) => {
+                FieldType::Virtual((raw_field, FieldExtra::ForeignKey(raw_foreign_key))) => {
+                    let columns = &raw_foreign_key.columns;
+
                     let fk_table_name = match &raw_field.datatype {
                         RawDataType::ForeignKeyTable(fk_table_name) => fk_table_name,
                         _ => todo!(),
                     };
 
-                    let fk_table = parsed_tables.get(fk_table_name).unwrap();
-                    let fk_table_primary_key = &fk_table.extra.primary_key;
+                    if raw_field.default.is_some() {
+                        bail!(
+                            "Error: Field {:?} in table {:?} is a foreign key, which can't have a default value",
+                            raw_field.name,
+                            raw.name
+                        );
+                    }
+
+                    let is_self_reference = fk_table_name == &raw.qualified_name();
+                    // naming always uses the unqualified name of the referenced table
+                    let fk_table_short_name = unqualified_name(fk_table_name);
+
+                    let fk_fields = if is_self_reference {
+                        Table::self_referenced_primary_key(raw)?
+                    } else {
+                        let fk_table = parsed_tables.get(fk_table_name).unwrap();
+
+                        fk_table
+                            .extra
+                            .primary_key
+                            .iter()
+                            .map(|name| fk_table.fields.get(name).unwrap().clone())
+                            .collect::<Vec<_>>()
+                    };
+
+                    if !columns.is_empty() && columns.len() != fk_fields.len() {
+                        bail!(
+                            "Error: Field {:?} in table {:?} overrides {} column(s), but the primary key of {:?} has {} field(s)",
+                            raw_field.name,
+                            raw.name,
+                            columns.len(),
+                            fk_table_name,
+                            fk_fields.len()
+                        );
+                    }
+
+                    let mut foreign_key = ForeignKey {
+                        name: raw_field.name.clone(),
+                        table: fk_table_name.clone(),
+                        columns: Vec::with_capacity(fk_fields.len()),
+                        references: Vec::with_capacity(fk_fields.len()),
+                        constraint: raw_foreign_key.name.clone().unwrap_or_else(|| {
+                            naming.foreign_key_name(&raw.name, &raw_field.name, fk_table_short_name)
+                        }),
+                        renamed_from: raw_field.renamed_from.clone(),
+                    };
 
                     let prefix = &raw_field.name;
-                    for fk_table_primary_key_name in fk_table_primary_key {
-                        let fk_field = fk_table.fields.get(fk_table_primary_key_name).unwrap();
+                    for (index, fk_field) in fk_fields.into_iter().enumerate() {
+                        let field_name = match columns.get(index) {
+                            Some(column) => column.clone(),
+                            None => naming.column.column_name(
+                                prefix,
+                                fk_table_short_name,
+                                &fk_field.name,
+                            ),
+                        };
 
-                        let field_name = format!("{}_{}", prefix, fk_field.name);
+                        foreign_key.columns.push(field_name.clone());
+                        foreign_key.references.push(fk_field.name.clone());
 
                         let field = Field {
-                            name: field_name.clone(),
+                            name: field_name,
                             datatype: fk_field.datatype,
+                            // a row can't reference itself on insert, e.g. the root of a tree
+                            nullable: is_self_reference || raw_field.nullable,
+                            default: None,
+                            position: raw_field.position,
+                            span: raw_field.span,
+                            comment: raw_field.comment.clone(),
                             foreign_key_reference: Some((
                                 fk_table_name.clone(),
-                                Rc::new(fk_field.clone()),
+                                fk_field.name.clone(),
                             )),
+                            renamed_from: None,
                         };
 
-                        parsed_table.fields.insert(field_name, field);
+                        parsed_table.insert_field(field, Some(&raw_field.name))?;
                     }
+
+                    parsed_table.foreign_keys.push(foreign_key);
                 }
             };
         }
@@ -154,55 +416,161 @@ impl Table {
                 .push(primary_key_field.clone());
         }
 
+        if !parsed_table.extra.primary_key.is_empty() {
+            parsed_table.constraints.push(Constraint {
+                name: raw
+                    .extra
+                    .primary_key_name
+                    .clone()
+                    .unwrap_or_else(|| naming.primary_key_name(&raw.name)),
+                kind: ConstraintKind::PrimaryKey(parsed_table.extra.primary_key.clone()),
+            });
+        }
+
+        for (unique_fields, name) in &raw.extra.unique {
+            for unique_field in unique_fields {
+                if !parsed_table.fields.contains_key(unique_field) {
+                    bail!(
+                        "Error: Table {:?} doesn't have a field with the name {:?}, encountered error while checking for unique constraint",
+                        parsed_table.name,
+                        unique_field
+                    );
+                }
+            }
+
+            parsed_table.constraints.push(Constraint {
+                name: name
+                    .clone()
+                    .unwrap_or_else(|| naming.unique_name(&raw.name, unique_fields)),
+                kind: ConstraintKind::Unique(unique_fields.clone()),
+            });
+        }
+
+        for (index, (expression, name)) in raw.extra.check.iter().enumerate() {
+            parsed_table.constraints.push(Constraint {
+                name: name
+                    .clone()
+                    .unwrap_or_else(|| naming.check_name(&raw.name, index + 1)),
+                kind: ConstraintKind::Check(expression.clone()),
+            });
+        }
+
         Ok(parsed_table)
     }
+
+    /// Adds the field to the table, fails if a field with the same name already exists.
+    /// `foreign_key` is the name of the `@foreign_key()` field which generated `field`.
+    fn insert_field(&mut self, field: Field, foreign_key: Option<&str>) -> Result<()> {
+        if let Some(existing) = self.fields.get(&field.name) {
+            let generated_by = match foreign_key {
+                Some(foreign_key) => format!(" (generated by the foreign key {:?})", foreign_key),
+                None => String::new(),
+            };
+
+            bail!(
+                "Error: Table {:?} has more than one field with the name {:?}, defined at {} and at {}{}",
+                self.name,
+                field.name,
+                existing.position,
+                field.position,
+                generated_by
+            );
+        }
+
+        self.field_order.push(field.name.clone());
+        self.fields.insert(field.name.clone(), field);
+
+        Ok(())
+    }
+
+    /// Names of all constraints of the table, including the foreign keys.
+    pub fn constraint_names(&self) -> Vec<&str> {
+        self.constraints
+            .iter()
+            .map(|item| item.name.as_str())
+            .chain(
+                self.foreign_keys
+                    .iter()
+                    .map(|item| item.constraint.as_str()),
+            )
+            .collect()
+    }
+
+    /// Checks that no constraint name is used twice inside of a database schema.
+    fn validate_constraint_names(tables: &TableCollection) -> Result<()> {
+        let mut seen: HashMap<(Option<&str>, &str), &str> = HashMap::new();
+
+        for table in tables.values() {
+            for name in table.constraint_names() {
+                if let Some(other_table) = seen.insert((table.schema(), name), &table.name) {
+                    bail!(
+                        "Error: Constraint name {:?} is used in table {:?} and in table {:?}",
+                        name,
+                        other_table,
+                        table.name
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the primary key of a table that is still being parsed, used for foreign keys
+    /// which reference their own table.
+    fn self_referenced_primary_key(raw: &RawTable) -> Result<Vec<Field>> {
+        let mut fields = Vec::with_capacity(raw.extra.primary_key.len());
+
+        for primary_key_field in &raw.extra.primary_key {
+            match raw.get_field(primary_key_field) {
+                Some(FieldType::Real(raw_field)) => fields.push(Field::parse(raw_field)?),
+                Some(FieldType::Virtual(_)) => bail!(
+                    "Error: Table {:?} references itself, but the primary key field {:?} is a foreign key",
+                    raw.name,
+                    primary_key_field
+                ),
+                None => bail!(
+                    "Error: Table {:?} doesn't have a field with the name {:?}, encountered error while resolving a self reference",
+                    raw.name,
+                    primary_key_field
+                ),
+            }
+        }
+
+        Ok(fields)
+    }
 }
 
 impl TransformSQL for Table {
     fn transform<W: Write>(&self, buffer: &mut W) -> Result<()> {
-        writeln!(buffer, "CREATE TABLE {} (", self.name)?;
+        writeln!(buffer, "CREATE TABLE {} (", self.qualified_name())?;
 
-        let mut foreign_keys_table_fields: HashMap<String, Vec<&Field>> = HashMap::new();
+        let mut definitions = Vec::with_capacity(
+            self.fields.len() + self.foreign_keys.len() + self.constraints.len(),
+        );
 
-        for field in self.fields.values() {
-            field.transform(buffer)?;
+        for field in self.fields() {
+            let mut definition = Vec::new();
+            field.transform(&mut definition)?;
 
-            if field.foreign_key_reference.is_some() {
-                let table = &field.foreign_key_reference.as_ref().unwrap().0;
+            definitions.push(String::from_utf8(definition)?);
+        }
 
-                match foreign_keys_table_fields.get_mut(table) {
-                    Some(fields_list) => {
-                        fields_list.push(field);
-                    }
-                    None => {
-                        foreign_keys_table_fields.insert(table.to_string(), vec![field]);
-                    }
-                };
-            }
+        for foreign_key in &self.foreign_keys {
+            let mut definition = Vec::new();
+            foreign_key.transform(&mut definition)?;
+
+            definitions.push(String::from_utf8(definition)?);
         }
 
-        for (table_name, fields) in foreign_keys_table_fields {
-            let field_names = fields
-                .iter()
-                .map(|item| item.name.as_str())
-                .collect::<Vec<_>>()
-                .join(",");
+        for constraint in &self.constraints {
+            let mut definition = Vec::new();
+            constraint.transform(&mut definition)?;
 
-            let other_field_names = fields
-                .iter()
-                .map(|item| item.foreign_key_reference.as_ref().unwrap().1.name.clone())
-                .collect::<Vec<_>>()
-                .join(",");
-
-            writeln!(
-                buffer,
-                "FOREIGN KEY ({}) REFERENCES {}({}),",
-                field_names, table_name, other_field_names
-            )?;
+            definitions.push(String::from_utf8(definition)?);
         }
 
-        let primary_key_formatted = self.extra.primary_key.join(",");
-        writeln!(buffer, "PRIMARY KEY ({})", primary_key_formatted)?;
+        writeln!(buffer, "{}", definitions.join(",\n"))?;
 
         writeln!(buffer, ");")?;
 
@@ -210,12 +578,128 @@ impl TransformSQL for Table {
     }
 }
 
+/// A named primary key, unique or check constraint.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Constraint {
+    pub(crate) name: String,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub(crate) kind: ConstraintKind,
+}
+
+impl Constraint {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> &ConstraintKind {
+        &self.kind
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
+pub enum ConstraintKind {
+    /// values: fields of the primary key
+    PrimaryKey(Vec<String>),
+    /// values: fields which have to be unique together
+    Unique(Vec<String>),
+    /// values: the expression as it has been written in the schema
+    Check(String),
+}
+
+impl TransformSQL for Constraint {
+    fn transform<W: Write>(&self, buffer: &mut W) -> Result<()> {
+        write!(buffer, "CONSTRAINT {} ", self.name)?;
+
+        match &self.kind {
+            ConstraintKind::PrimaryKey(fields) => {
+                write!(buffer, "PRIMARY KEY ({})", fields.join(","))?
+            }
+            ConstraintKind::Unique(fields) => write!(buffer, "UNIQUE ({})", fields.join(","))?,
+            ConstraintKind::Check(expression) => write!(buffer, "CHECK ({})", expression)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// A foreign key created from a `@foreign_key()` field.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForeignKey {
+    /// name of the `@foreign_key()` field
+    pub(crate) name: String,
+    /// name of the referenced table
+    pub(crate) table: String,
+    /// generated columns, in the order of the primary key of the referenced table
+    pub(crate) columns: Vec<String>,
+    /// primary key fields of the referenced table
+    pub(crate) references: Vec<String>,
+    pub(crate) constraint: String,
+    /// name of the `@foreign_key()` field before it has been renamed
+    pub(crate) renamed_from: Option<String>,
+}
+
+impl ForeignKey {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    pub fn columns(&self) -> &Vec<String> {
+        &self.columns
+    }
+
+    pub fn references(&self) -> &Vec<String> {
+        &self.references
+    }
+
+    pub fn constraint(&self) -> &str {
+        &self.constraint
+    }
+
+    /// Name of the `@foreign_key()` field before it has been renamed with
+    /// `@renamed_from(old_name)`.
+    pub fn renamed_from(&self) -> Option<&str> {
+        self.renamed_from.as_deref()
+    }
+}
+
+impl TransformSQL for ForeignKey {
+    fn transform<W: Write>(&self, buffer: &mut W) -> Result<()> {
+        write!(
+            buffer,
+            "CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {}({})",
+            self.constraint,
+            self.columns.join(","),
+            self.table,
+            self.references.join(",")
+        )?;
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Field {
     pub(crate) name: String,
     pub(crate) datatype: DataType,
-    // TODO change Rc<Field> to Box<Field>
-    pub(crate) foreign_key_reference: Option<(String, Rc<Field>)>,
+    pub(crate) nullable: bool,
+    pub(crate) default: Option<String>,
+    pub(crate) position: Position,
+    pub(crate) span: Span,
+    pub(crate) comment: Option<String>,
+    /// values: `(qualified name of the referenced table, name of the referenced field)`
+    pub(crate) foreign_key_reference: Option<(String, String)>,
+    pub(crate) renamed_from: Option<String>,
 }
 
 impl Field {
@@ -223,13 +707,71 @@ impl Field {
         Ok(Field {
             name: raw.name.to_string(),
             datatype: DataType::parse(&raw.datatype)?,
+            nullable: raw.nullable,
+            default: raw.default.clone(),
+            position: raw.position,
+            span: raw.span,
+            comment: raw.comment.clone(),
             foreign_key_reference: None,
+            renamed_from: raw.renamed_from.clone(),
         })
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn datatype(&self) -> &DataType {
         &self.datatype
     }
+
+    pub fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+
+    /// Default value as it has been written in `@default(...)`.
+    pub fn default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    /// Position of the field, or of the `@foreign_key()` field it has been generated for.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Name of the field before it has been renamed with `@renamed_from(old_name)`, generated
+    /// foreign key fields are renamed together with their [`ForeignKey`].
+    pub fn renamed_from(&self) -> Option<&str> {
+        self.renamed_from.as_deref()
+    }
+
+    /// Byte range of the field including its annotations inside of the source of its table, the
+    /// `@foreign_key()` field for generated fields.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// `//` comment lines directly above the field and a comment behind it on the same line, see
+    /// [`crate::syntax::SyntaxTree::comment`].
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    /// Name of the referenced table if the field has been generated for a foreign key.
+    pub fn foreign_key_table(&self) -> Option<&str> {
+        self.foreign_key_reference
+            .as_ref()
+            .map(|(table, _)| table.as_str())
+    }
+
+    /// Referenced table and primary key field if the field has been generated for a foreign key,
+    /// `tables` is the collection the field has been resolved with.
+    pub fn references<'a>(&self, tables: &'a TableCollection) -> Option<(&'a Table, &'a Field)> {
+        let (table_name, field_name) = self.foreign_key_reference.as_ref()?;
+        let table = tables.get(table_name)?;
+
+        Some((table, table.get_field(field_name)?))
+    }
 }
 
 impl TransformSQL for Field {
@@ -237,6 +779,14 @@ impl TransformSQL for Field {
         write!(buffer, "{} ", self.name)?;
         self.datatype.transform(buffer)?;
 
+        if !self.nullable {
+            write!(buffer, " NOT NULL")?;
+        }
+
+        if let Some(default) = &self.default {
+            write!(buffer, " DEFAULT {}", default)?;
+        }
+
         Ok(())
     }
 }
@@ -260,29 +810,74 @@ pub enum DataType {
     /// values: `(precision, scale)`
     Decimal(u8, u8),
 }
-const_assert_eq!(
-    std::mem::variant_count::<RawDataType>() - 2,
-    std::mem::variant_count::<DataType>()
-);
+
+/// Builtin types of tsql with their arguments, every other type of a field references a table.
+pub const BUILTIN_TYPES: [(&str, &str); 13] = [
+    ("int", "int"),
+    ("bool", "bool"),
+    ("bigint", "bigint"),
+    ("date", "date"),
+    ("datetime", "datetime"),
+    ("time", "time"),
+    ("double", "double"),
+    ("float", "float"),
+    ("uuid", "uuid"),
+    ("varchar", "varchar(length)"),
+    ("char", "char(length)"),
+    ("text", "text(length)"),
+    ("decimal", "decimal(precision, scale)"),
+];
 
 impl DataType {
+    /// Builtin type with the name and arguments as written in tsql, e.g. `varchar` and `["32"]`.
+    /// `None` if there is no such type, see [`BUILTIN_TYPES`].
+    pub fn from_name(name: &str, arguments: &[&str]) -> Option<Self> {
+        match (name, arguments) {
+            ("int", []) => Some(DataType::Int),
+            ("bool", []) => Some(DataType::Bool),
+            ("bigint", []) => Some(DataType::BigInt),
+            ("date", []) => Some(DataType::Date),
+            ("datetime", []) => Some(DataType::DateTime),
+            ("time", []) => Some(DataType::Time),
+            ("double", []) => Some(DataType::Double),
+            ("float", []) => Some(DataType::Float),
+            ("uuid", []) => Some(DataType::Uuid),
+
+            ("varchar", [length]) => length.parse().ok().map(DataType::VarChar),
+            ("char", [length]) => length.parse().ok().map(DataType::Char),
+            ("text", [length]) => length.parse().ok().map(DataType::Text),
+
+            ("decimal", [precision, scale]) => match (precision.parse(), scale.parse()) {
+                (Ok(precision), Ok(scale)) => Some(DataType::Decimal(precision, scale)),
+                _ => None,
+            },
+
+            _ => None,
+        }
+    }
+
+    /// Name of the type in tsql without its arguments, one of [`BUILTIN_TYPES`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataType::Int => "int",
+            DataType::Bool => "bool",
+            DataType::BigInt => "bigint",
+            DataType::Date => "date",
+            DataType::DateTime => "datetime",
+            DataType::Time => "time",
+            DataType::Double => "double",
+            DataType::Float => "float",
+            DataType::Uuid => "uuid",
+            DataType::VarChar(_) => "varchar",
+            DataType::Char(_) => "char",
+            DataType::Text(_) => "text",
+            DataType::Decimal(_, _) => "decimal",
+        }
+    }
+
     fn parse(raw: &RawDataType) -> Result<Self> {
         match raw {
-            RawDataType::Int => Ok(DataType::Int),
-            RawDataType::Bool => Ok(DataType::Bool),
-            RawDataType::BigInt => Ok(DataType::BigInt),
-            RawDataType::Date => Ok(DataType::Date),
-            RawDataType::DateTime => Ok(DataType::DateTime),
-            RawDataType::Time => Ok(DataType::Time),
-            RawDataType::Double => Ok(DataType::Double),
-            RawDataType::Float => Ok(DataType::Float),
-            RawDataType::Uuid => Ok(DataType::Uuid),
-
-            RawDataType::VarChar(args) => Ok(DataType::VarChar(*args)),
-            RawDataType::Char(args) => Ok(DataType::Char(*args)),
-            RawDataType::Text(args) => Ok(DataType::Text(*args)),
-
-            RawDataType::Decimal(precision, scale) => Ok(DataType::Decimal(*precision, *scale)),
+            RawDataType::Builtin(datatype) => Ok(*datatype),
 
             RawDataType::Unknown => bail!("Error: encountered type unknown. raw: {:?}", raw),
             RawDataType::ForeignKeyTable(_) => {
@@ -292,6 +887,41 @@ impl DataType {
     }
 }
 
+/// The type as it is written in tsql, e.g. `decimal(10, 2)`.
+impl Display for DataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataType::VarChar(length) | DataType::Text(length) => {
+                write!(f, "{}({})", self.name(), length)
+            }
+            DataType::Char(length) => write!(f, "{}({})", self.name(), length),
+            DataType::Decimal(precision, scale) => {
+                write!(f, "{}({}, {})", self.name(), precision, scale)
+            }
+            _ => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+impl FromStr for DataType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, arguments) = match s.split_once('(') {
+            Some((name, arguments)) => match arguments.strip_suffix(')') {
+                Some(arguments) => (name, arguments.split(',').map(str::trim).collect()),
+                None => bail!("Error: Missing \")\" in the type {:?}", s),
+            },
+            None => (s, Vec::new()),
+        };
+
+        match DataType::from_name(name.trim(), &arguments) {
+            Some(datatype) => Ok(datatype),
+            None => bail!("Error: Unknown type {:?}", s),
+        }
+    }
+}
+
 impl TransformSQL for DataType {
     fn transform<W: Write>(&self, buffer: &mut W) -> Result<()> {
         let formatted = match self {
@@ -312,8 +942,40 @@ impl TransformSQL for DataType {
             DataType::Decimal(precision, scale) => format!("decimal({}, {})", precision, scale),
         };
 
-        writeln!(buffer, "{},", formatted)?;
+        write!(buffer, "{}", formatted)?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    mod from_name {
+        use crate::types::{DataType, BUILTIN_TYPES};
+
+        #[test]
+        fn just_works() {
+            // every listed type can be parsed with its arguments and has the listed name
+            for (name, signature) in BUILTIN_TYPES {
+                let arguments = signature.matches(['(', ',']).count();
+                let arguments = vec!["8"; arguments];
+
+                let datatype = DataType::from_name(name, &arguments).unwrap();
+                assert_eq!(datatype.name(), name);
+            }
+
+            assert_eq!(
+                DataType::from_name("decimal", &["24", "4"]),
+                Some(DataType::Decimal(24, 4))
+            );
+        }
+
+        #[test]
+        fn errors() {
+            assert_eq!(DataType::from_name("Human", &[]), None);
+            assert_eq!(DataType::from_name("varchar", &[]), None);
+            assert_eq!(DataType::from_name("int", &["4"]), None);
+            assert_eq!(DataType::from_name("char", &["256"]), None);
+        }
+    }
+}
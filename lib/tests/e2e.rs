@@ -1,10 +1,28 @@
 use std::ffi::OsStr;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
-use tsql::parse_file;
-use tsql::types::DataType;
+use tsql::codegen::typescript::TypeScriptOptions;
+use tsql::ddl::parse_sql;
+use tsql::dialect::Dialect;
+use tsql::diff::diff;
+use tsql::formatter::format_str;
+use tsql::graph::{write_graph, GraphFormat};
+use tsql::ide::Workspace;
+use tsql::migrations::{MigrationDir, DOWN_FILE, UP_FILE};
+use tsql::naming::{NamingConvention, NamingStrategy};
+use tsql::printer::{print_tables, print_tables_with_naming};
+#[cfg(feature = "sqlite")]
+use tsql::sqlite::read_database;
+use tsql::syntax::{NodeKind, SyntaxTree};
+use tsql::types::{dependency_order, ConstraintKind, DataType};
+use tsql::{
+    parse_dir, parse_file, parse_file_with_naming, parse_file_with_sources, parse_str,
+    parse_str_with_naming, transform_drop_tables, transform_tables, DropOptions,
+};
 
 #[test]
+#[allow(clippy::result_filter_map, clippy::match_like_matches_macro)]
 fn e2e_parse_all_files() {
     let path = Path::new("./tests/files");
 
@@ -106,3 +124,1088 @@ fn parse_pks() {
     let table = all_table.unwrap();
     assert_eq!(table.primary_keys(), &vec!["start", "end"]);
 }
+
+#[test]
+fn parse_self_reference() {
+    let path = Path::new("./tests/files/self_reference.tsql");
+
+    let out = parse_file(path);
+    assert!(out.is_ok());
+
+    let tables = out.unwrap();
+
+    let employee_table = tables.get("Employee");
+    assert!(employee_table.is_some());
+    let table = employee_table.unwrap();
+    assert_eq!(table.primary_keys(), &vec!["id"]);
+
+    let manager_id = table.get_field("manager_id");
+    assert!(manager_id.is_some());
+    let manager_id = manager_id.unwrap();
+    assert_eq!(manager_id.datatype(), &DataType::Int);
+    assert!(manager_id.is_nullable());
+
+    assert!(!table.get_field("id").unwrap().is_nullable());
+}
+
+#[test]
+fn parse_naming() {
+    let path = Path::new("./tests/files/naming.tsql");
+
+    let out = parse_file(path);
+    assert!(out.is_ok());
+
+    let tables = out.unwrap();
+
+    let has_appointment = tables.get("has_appointment");
+    assert!(has_appointment.is_some());
+    let table = has_appointment.unwrap();
+    assert_eq!(table.primary_keys(), &vec!["humanId", "begins", "ends"]);
+
+    assert_eq!(
+        table.get_field("humanId").unwrap().foreign_key_table(),
+        Some("Human")
+    );
+    assert_eq!(
+        table.get_field("begins").unwrap().datatype(),
+        &DataType::DateTime
+    );
+
+    let mut constraints = table
+        .foreign_keys()
+        .iter()
+        .map(|item| item.constraint())
+        .collect::<Vec<_>>();
+    constraints.sort();
+    assert_eq!(
+        constraints,
+        vec!["has_appointment_human_fkey", "has_appointment_termin_fkey"]
+    );
+
+    // `constraint` is an alias of `foreign_key`
+    let tables = parse_str(
+        "@naming(constraint = \"fk_{table}_{target}\");
+        @primary_key(id) table Human { int id, };
+        @primary_key(plate) table Car { varchar(16) plate, @foreign_key() Human owner, };",
+    )
+    .unwrap();
+    assert_eq!(
+        tables.get("Car").unwrap().foreign_keys()[0].constraint(),
+        "fk_Car_Human"
+    );
+}
+
+#[test]
+fn parse_naming_overrides_default() {
+    let path = Path::new("./tests/files/fk.tsql");
+
+    let naming = NamingConvention {
+        column: NamingStrategy::TablePk,
+        ..Default::default()
+    };
+
+    // the primary key of `has_appointment` uses the snake case names
+    assert_eq!(
+        parse_file_with_naming(path, naming.clone())
+            .unwrap_err()
+            .to_string(),
+        "Error: Table \"has_appointment\" doesn't have a field with the name \"human_id\", encountered error while checking for primary key"
+    );
+
+    let naming = NamingConvention {
+        foreign_key: "{table}_{field}_fkey".to_string(),
+        ..naming
+    };
+    let tables = parse_str_with_naming(
+        "@primary_key(id) table Human { int id, };
+        @primary_key(plate) table Car { varchar(16) plate, @foreign_key() Human owner, };",
+        naming,
+    )
+    .unwrap();
+
+    let car = tables.get("Car").unwrap();
+    assert!(car.get_field("owner_id").is_none());
+    assert_eq!(
+        car.get_field("HumanId").unwrap().foreign_key_table(),
+        Some("Human")
+    );
+    assert_eq!(car.foreign_keys()[0].constraint(), "Car_owner_fkey");
+}
+
+#[test]
+fn parse_constraints() {
+    let path = Path::new("./tests/files/constraints.tsql");
+
+    let out = parse_file(path);
+    assert!(out.is_ok());
+
+    let tables = out.unwrap();
+
+    let table = tables.get("Person").unwrap();
+    let constraints = table
+        .constraints()
+        .iter()
+        .map(|item| (item.name(), item.kind().clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        constraints,
+        vec![
+            (
+                "pk_person",
+                ConstraintKind::PrimaryKey(vec!["id".to_string()])
+            ),
+            (
+                "uq_Person_email",
+                ConstraintKind::Unique(vec!["email".to_string()])
+            ),
+            (
+                "uq_person_identity",
+                ConstraintKind::Unique(vec!["name".to_string(), "birth".to_string()])
+            ),
+            (
+                "ck_Person_1",
+                ConstraintKind::Check("length(name) > 0".to_string())
+            ),
+            (
+                "ck_Person_2",
+                ConstraintKind::Check(
+                    "(birth > '1900-01-01') AND (birth < '2100-01-01')".to_string()
+                )
+            ),
+        ]
+    );
+
+    let table = tables.get("Phone").unwrap();
+    assert_eq!(table.foreign_keys()[0].constraint(), "fk_phone_owner");
+    assert_eq!(table.constraints()[0].name(), "pk_Phone");
+}
+
+#[test]
+fn duplicate_constraint_names() {
+    let content = "@primary_key(id) as pk_shared table Lorem {  int id, };@primary_key(id) as pk_shared table Ipsum {  int id, };";
+
+    let out = parse_str(content);
+    assert!(out.is_err());
+    assert!(out.unwrap_err().to_string().contains("pk_shared"));
+}
+
+#[test]
+fn constraint_name_length() {
+    let path = Path::new("./tests/files/constraints.tsql");
+
+    let naming = NamingConvention {
+        primary_key: format!("pk_{}_{{table}}", "x".repeat(60)),
+        ..Default::default()
+    };
+
+    // the length is only checked for a dialect, not while resolving
+    let tables = parse_file_with_naming(path, naming).unwrap();
+
+    assert!(Dialect::Generic.validate(&tables).is_ok());
+    assert!(Dialect::Postgres.validate(&tables).is_err());
+    assert!(Dialect::MySql.validate(&tables).is_err());
+}
+
+#[test]
+fn duplicate_table() {
+    let content = "@primary_key(id)
+table Human {
+    int id,
+};
+
+table Human {
+    int id,
+};
+";
+
+    let out = parse_str(content);
+    assert!(out.is_err());
+    assert_eq!(
+        out.unwrap_err().to_string(),
+        "Error: Table \"Human\" is defined more than once, at 2:7 and at 6:7"
+    );
+}
+
+#[test]
+fn duplicate_field() {
+    let content = "table Human {
+    int id,
+    varchar(32) name,
+    varchar(64) name,
+};
+";
+
+    let out = parse_str(content);
+    assert!(out.is_err());
+    assert_eq!(
+        out.unwrap_err().to_string(),
+        "Error: Table \"Human\" has more than one field with the name \"name\", defined at 3:17 and at 4:17"
+    );
+}
+
+#[test]
+fn duplicate_generated_field() {
+    let content = "@primary_key(id)
+table Human {
+    int id,
+};
+
+table Pet {
+    int human_id,
+    @foreign_key()
+    Human human,
+};
+";
+
+    let out = parse_str(content);
+    assert!(out.is_err());
+    assert_eq!(
+        out.unwrap_err().to_string(),
+        "Error: Table \"Pet\" has more than one field with the name \"human_id\", defined at 7:9 and at 9:11 (generated by the foreign key \"human\")"
+    );
+}
+
+#[test]
+fn parse_imports() {
+    let path = Path::new("./tests/files/imports/main.tsql");
+
+    let out = parse_file(path);
+    assert!(out.is_ok());
+
+    let tables = out.unwrap();
+    assert_eq!(
+        tables.keys().collect::<Vec<_>>(),
+        vec!["Human", "Pet", "Termin", "has_appointment"]
+    );
+
+    // `@naming` only applies to the file in which it's written
+    assert!(tables.get("Pet").unwrap().get_field("ownerId").is_some());
+    assert!(tables
+        .get("has_appointment")
+        .unwrap()
+        .get_field("human_id")
+        .is_some());
+
+    assert_eq!(
+        tables.get("Termin").unwrap().file(),
+        Some(Path::new("./tests/files/imports/calendar/termin.tsql"))
+    );
+
+    let (sourced, files) = parse_file_with_sources(path, NamingConvention::default()).unwrap();
+    assert!(diff(&tables, &sourced).is_empty());
+
+    let files = files
+        .iter()
+        .map(|file| {
+            file.strip_prefix(Path::new("./tests/files/imports").canonicalize().unwrap())
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        files,
+        vec![
+            Path::new("calendar/termin.tsql"),
+            Path::new("main.tsql"),
+            Path::new("people.tsql")
+        ]
+    );
+}
+
+#[test]
+fn parse_directory() {
+    let out = parse_dir("./tests/files/imports");
+    assert!(out.is_ok());
+
+    let tables = out.unwrap();
+    assert_eq!(
+        tables.keys().collect::<Vec<_>>(),
+        vec!["Human", "Pet", "Room", "Termin", "has_appointment"]
+    );
+}
+
+#[test]
+fn parse_directory_without_imports() {
+    let directory = std::env::temp_dir().join(format!("tsql_directory_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(
+        directory.join("human.tsql"),
+        "@primary_key(id) table Human { int id, };",
+    )
+    .unwrap();
+    std::fs::write(
+        directory.join("pet.tsql"),
+        "@primary_key(id) table Pet { int id, @foreign_key() Human owner, };",
+    )
+    .unwrap();
+
+    let out = parse_dir(&directory);
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    let tables = out.unwrap();
+    assert_eq!(tables.keys().collect::<Vec<_>>(), vec!["Human", "Pet"]);
+
+    let owner = tables.get("Pet").unwrap().get_field("owner_id").unwrap();
+    assert_eq!(owner.foreign_key_table(), Some("Human"));
+}
+
+#[test]
+fn import_errors() {
+    let out = parse_file("./tests/files/imports_errors/cycle_a.tsql");
+    assert!(out.is_err());
+    assert_eq!(
+        out.unwrap_err().to_string(),
+        "Error: Import cycle detected: ./tests/files/imports_errors/cycle_a.tsql -> ./tests/files/imports_errors/cycle_b.tsql -> ./tests/files/imports_errors/cycle_a.tsql"
+    );
+
+    let out = parse_file("./tests/files/imports_errors/not_imported.tsql");
+    assert!(out.is_err());
+    assert_eq!(
+        out.unwrap_err().to_string(),
+        "Error: Table \"Booking\" at ./tests/files/imports_errors/not_imported.tsql:4:7 references the table \"Room\", which isn't imported"
+    );
+
+    let out = parse_file("./tests/files/imports_errors/missing_file.tsql");
+    assert!(out.is_err());
+    assert!(out
+        .unwrap_err()
+        .to_string()
+        .starts_with("Error: Can't import \"does_not_exist.tsql\" at ./tests/files/imports_errors/missing_file.tsql:5:1"));
+
+    let out = parse_str("import \"lorem.tsql\";");
+    assert!(out.is_err());
+}
+
+#[test]
+fn unresolvable_foreign_keys() {
+    let out = parse_str("@primary_key(id) table Lorem {  int id,  @foreign_key() Ipsum ipsum, };");
+    assert!(out.is_err());
+    assert_eq!(
+        out.unwrap_err().to_string(),
+        "Error: Table \"Lorem\" at 1:24 references the table \"Ipsum\", which doesn't exist"
+    );
+
+    let out = parse_str(
+        "@primary_key(id) table Lorem {  int id,  @foreign_key() Ipsum ipsum, };@primary_key(id) table Ipsum {  int id,  @foreign_key() Lorem lorem, };",
+    );
+    assert!(out.is_err());
+    assert_eq!(
+        out.unwrap_err().to_string(),
+        "Error: The foreign keys of the tables [\"Ipsum\", \"Lorem\"] reference each other in a cycle"
+    );
+}
+
+#[test]
+fn foreign_key_declared_before_table() {
+    // `Apple` is ordered before `Basket` but references it
+    let content = "@primary_key(id)
+table Apple {
+    int id,
+    @foreign_key()
+    Basket basket,
+};
+
+@primary_key(id)
+table Basket {
+    int id,
+};
+";
+
+    let out = parse_str(content);
+    assert!(out.is_ok());
+}
+
+#[test]
+fn parse_schemas() {
+    let path = Path::new("./tests/files/schemas.tsql");
+
+    let out = parse_file(path);
+    assert!(out.is_ok());
+
+    let tables = out.unwrap();
+    assert_eq!(
+        tables.keys().collect::<Vec<_>>(),
+        vec![
+            "Account",
+            "auth.Account",
+            "billing.Account",
+            "billing.Invoice",
+            "billing.Payment"
+        ]
+    );
+
+    let account = tables.get("billing.Account").unwrap();
+    assert_eq!(account.schema(), Some("billing"));
+    assert_eq!(account.foreign_keys()[0].table(), "auth.Account");
+
+    // unqualified references inside of a schema prefer the tables of the same schema
+    let invoice = tables.get("billing.Invoice").unwrap();
+    assert_eq!(invoice.foreign_keys()[0].table(), "billing.Account");
+
+    let payment = tables.get("billing.Payment").unwrap();
+    assert_eq!(payment.schema(), Some("billing"));
+    assert!(payment.get_field("invoice_number").is_some());
+
+    let mut postgres = Vec::new();
+    transform_tables(&tables, Dialect::Postgres, &mut postgres).unwrap();
+    let postgres = String::from_utf8(postgres).unwrap();
+    assert!(postgres
+        .starts_with("CREATE SCHEMA IF NOT EXISTS auth;\nCREATE SCHEMA IF NOT EXISTS billing;\n"));
+    assert!(postgres.contains("REFERENCES auth.Account(id)"));
+    assert!(
+        postgres.find("CREATE TABLE auth.Account").unwrap()
+            < postgres.find("CREATE TABLE billing.Account").unwrap()
+    );
+
+    let mut sqlite = Vec::new();
+    transform_tables(&tables, Dialect::Sqlite, &mut sqlite).unwrap();
+    assert!(!String::from_utf8(sqlite).unwrap().contains("CREATE SCHEMA"));
+}
+
+#[test]
+fn diff_files() {
+    let old = parse_file(Path::new("./tests/files/diff/old.tsql")).unwrap();
+    let new = parse_file(Path::new("./tests/files/diff/new.tsql")).unwrap();
+
+    let migration = diff(&old, &new);
+    assert!(!migration.is_empty());
+
+    let mut buffer = Vec::new();
+    migration.transform(Dialect::Postgres, &mut buffer).unwrap();
+    let sql = String::from_utf8(buffer).unwrap();
+
+    let statements = [
+        "ALTER TABLE People DROP COLUMN age;",
+        "DROP TABLE Garage;",
+        "ALTER TABLE People RENAME TO Human;",
+        "ALTER TABLE Human RENAME COLUMN name TO full_name;",
+        "ALTER TABLE Human RENAME CONSTRAINT pk_People TO pk_Human;",
+        "CREATE TABLE Street (",
+        "ALTER TABLE Human ADD COLUMN birth date NOT NULL;",
+        "ALTER TABLE Human ALTER COLUMN full_name SET DATA TYPE varchar(128);",
+        "ALTER TABLE Car ADD CONSTRAINT uq_Car_plate UNIQUE (plate);",
+    ];
+
+    let mut last = 0;
+    for statement in statements {
+        let index = sql.find(statement);
+        assert!(index.is_some(), "{statement:?} is missing in {sql}");
+        assert!(
+            index.unwrap() >= last,
+            "{statement:?} is out of order in {sql}"
+        );
+        last = index.unwrap();
+    }
+
+    // the foreign key follows the renamed table
+    assert!(!sql.contains("fk_Car_owner"));
+
+    // a migration from a schema to itself is empty
+    assert!(diff(&new, &new).is_empty());
+}
+
+#[test]
+fn print_round_trip() {
+    let paths = [
+        "./tests/files/types.tsql",
+        "./tests/files/pk.tsql",
+        "./tests/files/fk.tsql",
+        "./tests/files/self_reference.tsql",
+        "./tests/files/naming.tsql",
+        "./tests/files/constraints.tsql",
+        "./tests/files/schemas.tsql",
+        "./tests/files/imports/main.tsql",
+    ];
+
+    for path in paths {
+        let tables = parse_file(path).unwrap();
+
+        let mut printed = Vec::new();
+        print_tables(&tables, &mut printed).unwrap();
+        let printed = String::from_utf8(printed).unwrap();
+
+        let reparsed = parse_str(&printed).unwrap();
+        assert!(diff(&tables, &reparsed).is_empty(), "{path}:\n{printed}");
+        // the printed file passes `tsql fmt --check`
+        assert_eq!(format_str(&printed).unwrap(), printed, "{path}");
+
+        let mut reprinted = Vec::new();
+        print_tables(&reparsed, &mut reprinted).unwrap();
+        assert_eq!(printed, String::from_utf8(reprinted).unwrap());
+    }
+}
+
+#[test]
+fn migration_dir() {
+    let path = std::env::temp_dir().join(format!("tsql_migrations_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    let migrations = MigrationDir::new(&path);
+
+    assert!(migrations.migrations().unwrap().is_empty());
+    assert!(migrations.snapshot().unwrap().is_empty());
+
+    let old = parse_file("./tests/files/diff/old.tsql").unwrap();
+    let new = parse_file("./tests/files/diff/new.tsql").unwrap();
+
+    let first = migrations
+        .create("init", &old, Dialect::Postgres)
+        .unwrap()
+        .unwrap();
+    assert!(first.ends_with("0001_init"));
+    let up = std::fs::read_to_string(first.join(UP_FILE)).unwrap();
+    assert!(up.contains("CREATE TABLE People ("));
+    let down = std::fs::read_to_string(first.join(DOWN_FILE)).unwrap();
+    assert!(down.contains("DROP TABLE People;"));
+
+    let second = migrations
+        .create("rename_people", &new, Dialect::Postgres)
+        .unwrap()
+        .unwrap();
+    assert!(second.ends_with("0002_rename_people"));
+    let up = std::fs::read_to_string(second.join(UP_FILE)).unwrap();
+    assert!(up.contains("ALTER TABLE People RENAME TO Human;"));
+    let down = std::fs::read_to_string(second.join(DOWN_FILE)).unwrap();
+    assert!(down.contains("ALTER TABLE Human RENAME TO People;"));
+
+    assert!(diff(&migrations.snapshot().unwrap(), &new).is_empty());
+    assert_eq!(
+        migrations
+            .create("nothing", &new, Dialect::Postgres)
+            .unwrap(),
+        None
+    );
+    assert!(migrations
+        .create("bad name", &old, Dialect::Postgres)
+        .is_err());
+    assert_eq!(migrations.migrations().unwrap(), vec![first, second]);
+
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn drop_tables() {
+    let tables = parse_file("./tests/files/schemas.tsql").unwrap();
+
+    let mut buffer = Vec::new();
+    transform_drop_tables(
+        &tables,
+        Dialect::Postgres,
+        DropOptions::default(),
+        &mut buffer,
+    )
+    .unwrap();
+    let sql = String::from_utf8(buffer).unwrap();
+
+    assert_eq!(
+        sql.lines()
+            .filter(|line| line.starts_with("DROP TABLE"))
+            .count(),
+        tables.len()
+    );
+    // a table is dropped before the tables it references
+    assert!(
+        sql.find("DROP TABLE billing.Payment;").unwrap()
+            < sql.find("DROP TABLE billing.Invoice;").unwrap()
+    );
+    assert!(
+        sql.find("DROP TABLE billing.Account;").unwrap()
+            < sql.find("DROP TABLE auth.Account;").unwrap()
+    );
+    assert!(!sql.contains("DROP SCHEMA"));
+
+    let options = DropOptions {
+        if_exists: true,
+        cascade: true,
+        drop_schemas: true,
+    };
+
+    let mut buffer = Vec::new();
+    transform_drop_tables(&tables, Dialect::Postgres, options, &mut buffer).unwrap();
+    let sql = String::from_utf8(buffer).unwrap();
+    assert!(sql.contains("DROP TABLE IF EXISTS billing.Payment CASCADE;"));
+    // the schemas are dropped after their tables, like they are created before them, and
+    // never with their other objects
+    assert!(sql.ends_with("DROP SCHEMA IF EXISTS auth;\nDROP SCHEMA IF EXISTS billing;\n"));
+
+    let mut buffer = Vec::new();
+    assert!(transform_drop_tables(&tables, Dialect::MySql, options, &mut buffer).is_err());
+    assert!(buffer.is_empty());
+
+    let mut buffer = Vec::new();
+    assert!(transform_drop_tables(&tables, Dialect::Sqlite, options, &mut buffer).is_err());
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn import_sql() {
+    let content = std::fs::read_to_string("./tests/files/ddl/schema.sql").unwrap();
+    let (tables, warnings) = parse_sql(&content).unwrap();
+
+    assert_eq!(tables.len(), 3);
+    assert_eq!(warnings.len(), 2, "{warnings:?}");
+
+    let mut printed = Vec::new();
+    print_tables_with_naming(&tables, &NamingConvention::default(), &mut printed).unwrap();
+    let printed = String::from_utf8(printed).unwrap();
+
+    // the foreign keys keep the place of their columns
+    assert!(
+        printed.contains(
+            "@primary_key(id)
+@unique(plate)
+table Car {
+    int            id,
+    @foreign_key()
+    People         owner,
+    char(8)        plate,
+    @nullable @default(0)
+    decimal(10, 2) price,
+    @default(CURRENT_TIMESTAMP)
+    datetime       registered,
+};"
+        ),
+        "{printed}"
+    );
+    assert!(
+        printed.contains("table Rental {\n    @foreign_key()\n    Car  car,"),
+        "{printed}"
+    );
+    assert!(printed.contains("@check(length(name) > 0) as ck_People_name"));
+    assert!(printed.contains("@check(nickname <> ')') as ck_People_nickname"));
+    assert_eq!(format_str(&printed).unwrap(), printed);
+
+    let reparsed = parse_str(&printed).unwrap();
+    assert!(diff(&tables, &reparsed).is_empty(), "{printed}");
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn read_sqlite_database() {
+    let paths = [
+        "./tests/files/pk.tsql",
+        "./tests/files/fk.tsql",
+        "./tests/files/self_reference.tsql",
+        "./tests/files/constraints.tsql",
+    ];
+
+    for (index, path) in paths.iter().enumerate() {
+        let tables = parse_file(path).unwrap();
+
+        let mut sql = Vec::new();
+        transform_tables(&tables, Dialect::Sqlite, &mut sql).unwrap();
+
+        let database =
+            std::env::temp_dir().join(format!("tsql_database_{}_{}.db", std::process::id(), index));
+        let _ = std::fs::remove_file(&database);
+        let connection = rusqlite::Connection::open(&database).unwrap();
+        connection
+            .execute_batch(&String::from_utf8(sql).unwrap())
+            .unwrap();
+        drop(connection);
+
+        let (read, _) = read_database(&database).unwrap();
+        let changes = diff(&read, &tables);
+        assert!(changes.is_empty(), "{path}: {:?}", changes.changes());
+
+        std::fs::remove_file(&database).unwrap();
+    }
+
+    // the sql import and the database agree on the same schema
+    let content = std::fs::read_to_string("./tests/files/ddl/schema.sql").unwrap();
+    let (imported, _) = parse_sql(&content).unwrap();
+
+    let database = std::env::temp_dir().join(format!("tsql_database_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&database);
+    let connection = rusqlite::Connection::open(&database).unwrap();
+    connection.execute_batch(&content).unwrap();
+    drop(connection);
+
+    let (read, warnings) = read_database(&database).unwrap();
+    let changes = diff(&read, &imported);
+    assert!(changes.is_empty(), "{:?}", changes.changes());
+    assert_eq!(warnings.len(), 2, "{warnings:?}");
+
+    std::fs::remove_file(&database).unwrap();
+
+    assert!(read_database("./tests/files/missing.db").is_err());
+}
+
+#[test]
+fn parse_comments() {
+    let tables = parse_file("./tests/files/comments.tsql").unwrap();
+
+    let book = tables.get("Book").unwrap();
+    assert_eq!(book.position().line(), 5);
+    assert_eq!(
+        book.constraints()[1].kind(),
+        &ConstraintKind::Check("title != '//not a comment'".to_string())
+    );
+    assert!(book.get_field("author_id").unwrap().is_nullable());
+}
+
+#[test]
+fn parse_large_schema() {
+    // every table and field has comments, looking them up must not scan the whole source
+    let mut content = String::new();
+    for table in 0..200 {
+        content.push_str(&format!(
+            "// table {table}\n@primary_key(id)\ntable Table{table} {{\n    int id,\n"
+        ));
+        for field in 0..100 {
+            content.push_str(&format!(
+                "    // field {field}\n    varchar(32) field_{field}, // of table {table}\n"
+            ));
+        }
+        content.push_str("};\n\n");
+    }
+
+    let start = Instant::now();
+    let tables = parse_str(&content).unwrap();
+    let elapsed = start.elapsed();
+
+    assert_eq!(tables.len(), 200);
+    let table = tables.get("Table199").unwrap();
+    assert_eq!(table.comment(), Some("table 199"));
+    assert_eq!(
+        table.get_field("field_99").unwrap().comment(),
+        Some("field 99\nof table 199")
+    );
+    assert!(elapsed < Duration::from_secs(5), "parsing took {elapsed:?}");
+}
+
+#[test]
+fn share_tables_across_threads() {
+    let tables = std::sync::Arc::new(parse_file("./tests/files/fk.tsql").unwrap());
+
+    let shared = tables.clone();
+    let handle = std::thread::spawn(move || {
+        let field = shared["has_appointment"].get_field("human_id").unwrap();
+        field.references(&shared).unwrap().0.name().to_string()
+    });
+
+    assert_eq!(handle.join().unwrap(), "Human");
+}
+
+#[test]
+fn read_model() {
+    let tables = parse_file("./tests/files/fk.tsql").unwrap();
+
+    let order = dependency_order(&tables)
+        .into_iter()
+        .map(|table| table.name())
+        .collect::<Vec<_>>();
+    assert_eq!(order, vec!["Human", "Termin", "has_appointment"]);
+
+    let appointment = tables.get("has_appointment").unwrap();
+    let fields = appointment
+        .fields()
+        .map(|field| field.name())
+        .collect::<Vec<_>>();
+    assert_eq!(fields, vec!["human_id", "termin_start", "termin_end"]);
+
+    let (table, field) = appointment
+        .get_field("termin_end")
+        .unwrap()
+        .references(&tables)
+        .unwrap();
+    assert_eq!((table.name(), field.name()), ("Termin", "end"));
+    assert!(tables["Human"]
+        .get_field("id")
+        .unwrap()
+        .references(&tables)
+        .is_none());
+
+    let foreign_key = appointment.get_foreign_key("termin").unwrap();
+    assert_eq!(foreign_key.columns(), &vec!["termin_start", "termin_end"]);
+
+    let content = std::fs::read_to_string("./tests/files/comments.tsql").unwrap();
+    let tables = parse_str(&content).unwrap();
+
+    let book = tables.get("Book").unwrap();
+    assert_eq!(book.comment(), None);
+    assert!(content[book.span().start..].starts_with("@primary_key(id)"));
+    assert!(content[..book.span().end].ends_with("};"));
+
+    let title = book.get_field("title").unwrap();
+    assert_eq!(title.comment(), Some("shown in the catalog"));
+    assert_eq!(
+        &content[title.span().start..title.span().end],
+        "varchar(128) title,"
+    );
+
+    let author = book.get_field("author_id").unwrap();
+    assert_eq!(
+        author.comment(),
+        Some("the author is optional for anonymous books")
+    );
+    assert!(content[author.span().start..].starts_with("@nullable @foreign_key()"));
+}
+
+/// Every `.tsql` file inside of the directory and its subdirectories.
+fn tsql_files(path: &Path, files: &mut Vec<std::path::PathBuf>) {
+    for entry in path.read_dir().unwrap().flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            tsql_files(&path, files);
+        } else if path.extension() == Some(OsStr::new("tsql")) {
+            files.push(path);
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_all_files() {
+    let mut files = Vec::new();
+    tsql_files(Path::new("./tests/files"), &mut files);
+
+    for path in files {
+        let content = std::fs::read_to_string(&path).unwrap();
+        if content.contains("import ") {
+            continue;
+        }
+
+        let tables = parse_str(&content).unwrap();
+        let json = serde_json::to_string(&tables).unwrap();
+        let deserialized = serde_json::from_str(&json).unwrap();
+
+        assert!(diff(&tables, &deserialized).is_empty(), "{path:?}");
+        assert_eq!(
+            serde_json::to_string(&deserialized).unwrap(),
+            json,
+            "{path:?}"
+        );
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_schema_all_files() {
+    let mut files = Vec::new();
+    tsql_files(Path::new("./tests/files"), &mut files);
+
+    for path in files {
+        let content = std::fs::read_to_string(&path).unwrap();
+        if content.contains("import ") {
+            continue;
+        }
+
+        let tables = parse_str(&content).unwrap();
+        let schema = tsql::json_schema::json_schema(&tables);
+
+        for (name, table) in &tables {
+            let properties = &schema["$defs"][name]["properties"];
+            for field in table.fields() {
+                assert!(properties[field.name()].is_object(), "{path:?}");
+            }
+        }
+    }
+
+    let content = std::fs::read_to_string("./tests/files/constraints.tsql").unwrap();
+    let schema = tsql::json_schema::json_schema(&parse_str(&content).unwrap());
+
+    let person = &schema["$defs"]["Person"];
+    assert_eq!(person["required"], serde_json::json!(["id"]));
+    assert_eq!(person["properties"]["name"]["minLength"], 1);
+    assert_eq!(person["properties"]["name"]["maxLength"], 64);
+    assert_eq!(person["properties"]["birth"]["format"], "date");
+
+    let phone = &schema["$defs"]["Phone"];
+    assert_eq!(
+        phone["required"],
+        serde_json::json!(["person_id", "number"])
+    );
+}
+
+#[test]
+fn codegen_all_files() {
+    let mut files = Vec::new();
+    tsql_files(Path::new("./tests/files"), &mut files);
+
+    for path in files {
+        let content = std::fs::read_to_string(&path).unwrap();
+        if content.contains("import ") {
+            continue;
+        }
+
+        let tables = parse_str(&content).unwrap();
+        let mut out = Vec::new();
+        tsql::codegen::rust::generate(&tables, &Default::default(), &mut out).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches("pub struct ").count(), tables.len(), "{path:?}");
+    }
+}
+
+#[test]
+fn codegen_typescript() {
+    let tables = parse_file("./tests/files/constraints.tsql").unwrap();
+
+    let options = TypeScriptOptions {
+        zod: true,
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    tsql::codegen::typescript::generate(&tables, &options, &mut out).unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "// Generated with tsql, manual changes will be overridden.
+
+import { z } from \"zod\";
+
+/** Row of the table `Person`. */
+export interface Person {
+    id: number;
+    name: string;
+    email: string;
+    birth: string;
+}
+
+export const PersonSchema = z.object({
+    id: z.number().int(),
+    name: z.string().max(64).min(1),
+    email: z.string().max(256),
+    birth: z.string().date(),
+});
+
+/** Row of the table `Phone`. */
+export interface Phone {
+    /** References `id` of {@link Person}. */
+    person_id: number;
+    number: string;
+}
+
+export const PhoneSchema = z.object({
+    person_id: z.number().int(),
+    number: z.string().max(32),
+});
+"
+    );
+}
+
+#[test]
+fn graph() {
+    let tables = parse_file("./tests/files/fk.tsql").unwrap();
+
+    let mut out = Vec::new();
+    write_graph(&tables, GraphFormat::Mermaid, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(
+        "    has_appointment {
+        int human_id PK, FK
+        datetime termin_start PK, FK
+        datetime termin_end PK, FK
+    }
+"
+    ));
+    assert!(out.ends_with(
+        "    Human ||--o{ has_appointment : \"human\"
+    Termin ||--o{ has_appointment : \"termin\"
+"
+    ));
+
+    let mut out = Vec::new();
+    write_graph(&tables, GraphFormat::Dot, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let edges = out
+        .lines()
+        .filter(|line| line.contains(" -> "))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        edges,
+        vec![
+            "    \"has_appointment\":\"human_id\" -> \"Human\":\"id\" [dir=both, arrowtail=crowodot, arrowhead=teetee, label=\"human\"];",
+            "    \"has_appointment\":\"termin_start\" -> \"Termin\":\"start\" [dir=both, arrowtail=crowodot, arrowhead=teetee, label=\"termin\"];",
+        ]
+    );
+}
+
+#[test]
+fn format_all_files() {
+    let mut files = Vec::new();
+    tsql_files(Path::new("./tests/files"), &mut files);
+
+    for path in files {
+        let content = std::fs::read_to_string(&path).unwrap();
+
+        let formatted = format_str(&content).unwrap();
+        assert_eq!(format_str(&formatted).unwrap(), formatted, "{path:?}");
+
+        // formatting doesn't change the meaning, files with imports can't be parsed alone
+        if !content.contains("import ") {
+            let tables = parse_str(&content).unwrap();
+            let reformatted = parse_str(&formatted).unwrap();
+            assert!(diff(&tables, &reformatted).is_empty(), "{path:?}");
+        }
+    }
+
+    // the fixture is already formatted
+    let content = std::fs::read_to_string("./tests/files/comments.tsql").unwrap();
+    assert_eq!(format_str(&content).unwrap(), content);
+}
+
+#[test]
+fn syntax_tree_is_lossless() {
+    let mut files = Vec::new();
+    tsql_files(Path::new("./tests/files"), &mut files);
+
+    for path in files {
+        let content = std::fs::read_to_string(&path).unwrap();
+        let tree = SyntaxTree::parse(&content).unwrap();
+
+        assert_eq!(tree.to_string(), content, "{path:?}");
+
+        for token in tree.tokens() {
+            assert_eq!(&content[token.span().start..token.span().end], token.text());
+        }
+    }
+
+    let content = std::fs::read_to_string("./tests/files/comments.tsql").unwrap();
+    let tree = SyntaxTree::parse(&content).unwrap();
+    let tables = tree
+        .statements()
+        .iter()
+        .filter(|statement| statement.kind() == NodeKind::Table)
+        .map(|statement| statement.name().unwrap().text())
+        .collect::<Vec<_>>();
+    assert_eq!(tables, vec!["Book", "Author"]);
+}
+
+#[test]
+fn ide_workspace() {
+    let mut workspace = Workspace::new(NamingConvention::default());
+    workspace.add_dir(Path::new("./tests/files")).unwrap();
+
+    // an unqualified reference inside of a schema block is part of the same schema
+    let path = Path::new("./tests/files/schemas.tsql");
+    let content = std::fs::read_to_string(path).unwrap();
+    let offset = content.find("Account account").unwrap();
+    let definition = workspace.definition(path, offset).unwrap();
+    let line = content[..definition.span.start].matches('\n').count() + 1;
+    assert_eq!(line, 11);
+
+    // the imports are renamed together with the table, but not the tables with the same name
+    // in unrelated files
+    let path = Path::new("./tests/files/imports/calendar/termin.tsql");
+    let content = std::fs::read_to_string(path).unwrap();
+    let offset = content.find("Termin").unwrap();
+    let edits = workspace.rename(path, offset, "Appointment").unwrap();
+    let mut files = edits
+        .iter()
+        .map(|edit| edit.path.file_name().unwrap().to_str().unwrap())
+        .collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(
+        files,
+        vec!["main.tsql", "main.tsql", "not_imported.tsql", "termin.tsql"]
+    );
+
+    assert!(workspace.diagnostics(path).is_empty());
+    assert_eq!(
+        workspace
+            .diagnostics(Path::new("./tests/files/imports_errors/cycle_a.tsql"))
+            .len(),
+        1
+    );
+}
@@ -0,0 +1,30 @@
+-- Dump of the car registry
+CREATE TABLE "People" (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(64) NOT NULL,
+    nickname VARCHAR(32),
+    verified BOOLEAN NOT NULL DEFAULT false,
+    CONSTRAINT ck_People_name CHECK (length(name) > 0),
+    CONSTRAINT ck_People_nickname CHECK (nickname <> ')')
+);
+
+CREATE TABLE "Car" (
+    id INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    plate CHAR(8) NOT NULL,
+    price NUMERIC(10, 2) DEFAULT 0,
+    registered TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT pk_Car PRIMARY KEY (id),
+    CONSTRAINT fk_Car_owner FOREIGN KEY (owner_id) REFERENCES "People" (id) ON DELETE CASCADE,
+    UNIQUE (plate)
+);
+
+/* rentals */
+CREATE TABLE "Rental" (
+    car_id INTEGER NOT NULL REFERENCES "Car",
+    "starts_at" DATE NOT NULL,
+    "ends_at" DATE,
+    PRIMARY KEY (car_id, "starts_at")
+);
+
+CREATE INDEX idx_Rental_ends_at ON "Rental" ("ends_at");
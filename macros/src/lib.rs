@@ -0,0 +1,238 @@
+//! Schemas which are checked at compile time, see [`include_schema!`].
+//!
+//! The macro is used as `tsql_macros::include_schema!`, `tsql` can't re-export it as
+//! `tsql::include_schema!`, as this crate depends on `tsql` to parse the schema.
+
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parenthesized, Ident, LitStr, Token};
+use tsql::codegen::rust::{generate, RustOptions};
+use tsql::dialect::Dialect;
+use tsql::naming::NamingConvention;
+use tsql::{parse_file_with_sources, transform_tables};
+
+/// Constants of the `ddl` module, every dialect gets one unless they are restricted with
+/// `dialects(...)`.
+const DIALECTS: [(&str, Dialect); 4] = [
+    ("GENERIC", Dialect::Generic),
+    ("POSTGRES", Dialect::Postgres),
+    ("MYSQL", Dialect::MySql),
+    ("SQLITE", Dialect::Sqlite),
+];
+
+/// Parses and validates a tsql file while compiling and expands to a struct for the rows of
+/// every table, see [`tsql::codegen::rust`], and a module `ddl` with the `CREATE TABLE`
+/// statements of every dialect as a constant:
+///
+/// ```ignore
+/// tsql_macros::include_schema!("schema.tsql", derive(serde, sqlx), dialects(postgres));
+///
+/// let human = Human { id: 1, name: "Ada".to_string() };
+/// sqlx::raw_sql(ddl::POSTGRES).execute(&pool).await?;
+/// ```
+///
+/// The path is relative to the directory of the `Cargo.toml` of the crate. An invalid schema
+/// fails the build with the error of the parser, which points at the path and names the
+/// `file:line:column` of the problem in its message, and the crate is compiled again when the file
+/// or a file it imports changes. `dialects(...)` restricts the constants to the given dialects,
+/// otherwise there is one for every dialect. The schema is validated for each of them, e.g. a
+/// constraint name which is too long for postgres fails the build.
+#[proc_macro]
+pub fn include_schema(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as Input);
+    let root = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+
+    match expand(input, &root) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// `"path.tsql"` with an optional `, derive(paths)` and `, dialects(names)` in any order
+struct Input {
+    path: LitStr,
+    derives: Vec<String>,
+    /// empty if every dialect is used
+    dialects: Vec<Dialect>,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+
+        let mut derives = Vec::new();
+        let mut dialects = Vec::new();
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let keyword = input.parse::<Ident>()?;
+
+            let content;
+            parenthesized!(content in input);
+
+            if keyword == "derive" {
+                derives = Punctuated::<syn::Path, Token![,]>::parse_terminated(&content)?
+                    .iter()
+                    .map(|path| quote!(#path).to_string().replace(' ', ""))
+                    .collect();
+            } else if keyword == "dialects" {
+                for name in Punctuated::<Ident, Token![,]>::parse_terminated(&content)? {
+                    let dialect = name
+                        .to_string()
+                        .parse::<Dialect>()
+                        .map_err(|err| syn::Error::new(name.span(), err))?;
+                    dialects.push(dialect);
+                }
+
+                if dialects.is_empty() {
+                    return Err(syn::Error::new(keyword.span(), "expected a dialect"));
+                }
+            } else {
+                return Err(syn::Error::new(
+                    keyword.span(),
+                    "expected `derive(...)` or `dialects(...)`",
+                ));
+            }
+        }
+
+        Ok(Input {
+            path,
+            derives,
+            dialects,
+        })
+    }
+}
+
+fn expand(input: Input, root: &Path) -> syn::Result<TokenStream> {
+    let span = input.path.span();
+    let error = |err: &dyn std::fmt::Display| syn::Error::new(span, err);
+
+    let path = root.join(input.path.value());
+    let (tables, files) =
+        parse_file_with_sources(&path, NamingConvention::default()).map_err(|err| error(&err))?;
+
+    let options = input
+        .derives
+        .iter()
+        .fold(RustOptions::default(), |options, derive| {
+            options.derive(derive)
+        });
+    let mut structs = Vec::new();
+    generate(&tables, &options, &mut structs).map_err(|err| error(&err))?;
+    let structs = String::from_utf8_lossy(&structs)
+        .parse::<TokenStream>()
+        .map_err(|err| error(&err))?;
+
+    let mut constants = Vec::with_capacity(DIALECTS.len());
+    for (name, dialect) in DIALECTS {
+        if !input.dialects.is_empty() && !input.dialects.contains(&dialect) {
+            continue;
+        }
+
+        dialect
+            .validate(&tables)
+            .map_err(|err| match input.dialects.is_empty() {
+                true => error(&format!(
+                    "{}, the dialects can be restricted with `dialects(...)`",
+                    err
+                )),
+                false => error(&err),
+            })?;
+
+        let mut sql = Vec::new();
+        transform_tables(&tables, dialect, &mut sql).map_err(|err| error(&err))?;
+        let sql = String::from_utf8_lossy(&sql);
+
+        let name = format_ident!("{}", name);
+        constants.push(quote! { pub const #name: &str = #sql; });
+    }
+
+    // `include_str!` tells cargo to compile the crate again if one of the files changes
+    let files = files.iter().map(|file| file.to_string_lossy().into_owned());
+
+    Ok(quote! {
+        #(const _: &str = include_str!(#files);)*
+
+        #structs
+
+        /// `CREATE TABLE` statements of the schema for every dialect.
+        pub mod ddl {
+            #(#constants)*
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    mod expand {
+        use std::path::Path;
+
+        use quote::quote;
+
+        use crate::{expand, Input};
+
+        fn expand_str(input: proc_macro2::TokenStream) -> syn::Result<String> {
+            let input = syn::parse2::<Input>(input)?;
+            expand(input, Path::new(env!("CARGO_MANIFEST_DIR"))).map(|tokens| tokens.to_string())
+        }
+
+        #[test]
+        fn just_works() {
+            let out = expand_str(quote!("tests/files/schema.tsql")).unwrap();
+            assert!(out.contains("pub struct Human"));
+            assert!(out.contains("pub const GENERIC : & str"));
+            assert!(out.contains("pub const SQLITE : & str"));
+            assert!(out.contains("include_str !"));
+
+            let out = expand_str(quote!("tests/files/schema.tsql", derive(serde, Hash))).unwrap();
+            assert!(out.contains("serde :: Serialize , serde :: Deserialize , Hash"));
+
+            let out = expand_str(quote!(
+                "tests/files/long_name.tsql",
+                dialects(generic, sqlite),
+                derive(Hash)
+            ))
+            .unwrap();
+            assert!(out.contains("pub const GENERIC : & str"));
+            assert!(out.contains("pub const SQLITE : & str"));
+            assert!(!out.contains("POSTGRES"));
+        }
+
+        #[test]
+        fn errors() {
+            let missing = expand_str(quote!("tests/files/missing.tsql")).unwrap_err();
+            assert!(missing.to_string().contains("missing.tsql"));
+
+            let invalid = expand_str(quote!("tests/files/invalid.tsql")).unwrap_err();
+            assert!(invalid.to_string().contains("\"Human\""));
+            assert!(invalid.to_string().contains("tests/files/invalid.tsql:2:7"));
+
+            let syntax = expand_str(quote!("tests/files/syntax_error.tsql")).unwrap_err();
+            assert!(syntax
+                .to_string()
+                .contains("tests/files/syntax_error.tsql:4:1"));
+
+            let long_name = expand_str(quote!("tests/files/long_name.tsql")).unwrap_err();
+            assert!(long_name
+                .to_string()
+                .contains("but Postgres only allows 63"));
+            assert!(long_name.to_string().contains("`dialects(...)`"));
+
+            let long_name =
+                expand_str(quote!("tests/files/long_name.tsql", dialects(mysql))).unwrap_err();
+            assert!(long_name.to_string().contains("but MySql only allows 64"));
+
+            let unknown =
+                expand_str(quote!("tests/files/schema.tsql", dialects(oracle))).unwrap_err();
+            assert_eq!(unknown.to_string(), "Error: unknown dialect \"oracle\"");
+
+            assert!(expand_str(quote!("tests/files/schema.tsql", dialects())).is_err());
+            assert!(expand_str(quote!("tests/files/schema.tsql", serde)).is_err());
+            assert!(expand_str(quote!(schema)).is_err());
+        }
+    }
+}
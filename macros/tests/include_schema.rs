@@ -0,0 +1,20 @@
+tsql_macros::include_schema!("tests/files/schema.tsql");
+
+#[test]
+fn include_schema() {
+    let human = Human {
+        id: 1,
+        name: "Ada".to_string(),
+        height: None,
+    };
+    let car = Car {
+        plate: "W-1234".to_string(),
+        owner_id: Some(human.id),
+        electric: true,
+    };
+    assert_eq!(car.clone(), car);
+
+    for sql in [ddl::GENERIC, ddl::POSTGRES, ddl::MYSQL, ddl::SQLITE] {
+        assert!(sql.find("CREATE TABLE Human").unwrap() < sql.find("CREATE TABLE Car").unwrap());
+    }
+}